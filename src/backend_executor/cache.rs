@@ -0,0 +1,439 @@
+//! Persistent response cache for backend requests
+
+use super::types::{BackendError, BackendExecutor, BackendRequest, BackendResponse, CacheMode};
+use crate::config::BackendConfig;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rusqlite::{Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Summary of cache contents
+#[derive(Debug, Clone, Default)]
+pub struct CacheStats {
+    /// Total cached responses
+    pub entries: u64,
+    /// Entries past their time-to-live
+    pub expired: u64,
+    /// Total number of cache hits served
+    pub hits: u64,
+    /// Cached responses per backend
+    pub by_backend: Vec<(String, u64)>,
+    /// Size of the database file in bytes
+    pub size_bytes: u64,
+}
+
+/// SQLite-backed store of backend responses
+pub struct ResponseCache {
+    conn: Mutex<Connection>,
+    path: PathBuf,
+}
+
+impl ResponseCache {
+    /// Open or create a response cache database
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open response cache at {}", path.display()))?;
+
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS responses (
+                key TEXT PRIMARY KEY,
+                backend TEXT NOT NULL,
+                model TEXT,
+                response TEXT NOT NULL,
+                hits INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                expires_at TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_responses_backend ON responses(backend);
+            CREATE INDEX IF NOT EXISTS idx_responses_expires ON responses(expires_at);
+            "#,
+        )?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Get the default cache path (~/.config/llm-mux/cache/responses.db)
+    pub fn default_path() -> Result<PathBuf> {
+        let config_dir = dirs::config_dir().context("Could not determine config directory")?;
+
+        let cache_dir = config_dir.join("llm-mux").join("cache");
+        std::fs::create_dir_all(&cache_dir).with_context(|| {
+            format!(
+                "Failed to create cache directory at {}",
+                cache_dir.display()
+            )
+        })?;
+
+        Ok(cache_dir.join("responses.db"))
+    }
+
+    /// Open the cache at its default location
+    pub fn open_default() -> Result<Self> {
+        Self::open(&Self::default_path()?)
+    }
+
+    /// Path of the underlying database
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Look up a cached response, ignoring expired entries
+    pub fn get(&self, key: &str) -> Result<Option<BackendResponse>> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let conn = self.conn.lock().expect("cache lock poisoned");
+
+        let response: Option<String> = conn
+            .query_row(
+                "SELECT response FROM responses
+                 WHERE key = ?1 AND (expires_at IS NULL OR expires_at > ?2)",
+                (key, &now),
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let Some(response) = response else {
+            return Ok(None);
+        };
+
+        conn.execute("UPDATE responses SET hits = hits + 1 WHERE key = ?1", [key])?;
+
+        let response: BackendResponse =
+            serde_json::from_str(&response).context("Failed to decode cached response")?;
+        Ok(Some(response))
+    }
+
+    /// Store a response, replacing any previous entry for the key
+    pub fn put(&self, key: &str, response: &BackendResponse, ttl: Option<Duration>) -> Result<()> {
+        let now = chrono::Utc::now();
+        let expires_at = ttl
+            .map(|ttl| -> Result<String> {
+                let expires_at = chrono::Duration::from_std(ttl)
+                    .ok()
+                    .and_then(|ttl| now.checked_add_signed(ttl))
+                    .context("cache ttl out of range")?;
+                Ok(expires_at.to_rfc3339())
+            })
+            .transpose()?;
+        let encoded = serde_json::to_string(response)?;
+
+        let conn = self.conn.lock().expect("cache lock poisoned");
+        conn.execute(
+            "INSERT INTO responses (key, backend, model, response, hits, created_at, expires_at)
+             VALUES (?1, ?2, ?3, ?4, 0, ?5, ?6)
+             ON CONFLICT(key) DO UPDATE SET
+                response = excluded.response,
+                hits = 0,
+                created_at = excluded.created_at,
+                expires_at = excluded.expires_at",
+            (
+                key,
+                &response.backend,
+                &response.model,
+                &encoded,
+                now.to_rfc3339(),
+                &expires_at,
+            ),
+        )?;
+
+        Ok(())
+    }
+
+    /// Summarize cache contents
+    pub fn stats(&self) -> Result<CacheStats> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let conn = self.conn.lock().expect("cache lock poisoned");
+
+        let (entries, hits): (u64, u64) = conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(hits), 0) FROM responses",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        let expired: u64 = conn.query_row(
+            "SELECT COUNT(*) FROM responses WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+            [&now],
+            |row| row.get(0),
+        )?;
+
+        let mut stmt = conn.prepare(
+            "SELECT backend, COUNT(*) FROM responses GROUP BY backend ORDER BY COUNT(*) DESC",
+        )?;
+        let by_backend = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let size_bytes = std::fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+
+        Ok(CacheStats {
+            entries,
+            expired,
+            hits,
+            by_backend,
+            size_bytes,
+        })
+    }
+
+    /// Remove cached responses, returning how many were deleted
+    pub fn clear(&self, expired_only: bool) -> Result<usize> {
+        let conn = self.conn.lock().expect("cache lock poisoned");
+
+        let removed = if expired_only {
+            let now = chrono::Utc::now().to_rfc3339();
+            conn.execute(
+                "DELETE FROM responses WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+                [&now],
+            )?
+        } else {
+            conn.execute("DELETE FROM responses", [])?
+        };
+
+        Ok(removed)
+    }
+}
+
+/// Compute the cache key for a request against a backend
+///
+/// The key covers everything that influences the response: backend name,
+/// model, generation parameters (command and arguments), system prompt,
+/// earlier conversation turns and the rendered prompt.
+pub fn cache_key(backend: &str, config: &BackendConfig, request: &BackendRequest) -> String {
+    // SHA-256 is stable across toolchains, unlike std's hashers, so keys survive upgrades
+    let mut fields = serde_json::json!([
+        backend,
+        config.model,
+        config.command,
+        config.args,
+        request.system_prompt,
+        request.prompt,
+    ]);
+    if !request.messages.is_empty()
+        && let Some(fields) = fields.as_array_mut()
+    {
+        fields.push(serde_json::json!(request.messages));
+        fields.push(serde_json::json!(config.transcript_format));
+    }
+
+    Sha256::digest(fields.to_string().as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Wrapper that serves repeated requests from the response cache
+pub struct CachedExecutor<T: BackendExecutor> {
+    inner: T,
+    cache: Arc<ResponseCache>,
+    config: BackendConfig,
+}
+
+impl<T: BackendExecutor> CachedExecutor<T> {
+    /// Create a new caching executor
+    pub fn new(inner: T, cache: Arc<ResponseCache>, config: BackendConfig) -> Self {
+        Self {
+            inner,
+            cache,
+            config,
+        }
+    }
+}
+
+#[async_trait]
+impl<T: BackendExecutor + 'static> BackendExecutor for CachedExecutor<T> {
+    async fn execute(&self, request: &BackendRequest) -> Result<BackendResponse, BackendError> {
+        // Tool results depend on local state, so tool-using requests are never cached
+        let (CacheMode::Enabled { ttl }, None) = (request.cache, &request.tools) else {
+            return self.inner.execute(request).await;
+        };
+
+        let key = cache_key(self.inner.name(), &self.config, request);
+
+        match self.cache.get(&key) {
+            Ok(Some(mut response)) => {
+                tracing::debug!(backend = %self.inner.name(), key = %key, "Cache hit");
+                response.cached = true;
+                return Ok(response);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!(backend = %self.inner.name(), error = %e, "Cache lookup failed");
+            }
+        }
+
+        let response = self.inner.execute(request).await?;
+
+        if let Err(e) = self.cache.put(&key, &response, ttl) {
+            tracing::warn!(backend = %self.inner.name(), error = %e, "Failed to cache response");
+        }
+
+        Ok(response)
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn is_available(&self) -> bool {
+        self.inner.is_available().await
+    }
+}
+
+/// Wrap an executor with the response cache
+pub fn with_cache<T: BackendExecutor + 'static>(
+    backend: T,
+    cache: Arc<ResponseCache>,
+    config: &BackendConfig,
+) -> CachedExecutor<T> {
+    CachedExecutor::new(backend, cache, config.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend_executor::types::Message;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use tempfile::TempDir;
+
+    /// Mock backend that counts how often it is called
+    struct CountingBackend {
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl BackendExecutor for CountingBackend {
+        async fn execute(&self, request: &BackendRequest) -> Result<BackendResponse, BackendError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(BackendResponse::new(
+                format!("answer to {}", request.prompt),
+                "mock".into(),
+                Duration::from_millis(10),
+            ))
+        }
+
+        fn name(&self) -> &str {
+            "mock"
+        }
+    }
+
+    fn open_cache(dir: &TempDir) -> Arc<ResponseCache> {
+        Arc::new(ResponseCache::open(&dir.path().join("responses.db")).unwrap())
+    }
+
+    #[test]
+    fn test_put_and_get() {
+        let dir = TempDir::new().unwrap();
+        let cache = open_cache(&dir);
+
+        let response =
+            BackendResponse::new("cached".into(), "claude".into(), Duration::from_secs(1));
+        cache.put("key", &response, None).unwrap();
+
+        let hit = cache.get("key").unwrap().unwrap();
+        assert_eq!(hit.text, "cached");
+        assert!(cache.get("missing").unwrap().is_none());
+
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.hits, 1);
+    }
+
+    #[test]
+    fn test_expired_entries_ignored() {
+        let dir = TempDir::new().unwrap();
+        let cache = open_cache(&dir);
+
+        let response = BackendResponse::new("old".into(), "claude".into(), Duration::ZERO);
+        cache.put("key", &response, Some(Duration::ZERO)).unwrap();
+
+        assert!(cache.get("key").unwrap().is_none());
+        assert_eq!(cache.stats().unwrap().expired, 1);
+        assert_eq!(cache.clear(true).unwrap(), 1);
+        assert_eq!(cache.stats().unwrap().entries, 0);
+    }
+
+    #[test]
+    fn test_cache_key_covers_prompt_and_system() {
+        let config = BackendConfig {
+            command: "claude".into(),
+            ..Default::default()
+        };
+        let request = BackendRequest::new("prompt");
+
+        let base = cache_key("claude", &config, &request);
+        assert_eq!(base, cache_key("claude", &config, &request));
+        assert_ne!(base, cache_key("codex", &config, &request));
+        assert_ne!(
+            base,
+            cache_key("claude", &config, &BackendRequest::new("other"))
+        );
+        assert_ne!(
+            base,
+            cache_key(
+                "claude",
+                &config,
+                &BackendRequest::new("prompt").with_system_prompt("be terse")
+            )
+        );
+        assert_ne!(
+            base,
+            cache_key(
+                "claude",
+                &config,
+                &BackendRequest::new("prompt").with_messages(vec![Message::user("earlier")])
+            )
+        );
+    }
+
+    #[test]
+    fn test_cache_key_is_stable() {
+        let config = BackendConfig {
+            command: "claude".into(),
+            ..Default::default()
+        };
+        // Keys are persisted, so they must not change between builds
+        assert_eq!(
+            cache_key("claude", &config, &BackendRequest::new("prompt")),
+            "befd1679eb5fd1e57030bb82e30fc558a43432ef02daa9f02f7796f679b9a0d0"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cached_executor_serves_repeat_requests() {
+        let dir = TempDir::new().unwrap();
+        let backend = CountingBackend {
+            calls: AtomicU32::new(0),
+        };
+        let executor = with_cache(backend, open_cache(&dir), &BackendConfig::default());
+
+        let request = BackendRequest::new("hello").with_cache(None);
+        let first = executor.execute(&request).await.unwrap();
+        let second = executor.execute(&request).await.unwrap();
+
+        assert!(!first.cached);
+        assert!(second.cached);
+        assert_eq!(second.text, "answer to hello");
+        assert_eq!(executor.inner.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cached_executor_bypassed_when_disabled() {
+        let dir = TempDir::new().unwrap();
+        let backend = CountingBackend {
+            calls: AtomicU32::new(0),
+        };
+        let executor = with_cache(backend, open_cache(&dir), &BackendConfig::default());
+
+        let request = BackendRequest::new("hello");
+        executor.execute(&request).await.unwrap();
+        executor.execute(&request).await.unwrap();
+
+        assert_eq!(executor.inner.calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,575 @@
+#![allow(dead_code)]
+
+//! Per-backend circuit breaker that skips backends after repeated failures
+
+use super::types::{BackendError, BackendExecutor, BackendRequest, BackendResponse};
+use crate::config::BackendConfig;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// Breakers shared by every executor in the process, keyed by backend name
+static BREAKERS: OnceLock<Mutex<HashMap<String, Arc<CircuitBreaker>>>> = OnceLock::new();
+
+/// Circuit state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Requests flow normally
+    Closed,
+    /// Requests are rejected until the cooldown elapses
+    Open { retry_in: Duration },
+    /// Cooldown elapsed; a single probe request is allowed through
+    HalfOpen,
+}
+
+impl fmt::Display for BreakerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BreakerState::Closed => write!(f, "closed"),
+            BreakerState::Open { retry_in } => write!(f, "open, retry in {}s", retry_in.as_secs()),
+            BreakerState::HalfOpen => write!(f, "half-open"),
+        }
+    }
+}
+
+/// Breaker state that survives across runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Health {
+    consecutive_failures: u32,
+    opened_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    probing: bool,
+}
+
+/// Circuit breaker for a single backend
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    name: String,
+    threshold: u32,
+    cooldown: Duration,
+    /// Whether persistence was requested, even if no state path was available
+    persist_requested: bool,
+    persist_path: Option<PathBuf>,
+    health: Mutex<Health>,
+}
+
+impl CircuitBreaker {
+    /// Create a breaker that opens after `threshold` consecutive failures
+    pub fn new(name: impl Into<String>, threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            name: name.into(),
+            threshold: threshold.max(1),
+            cooldown,
+            persist_requested: false,
+            persist_path: None,
+            health: Mutex::new(Health::default()),
+        }
+    }
+
+    /// Persist state to `path`, restoring any state already saved there
+    pub fn with_persistence(mut self, path: PathBuf) -> Self {
+        if let Some(health) = load_state(&path).remove(&self.name) {
+            self.health = Mutex::new(health);
+        }
+        self.persist_requested = true;
+        self.persist_path = Some(path);
+        self
+    }
+
+    /// Get the process-wide breaker for a backend, if one is configured
+    ///
+    /// The breaker is rebuilt when the backend's breaker settings change.
+    pub fn shared(name: &str, config: &BackendConfig) -> Option<Arc<Self>> {
+        let threshold = config.breaker_threshold?;
+        let cooldown = Duration::from_secs(config.breaker_cooldown);
+
+        let mut breakers = BREAKERS
+            .get_or_init(Default::default)
+            .lock()
+            .expect("circuit breaker registry poisoned");
+
+        if let Some(existing) = breakers.get(name)
+            && existing.threshold == threshold.max(1)
+            && existing.cooldown == cooldown
+            && existing.persist_requested == config.breaker_persist
+        {
+            return Some(existing.clone());
+        }
+
+        let mut breaker = Self::new(name, threshold, cooldown);
+        breaker.persist_requested = config.breaker_persist;
+        if config.breaker_persist {
+            match default_state_path() {
+                Some(path) => breaker = breaker.with_persistence(path),
+                None => tracing::warn!(backend = name, "Cannot persist circuit breaker state"),
+            }
+        }
+
+        let breaker = Arc::new(breaker);
+        breakers.insert(name.to_string(), breaker.clone());
+        Some(breaker)
+    }
+
+    /// Current state of the circuit
+    pub fn state(&self) -> BreakerState {
+        let health = self.health.lock().expect("circuit breaker poisoned");
+        self.state_of(&health)
+    }
+
+    /// Number of consecutive failures recorded
+    pub fn consecutive_failures(&self) -> u32 {
+        self.health
+            .lock()
+            .expect("circuit breaker poisoned")
+            .consecutive_failures
+    }
+
+    fn state_of(&self, health: &Health) -> BreakerState {
+        let Some(opened_at) = health.opened_at else {
+            return BreakerState::Closed;
+        };
+
+        let elapsed = (Utc::now() - opened_at).to_std().unwrap_or_default();
+        if elapsed >= self.cooldown {
+            BreakerState::HalfOpen
+        } else {
+            BreakerState::Open {
+                retry_in: self.cooldown - elapsed,
+            }
+        }
+    }
+
+    /// Check whether a request may proceed, claiming the probe slot when half-open
+    pub fn try_acquire(&self) -> Result<Permit<'_>, BreakerState> {
+        let mut health = self.health.lock().expect("circuit breaker poisoned");
+        match self.state_of(&health) {
+            BreakerState::Closed => Ok(Permit {
+                breaker: self,
+                probe: false,
+            }),
+            BreakerState::HalfOpen if !health.probing => {
+                health.probing = true;
+                tracing::info!(backend = %self.name, "Circuit half-open, probing backend");
+                Ok(Permit {
+                    breaker: self,
+                    probe: true,
+                })
+            }
+            state => Err(state),
+        }
+    }
+
+    /// Record a successful request, closing the circuit
+    pub fn record_success(&self) {
+        let mut health = self.health.lock().expect("circuit breaker poisoned");
+        let was_open = health.opened_at.is_some();
+        let changed = was_open || health.consecutive_failures > 0;
+
+        *health = Health::default();
+
+        if was_open {
+            tracing::info!(backend = %self.name, "Circuit closed, backend recovered");
+        }
+        if changed {
+            self.persist(&health);
+        }
+    }
+
+    /// Record a failed request, opening the circuit once the threshold is hit
+    pub fn record_failure(&self) {
+        let mut health = self.health.lock().expect("circuit breaker poisoned");
+        health.consecutive_failures += 1;
+
+        // A failed probe reopens the circuit for another cooldown
+        if health.probing || health.consecutive_failures >= self.threshold {
+            if health.opened_at.is_none() || health.probing {
+                tracing::warn!(
+                    backend = %self.name,
+                    failures = health.consecutive_failures,
+                    cooldown_secs = self.cooldown.as_secs(),
+                    "Circuit opened"
+                );
+            }
+            health.opened_at = Some(Utc::now());
+            health.probing = false;
+        }
+
+        self.persist(&health);
+    }
+
+    fn persist(&self, health: &Health) {
+        let Some(path) = &self.persist_path else {
+            return;
+        };
+
+        let mut state = load_state(path);
+        state.insert(self.name.clone(), health.clone());
+
+        let result = serde_json::to_string_pretty(&state)
+            .map_err(std::io::Error::other)
+            .and_then(|json| std::fs::write(path, json));
+        if let Err(e) = result {
+            tracing::warn!(backend = %self.name, error = %e, "Failed to save circuit breaker state");
+        }
+    }
+}
+
+/// Admission through a breaker
+///
+/// A probe dropped before its outcome is recorded (e.g. a cancelled race loser)
+/// frees the probe slot, so the next request can probe instead.
+#[must_use]
+pub struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+}
+
+impl Permit<'_> {
+    /// The request finished; its outcome is recorded separately
+    pub fn finish(mut self) {
+        self.probe = false;
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.probe
+            && let Ok(mut health) = self.breaker.health.lock()
+        {
+            health.probing = false;
+        }
+    }
+}
+
+/// Default location of persisted breaker state (~/.config/llm-mux/cache/health.json)
+pub fn default_state_path() -> Option<PathBuf> {
+    let dir = dirs::config_dir()?.join("llm-mux").join("cache");
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir.join("health.json"))
+}
+
+fn load_state(path: &Path) -> HashMap<String, Health> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Whether an error says something about the backend's health
+///
+/// Only timeouts, network errors and unavailability count. Rate limits mean the
+/// backend is up, and auth or request errors are the caller's problem, so those
+/// leave the circuit as it is.
+fn counts_as_failure(error: &BackendError) -> bool {
+    matches!(
+        error,
+        BackendError::Timeout { .. }
+            | BackendError::Network { .. }
+            | BackendError::Unavailable { .. }
+    )
+}
+
+/// Wrapper that rejects requests while a backend's circuit is open
+pub struct CircuitBreakerExecutor<T: BackendExecutor> {
+    inner: T,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl<T: BackendExecutor> CircuitBreakerExecutor<T> {
+    /// Create a new circuit breaker executor
+    pub fn new(inner: T, breaker: Arc<CircuitBreaker>) -> Self {
+        Self { inner, breaker }
+    }
+}
+
+#[async_trait]
+impl<T: BackendExecutor + 'static> BackendExecutor for CircuitBreakerExecutor<T> {
+    async fn execute(&self, request: &BackendRequest) -> Result<BackendResponse, BackendError> {
+        let permit = self
+            .breaker
+            .try_acquire()
+            .map_err(|state| BackendError::Unavailable {
+                message: format!("circuit {}", state),
+            })?;
+
+        let result = self.inner.execute(request).await;
+        match &result {
+            Ok(_) => {
+                permit.finish();
+                self.breaker.record_success();
+            }
+            Err(e) if counts_as_failure(e) => {
+                permit.finish();
+                self.breaker.record_failure();
+            }
+            // Neutral outcome: dropping the permit frees a probe slot without
+            // changing the circuit's state
+            Err(_) => drop(permit),
+        }
+        result
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn is_available(&self) -> bool {
+        !matches!(self.breaker.state(), BreakerState::Open { .. })
+            && self.inner.is_available().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+    use tempfile::TempDir;
+
+    /// Mock backend whose health can be toggled
+    struct FlakyBackend {
+        healthy: AtomicBool,
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl BackendExecutor for FlakyBackend {
+        async fn execute(
+            &self,
+            _request: &BackendRequest,
+        ) -> Result<BackendResponse, BackendError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.healthy.load(Ordering::SeqCst) {
+                Ok(BackendResponse::new(
+                    "ok".into(),
+                    "flaky".into(),
+                    Duration::ZERO,
+                ))
+            } else {
+                Err(BackendError::network("connection refused"))
+            }
+        }
+
+        fn name(&self) -> &str {
+            "flaky"
+        }
+    }
+
+    /// Mock backend that always fails with the same error
+    struct FailingBackend {
+        error: BackendError,
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl BackendExecutor for FailingBackend {
+        async fn execute(
+            &self,
+            _request: &BackendRequest,
+        ) -> Result<BackendResponse, BackendError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Err(self.error.clone())
+        }
+
+        fn name(&self) -> &str {
+            "failing"
+        }
+    }
+
+    /// Mock backend that never answers
+    struct HangingBackend;
+
+    #[async_trait]
+    impl BackendExecutor for HangingBackend {
+        async fn execute(
+            &self,
+            _request: &BackendRequest,
+        ) -> Result<BackendResponse, BackendError> {
+            std::future::pending().await
+        }
+
+        fn name(&self) -> &str {
+            "hanging"
+        }
+    }
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let breaker = CircuitBreaker::new("test", 2, Duration::from_secs(60));
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+
+        breaker.record_failure();
+        assert!(matches!(breaker.state(), BreakerState::Open { .. }));
+        assert!(breaker.try_acquire().is_err());
+
+        breaker.record_success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert_eq!(breaker.consecutive_failures(), 0);
+    }
+
+    #[test]
+    fn test_half_open_allows_single_probe() {
+        let breaker = CircuitBreaker::new("test", 1, Duration::ZERO);
+        breaker.record_failure();
+
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        let probe = breaker.try_acquire().unwrap();
+        assert!(breaker.try_acquire().is_err());
+
+        // Failed probe reopens the circuit
+        breaker.record_failure();
+        probe.finish();
+        assert!(breaker.try_acquire().is_ok());
+    }
+
+    #[test]
+    fn test_persisted_state_restored() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("health.json");
+
+        let breaker = CircuitBreaker::new("local", 1, Duration::from_secs(300))
+            .with_persistence(path.clone());
+        breaker.record_failure();
+
+        let restored =
+            CircuitBreaker::new("local", 1, Duration::from_secs(300)).with_persistence(path);
+        assert!(matches!(restored.state(), BreakerState::Open { .. }));
+        assert_eq!(restored.consecutive_failures(), 1);
+    }
+
+    #[test]
+    fn test_shared_breaker_rebuilt_on_config_change() {
+        let mut config = BackendConfig {
+            breaker_threshold: Some(3),
+            breaker_cooldown: 60,
+            ..Default::default()
+        };
+
+        let a = CircuitBreaker::shared("test-shared-breaker", &config).unwrap();
+        let b = CircuitBreaker::shared("test-shared-breaker", &config).unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+
+        config.breaker_threshold = Some(1);
+        let c = CircuitBreaker::shared("test-shared-breaker", &config).unwrap();
+        assert!(!Arc::ptr_eq(&a, &c));
+        assert_eq!(c.threshold, 1);
+
+        config.breaker_cooldown = 5;
+        let d = CircuitBreaker::shared("test-shared-breaker", &config).unwrap();
+        assert!(!Arc::ptr_eq(&c, &d));
+        assert_eq!(d.cooldown, Duration::from_secs(5));
+
+        assert!(CircuitBreaker::shared("test-no-breaker", &BackendConfig::default()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_executor_skips_open_backend() {
+        let breaker = Arc::new(CircuitBreaker::new("flaky", 2, Duration::from_secs(60)));
+        let executor = CircuitBreakerExecutor::new(
+            FlakyBackend {
+                healthy: AtomicBool::new(false),
+                calls: AtomicU32::new(0),
+            },
+            breaker,
+        );
+        let request = BackendRequest::new("test");
+
+        assert!(executor.execute(&request).await.is_err());
+        assert!(executor.execute(&request).await.is_err());
+
+        let result = executor.execute(&request).await;
+        assert!(matches!(result, Err(BackendError::Unavailable { .. })));
+        assert_eq!(executor.inner.calls.load(Ordering::SeqCst), 2);
+        assert!(!executor.is_available().await);
+    }
+
+    #[tokio::test]
+    async fn test_executor_recovers_after_probe() {
+        let breaker = Arc::new(CircuitBreaker::new("flaky", 1, Duration::ZERO));
+        let executor = CircuitBreakerExecutor::new(
+            FlakyBackend {
+                healthy: AtomicBool::new(false),
+                calls: AtomicU32::new(0),
+            },
+            breaker.clone(),
+        );
+        let request = BackendRequest::new("test");
+
+        assert!(executor.execute(&request).await.is_err());
+        executor.inner.healthy.store(true, Ordering::SeqCst);
+
+        assert!(executor.execute(&request).await.is_ok());
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_auth_error_does_not_open_circuit() {
+        let breaker = Arc::new(CircuitBreaker::new("failing", 1, Duration::from_secs(60)));
+        let executor = CircuitBreakerExecutor::new(
+            FailingBackend {
+                error: BackendError::auth("invalid api key"),
+                calls: AtomicU32::new(0),
+            },
+            breaker.clone(),
+        );
+        let request = BackendRequest::new("test");
+
+        for _ in 0..3 {
+            assert!(matches!(
+                executor.execute(&request).await,
+                Err(BackendError::Auth { .. })
+            ));
+        }
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert_eq!(breaker.consecutive_failures(), 0);
+        assert_eq!(executor.inner.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_probe_keeps_circuit_half_open() {
+        let breaker = Arc::new(CircuitBreaker::new("failing", 1, Duration::ZERO));
+        breaker.record_failure();
+        let executor = CircuitBreakerExecutor::new(
+            FailingBackend {
+                error: BackendError::rate_limit(None),
+                calls: AtomicU32::new(0),
+            },
+            breaker.clone(),
+        );
+
+        let result = executor.execute(&BackendRequest::new("test")).await;
+        assert!(matches!(result, Err(BackendError::RateLimit { .. })));
+
+        // Neither closed nor reopened, and the probe slot is free again
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        assert_eq!(breaker.consecutive_failures(), 1);
+        assert!(breaker.try_acquire().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_probe_frees_slot() {
+        let breaker = Arc::new(CircuitBreaker::new("hanging", 1, Duration::ZERO));
+        breaker.record_failure();
+        let executor = Arc::new(CircuitBreakerExecutor::new(HangingBackend, breaker.clone()));
+
+        let probe = tokio::spawn({
+            let executor = executor.clone();
+            async move { executor.execute(&BackendRequest::new("test")).await }
+        });
+        while !breaker.health.lock().unwrap().probing {
+            tokio::task::yield_now().await;
+        }
+        assert!(breaker.try_acquire().is_err());
+
+        // Like a race loser being aborted
+        probe.abort();
+        assert!(probe.await.unwrap_err().is_cancelled());
+        assert!(breaker.try_acquire().is_ok());
+    }
+}
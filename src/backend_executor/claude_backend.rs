@@ -1,9 +1,11 @@
 //! Claude API backend executor
 
-use super::types::{BackendError, BackendExecutor, BackendRequest, BackendResponse};
+use super::rate_hints::retry_after_from_headers;
+use super::types::{BackendError, BackendExecutor, BackendRequest, BackendResponse, MessageRole};
 use crate::config::BackendConfig;
 use async_trait::async_trait;
-use serde::Deserialize;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
 use std::env;
 use std::time::{Duration, Instant};
 
@@ -19,6 +21,9 @@ pub struct ClaudeBackend {
     /// Model to use
     model: String,
 
+    /// Longest server-requested retry delay to report
+    max_retry_wait: Duration,
+
     /// HTTP client
     client: reqwest::Client,
 }
@@ -28,9 +33,19 @@ struct ClaudeResponse {
     content: Vec<ContentBlock>,
 }
 
-#[derive(Debug, Deserialize)]
-struct ContentBlock {
-    text: Option<String>,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlock {
+    Text {
+        text: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    #[serde(other)]
+    Other,
 }
 
 impl ClaudeBackend {
@@ -64,40 +79,22 @@ impl ClaudeBackend {
             name: name.into(),
             api_key,
             model,
+            max_retry_wait: Duration::from_secs(config.max_retry_wait),
             client,
         })
     }
 }
 
-#[async_trait]
-impl BackendExecutor for ClaudeBackend {
-    async fn execute(&self, request: &BackendRequest) -> Result<BackendResponse, BackendError> {
-        let start = Instant::now();
-
-        let body = serde_json::json!({
-            "model": self.model,
-            "max_tokens": 8192,
-            "messages": [
-                {
-                    "role": "user",
-                    "content": request.prompt
-                }
-            ]
-        });
-
-        eprintln!(
-            "[DEBUG {}] calling API with {} chars",
-            self.name,
-            request.prompt.len()
-        );
-
+impl ClaudeBackend {
+    /// Send one Messages API request and return the response content
+    async fn send(&self, body: &serde_json::Value) -> Result<Vec<ContentBlock>, BackendError> {
         let response = self
             .client
             .post("https://api.anthropic.com/v1/messages")
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
-            .json(&body)
+            .json(body)
             .send()
             .await
             .map_err(|e| BackendError::Unavailable {
@@ -106,12 +103,9 @@ impl BackendExecutor for ClaudeBackend {
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            return Err(BackendError::execution_failed(
-                Some(status.as_u16() as i32),
-                String::new(),
-                format!("API error {}: {}", status, body),
-            ));
+            return Err(map_api_error(status, &headers, body, self.max_retry_wait));
         }
 
         let claude_response: ClaudeResponse = response
@@ -119,20 +113,138 @@ impl BackendExecutor for ClaudeBackend {
             .await
             .map_err(|e| BackendError::parse(format!("Failed to parse response: {}", e)))?;
 
-        let text = claude_response
-            .content
-            .into_iter()
-            .filter_map(|block| block.text)
-            .collect::<Vec<_>>()
-            .join("\n");
+        Ok(claude_response.content)
+    }
+}
 
-        eprintln!("[DEBUG {}] got {} chars response", self.name, text.len());
+/// Map an API error status to BackendError
+fn map_api_error(
+    status: reqwest::StatusCode,
+    headers: &HeaderMap,
+    body: String,
+    max_retry_wait: Duration,
+) -> BackendError {
+    match status.as_u16() {
+        401 | 403 => BackendError::auth(format!("API error {}: {}", status, body)),
+        429 => BackendError::rate_limit(retry_after_from_headers(headers, max_retry_wait)),
+        // 529: API overloaded
+        500..=599 => BackendError::network(format!("API error {}: {}", status, body)),
+        _ => BackendError::execution_failed(
+            Some(status.as_u16() as i32),
+            String::new(),
+            format!("API error {}: {}", status, body),
+        ),
+    }
+}
 
-        Ok(BackendResponse::new(
-            text,
-            self.name.clone(),
-            start.elapsed(),
-        ))
+#[async_trait]
+impl BackendExecutor for ClaudeBackend {
+    async fn execute(&self, request: &BackendRequest) -> Result<BackendResponse, BackendError> {
+        let start = Instant::now();
+
+        // The system prompt is a top-level field; the rest are alternating turns
+        let mut system = None;
+        let mut messages = Vec::new();
+        for message in request.conversation() {
+            match message.role {
+                MessageRole::System => {
+                    system.get_or_insert(message.content);
+                }
+                role => messages.push(serde_json::json!({
+                    "role": role.as_str(),
+                    "content": message.content
+                })),
+            }
+        }
+
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "max_tokens": 8192,
+        });
+        if let Some(system) = system {
+            body["system"] = serde_json::Value::String(system);
+        }
+
+        let toolbox = request.tools.as_deref().filter(|t| !t.is_empty());
+        if let Some(toolbox) = toolbox {
+            let tools: Vec<_> = toolbox
+                .specs()
+                .map(|spec| {
+                    serde_json::json!({
+                        "name": spec.name,
+                        "description": spec.description,
+                        "input_schema": spec.parameters,
+                    })
+                })
+                .collect();
+            body["tools"] = serde_json::Value::Array(tools);
+        }
+
+        eprintln!(
+            "[DEBUG {}] calling API with {} chars",
+            self.name,
+            request.prompt.len()
+        );
+
+        let mut session = toolbox.map(|t| t.session());
+
+        // Run tool calls locally until the model gives a final answer
+        loop {
+            body["messages"] = serde_json::Value::Array(messages.clone());
+            let content = self.send(&body).await?;
+
+            let tool_uses: Vec<_> = content
+                .iter()
+                .filter_map(|block| match block {
+                    ContentBlock::ToolUse { id, name, input } => Some((id, name, input)),
+                    _ => None,
+                })
+                .collect();
+
+            let Some(session) = session.as_mut().filter(|_| !tool_uses.is_empty()) else {
+                let text = content
+                    .iter()
+                    .filter_map(|block| match block {
+                        ContentBlock::Text { text } => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                eprintln!("[DEBUG {}] got {} chars response", self.name, text.len());
+
+                return Ok(BackendResponse::new(
+                    text,
+                    self.name.clone(),
+                    start.elapsed(),
+                ));
+            };
+
+            if session.exceeded() {
+                return Err(BackendError::execution_failed(
+                    None,
+                    String::new(),
+                    "model kept calling tools after max_tool_calls was reached".into(),
+                ));
+            }
+
+            let mut results = Vec::new();
+            for (id, name, input) in tool_uses {
+                let output = session.call(name, input).await;
+                results.push(serde_json::json!({
+                    "type": "tool_result",
+                    "tool_use_id": id,
+                    "content": output,
+                }));
+            }
+
+            let echoed: Vec<_> = content
+                .iter()
+                .filter(|block| !matches!(block, ContentBlock::Other))
+                .collect();
+            messages.push(serde_json::json!({ "role": "assistant", "content": echoed }));
+            messages.push(serde_json::json!({ "role": "user", "content": results }));
+        }
     }
 
     fn name(&self) -> &str {
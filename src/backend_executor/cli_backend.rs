@@ -2,7 +2,9 @@
 
 //! CLI-based backend executor
 
-use super::types::{BackendError, BackendExecutor, BackendRequest, BackendResponse};
+use super::types::{
+    BackendError, BackendExecutor, BackendRequest, BackendResponse, DEFAULT_TRANSCRIPT_FORMAT,
+};
 use crate::config::BackendConfig;
 use crate::process::exit_status_code;
 use async_trait::async_trait;
@@ -31,6 +33,9 @@ pub struct CliBackend {
 
     /// Whether output is JSON
     json_output: bool,
+
+    /// Turn format used to flatten multi-turn requests into one prompt
+    transcript_format: String,
 }
 
 impl CliBackend {
@@ -45,6 +50,10 @@ impl CliBackend {
             timeout: Duration::from_secs(config.timeout),
             env: config.env.clone(),
             json_output,
+            transcript_format: config
+                .transcript_format
+                .clone()
+                .unwrap_or_else(|| DEFAULT_TRANSCRIPT_FORMAT.to_string()),
         }
     }
 
@@ -57,6 +66,7 @@ impl CliBackend {
             timeout: Duration::from_secs(300),
             env: Vec::new(),
             json_output: false,
+            transcript_format: DEFAULT_TRANSCRIPT_FORMAT.to_string(),
         }
     }
 
@@ -89,14 +99,17 @@ impl CliBackend {
         // (needed when running llm-mux from within Claude Code)
         cmd.env_remove("CLAUDECODE");
 
-        // Add the prompt as the final argument
-        cmd.arg(&request.prompt);
+        // Add the prompt (with any earlier turns flattened in) as the final argument
+        cmd.arg(request.transcript(&self.transcript_format));
 
         // Configure stdio
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
         cmd.stdin(Stdio::null());
 
+        // Kill the process if the request is cancelled (e.g. it lost a race)
+        cmd.kill_on_drop(true);
+
         cmd
     }
 }
@@ -104,6 +117,13 @@ impl CliBackend {
 #[async_trait]
 impl BackendExecutor for CliBackend {
     async fn execute(&self, request: &BackendRequest) -> Result<BackendResponse, BackendError> {
+        // A configuration mistake, not a sign of an unhealthy backend
+        if request.tools.is_some() {
+            return Err(BackendError::Config {
+                message: "tool calling needs an HTTP or Claude API backend".into(),
+            });
+        }
+
         let start = Instant::now();
         let timeout = request.timeout.unwrap_or(self.timeout);
 
@@ -251,6 +271,7 @@ impl BackendExecutor for CliBackend {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend_executor::types::Message;
 
     #[tokio::test]
     async fn test_cli_backend_echo() {
@@ -262,6 +283,21 @@ mod tests {
         assert_eq!(response.backend, "echo");
     }
 
+    #[tokio::test]
+    async fn test_cli_backend_flattens_conversation() {
+        let backend = CliBackend::new("echo", "echo");
+        let request = BackendRequest::new("Why?").with_messages(vec![
+            Message::user("Is the sky blue?"),
+            Message::assistant("Yes."),
+        ]);
+
+        let response = backend.execute(&request).await.unwrap();
+        assert_eq!(
+            response.text.trim(),
+            "User: Is the sky blue?\n\nAssistant: Yes.\n\nUser: Why?"
+        );
+    }
+
     #[tokio::test]
     async fn test_cli_backend_timeout() {
         let backend = CliBackend::new("sleep", "sleep").with_timeout(Duration::from_millis(100));
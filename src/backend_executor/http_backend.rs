@@ -2,9 +2,11 @@
 
 //! HTTP API-based backend executor
 
+use super::rate_hints::{delay_from_secs, retry_after_from_headers};
 use super::types::{BackendError, BackendExecutor, BackendRequest, BackendResponse, TokenUsage};
 use crate::config::BackendConfig;
 use async_trait::async_trait;
+use reqwest::header::HeaderMap;
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
 
@@ -26,15 +28,20 @@ pub struct HttpBackend {
     /// Default timeout
     timeout: Duration,
 
+    /// Longest server-requested retry delay to report
+    max_retry_wait: Duration,
+
     /// HTTP client
     client: reqwest::Client,
 }
 
 /// OpenAI-compatible chat completion request
 #[derive(Debug, Serialize)]
-struct ChatCompletionRequest {
+struct ChatCompletionRequest<'a> {
     model: String,
-    messages: Vec<Message>,
+    messages: &'a [Message],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<&'a [serde_json::Value]>,
     #[serde(skip_serializing_if = "Option::is_none")]
     max_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -44,7 +51,42 @@ struct ChatCompletionRequest {
 #[derive(Debug, Serialize)]
 struct Message {
     role: String,
-    content: String,
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<ToolCall>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+}
+
+impl Message {
+    fn new(role: &str, content: impl Into<String>) -> Self {
+        Self {
+            role: role.into(),
+            content: Some(content.into()),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }
+    }
+}
+
+/// Tool call requested by the model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ToolCall {
+    id: String,
+    #[serde(rename = "type", default = "default_tool_type")]
+    call_type: String,
+    function: FunctionCall,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FunctionCall {
+    name: String,
+    /// JSON-encoded arguments
+    arguments: String,
+}
+
+fn default_tool_type() -> String {
+    "function".into()
 }
 
 /// OpenAI-compatible chat completion response
@@ -65,6 +107,8 @@ struct Choice {
 #[derive(Debug, Deserialize)]
 struct ResponseMessage {
     content: Option<String>,
+    #[serde(default)]
+    tool_calls: Option<Vec<ToolCall>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -88,6 +132,7 @@ impl HttpBackend {
             api_key: config.api_key.clone(),
             model: config.model.clone(),
             timeout: Duration::from_secs(config.timeout),
+            max_retry_wait: Duration::from_secs(config.max_retry_wait),
             client,
         }
     }
@@ -105,6 +150,7 @@ impl HttpBackend {
             api_key: None,
             model: None,
             timeout: Duration::from_secs(300),
+            max_retry_wait: Duration::from_secs(BackendConfig::default().max_retry_wait),
             client,
         }
     }
@@ -134,12 +180,18 @@ impl HttpBackend {
     }
 
     /// Map HTTP status to BackendError
-    fn map_http_error(&self, status: reqwest::StatusCode, body: &str) -> BackendError {
+    fn map_http_error(
+        &self,
+        status: reqwest::StatusCode,
+        headers: &HeaderMap,
+        body: &str,
+    ) -> BackendError {
         match status.as_u16() {
             401 | 403 => BackendError::auth(format!("HTTP {}: {}", status, body)),
             429 => {
-                // Try to parse retry-after from body
-                let retry_after = self.parse_retry_after(body);
+                // Prefer rate-limit headers, then a retry_after field in the body
+                let retry_after = retry_after_from_headers(headers, self.max_retry_wait)
+                    .or_else(|| self.parse_retry_after(body));
                 BackendError::rate_limit(retry_after)
             }
             408 | 504 => BackendError::timeout(self.timeout, None),
@@ -160,43 +212,21 @@ impl HttpBackend {
         // Try to parse as JSON and look for retry_after field
         if let Ok(json) = serde_json::from_str::<serde_json::Value>(body) {
             if let Some(seconds) = json.get("retry_after").and_then(|v| v.as_f64()) {
-                return Some(Duration::from_secs_f64(seconds));
+                return delay_from_secs(seconds, self.max_retry_wait);
             }
         }
         None
     }
-}
-
-#[async_trait]
-impl BackendExecutor for HttpBackend {
-    async fn execute(&self, request: &BackendRequest) -> Result<BackendResponse, BackendError> {
-        let start = Instant::now();
-
-        // Build messages
-        let mut messages = Vec::new();
-
-        if let Some(ref system) = request.system_prompt {
-            messages.push(Message {
-                role: "system".into(),
-                content: system.clone(),
-            });
-        }
-
-        messages.push(Message {
-            role: "user".into(),
-            content: request.prompt.clone(),
-        });
-
-        // Build request body
-        let body = ChatCompletionRequest {
-            model: self.model.clone().unwrap_or_else(|| "gpt-4".into()),
-            messages,
-            max_tokens: None,
-            temperature: None,
-        };
 
+    /// Send one chat completion request
+    async fn send(
+        &self,
+        body: &ChatCompletionRequest<'_>,
+        timeout: Duration,
+        start: Instant,
+    ) -> Result<ChatCompletionResponse, BackendError> {
         // Build HTTP request
-        let mut http_request = self.client.post(self.chat_completion_url()).json(&body);
+        let mut http_request = self.client.post(self.chat_completion_url()).json(body);
 
         // Add auth header if we have an API key
         if let Some(ref key) = self.api_key {
@@ -204,7 +234,6 @@ impl BackendExecutor for HttpBackend {
         }
 
         // Send request with timeout
-        let timeout = request.timeout.unwrap_or(self.timeout);
         let result = tokio::time::timeout(timeout, http_request.send()).await;
 
         let elapsed = start.elapsed();
@@ -214,36 +243,13 @@ impl BackendExecutor for HttpBackend {
                 let status = response.status();
 
                 if status.is_success() {
-                    let completion: ChatCompletionResponse =
-                        response.json().await.map_err(|e| {
-                            BackendError::parse(format!("failed to parse response: {}", e))
-                        })?;
-
-                    let text = completion
-                        .choices
-                        .first()
-                        .and_then(|c| c.message.content.clone())
-                        .unwrap_or_default();
-
-                    let mut backend_response =
-                        BackendResponse::new(text, self.name.clone(), elapsed);
-
-                    if let Some(model) = completion.model {
-                        backend_response = backend_response.with_model(model);
-                    }
-
-                    if let Some(usage) = completion.usage {
-                        backend_response = backend_response.with_usage(TokenUsage {
-                            prompt_tokens: usage.prompt_tokens,
-                            completion_tokens: usage.completion_tokens,
-                            total_tokens: usage.total_tokens,
-                        });
-                    }
-
-                    Ok(backend_response)
+                    response.json().await.map_err(|e| {
+                        BackendError::parse(format!("failed to parse response: {}", e))
+                    })
                 } else {
+                    let headers = response.headers().clone();
                     let body = response.text().await.unwrap_or_default();
-                    Err(self.map_http_error(status, &body))
+                    Err(self.map_http_error(status, &headers, &body))
                 }
             }
             Ok(Err(e)) => {
@@ -262,6 +268,124 @@ impl BackendExecutor for HttpBackend {
             }
         }
     }
+}
+
+/// Add one response's token usage to a running total
+fn add_usage(total: &mut Option<TokenUsage>, usage: Usage) {
+    let sum = |a: Option<u32>, b: Option<u32>| match (a, b) {
+        (None, None) => None,
+        (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+    };
+    let total = total.get_or_insert(TokenUsage {
+        prompt_tokens: None,
+        completion_tokens: None,
+        total_tokens: None,
+    });
+    total.prompt_tokens = sum(total.prompt_tokens, usage.prompt_tokens);
+    total.completion_tokens = sum(total.completion_tokens, usage.completion_tokens);
+    total.total_tokens = sum(total.total_tokens, usage.total_tokens);
+}
+
+#[async_trait]
+impl BackendExecutor for HttpBackend {
+    async fn execute(&self, request: &BackendRequest) -> Result<BackendResponse, BackendError> {
+        let start = Instant::now();
+        let timeout = request.timeout.unwrap_or(self.timeout);
+
+        // Build messages
+        let mut messages: Vec<Message> = request
+            .conversation()
+            .into_iter()
+            .map(|m| Message::new(m.role.as_str(), m.content))
+            .collect();
+
+        let toolbox = request.tools.as_deref().filter(|t| !t.is_empty());
+        let tools: Option<Vec<serde_json::Value>> = toolbox.map(|t| {
+            t.specs()
+                .map(|spec| {
+                    serde_json::json!({
+                        "type": "function",
+                        "function": {
+                            "name": spec.name,
+                            "description": spec.description,
+                            "parameters": spec.parameters,
+                        }
+                    })
+                })
+                .collect()
+        });
+
+        let mut model = None;
+        let mut usage = None;
+
+        let mut session = toolbox.map(|t| t.session());
+
+        // Run tool calls locally until the model gives a final answer
+        loop {
+            let body = ChatCompletionRequest {
+                model: self.model.clone().unwrap_or_else(|| "gpt-4".into()),
+                messages: &messages,
+                tools: tools.as_deref(),
+                max_tokens: None,
+                temperature: None,
+            };
+            // Every round trip shares the request's timeout
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                return Err(BackendError::timeout(start.elapsed(), None));
+            }
+            let completion = self.send(&body, remaining, start).await?;
+
+            model = completion.model.or(model);
+            if let Some(u) = completion.usage {
+                add_usage(&mut usage, u);
+            }
+
+            let message = completion.choices.into_iter().next().map(|c| c.message);
+            let (content, tool_calls) = message
+                .map(|m| (m.content, m.tool_calls.unwrap_or_default()))
+                .unwrap_or_default();
+
+            let Some(session) = session.as_mut().filter(|_| !tool_calls.is_empty()) else {
+                let mut backend_response = BackendResponse::new(
+                    content.unwrap_or_default(),
+                    self.name.clone(),
+                    start.elapsed(),
+                );
+                if let Some(model) = model {
+                    backend_response = backend_response.with_model(model);
+                }
+                if let Some(usage) = usage {
+                    backend_response = backend_response.with_usage(usage);
+                }
+                return Ok(backend_response);
+            };
+
+            if session.exceeded() {
+                return Err(BackendError::execution_failed(
+                    None,
+                    content.unwrap_or_default(),
+                    "model kept calling tools after max_tool_calls was reached".into(),
+                ));
+            }
+
+            messages.push(Message {
+                role: "assistant".into(),
+                content,
+                tool_calls: tool_calls.clone(),
+                tool_call_id: None,
+            });
+            for call in tool_calls {
+                let arguments = serde_json::from_str(&call.function.arguments)
+                    .unwrap_or(serde_json::Value::Null);
+                let output = session.call(&call.function.name, &arguments).await;
+                messages.push(Message {
+                    tool_call_id: Some(call.id),
+                    ..Message::new("tool", output)
+                });
+            }
+        }
+    }
 
     fn name(&self) -> &str {
         &self.name
@@ -321,16 +445,182 @@ mod tests {
     fn test_map_http_error() {
         let backend = HttpBackend::new("test", "https://example.com");
 
-        let err = backend.map_http_error(reqwest::StatusCode::UNAUTHORIZED, "bad token");
+        let headers = HeaderMap::new();
+
+        let err = backend.map_http_error(reqwest::StatusCode::UNAUTHORIZED, &headers, "bad token");
         assert!(matches!(err, BackendError::Auth { .. }));
 
-        let err = backend.map_http_error(reqwest::StatusCode::TOO_MANY_REQUESTS, "rate limited");
+        let err = backend.map_http_error(
+            reqwest::StatusCode::TOO_MANY_REQUESTS,
+            &headers,
+            "rate limited",
+        );
         assert!(matches!(err, BackendError::RateLimit { .. }));
 
-        let err = backend.map_http_error(reqwest::StatusCode::INTERNAL_SERVER_ERROR, "error");
+        let err = backend.map_http_error(
+            reqwest::StatusCode::INTERNAL_SERVER_ERROR,
+            &headers,
+            "error",
+        );
         assert!(matches!(err, BackendError::Network { .. }));
     }
 
+    #[test]
+    fn test_map_http_error_retry_after_header() {
+        let backend = HttpBackend::new("test", "https://example.com");
+
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", "12".parse().unwrap());
+        let err = backend.map_http_error(
+            reqwest::StatusCode::TOO_MANY_REQUESTS,
+            &headers,
+            r#"{"retry_after": 3}"#,
+        );
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(12)));
+
+        let err = backend.map_http_error(
+            reqwest::StatusCode::TOO_MANY_REQUESTS,
+            &HeaderMap::new(),
+            r#"{"retry_after": 3}"#,
+        );
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(3)));
+    }
+
+    /// Serve one canned JSON response per connection, recording request bodies
+    async fn mock_server(
+        responses: Vec<serde_json::Value>,
+    ) -> (String, tokio::task::JoinHandle<Vec<serde_json::Value>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let mut bodies = Vec::new();
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                let body_start = loop {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    if let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break pos + 4;
+                    }
+                };
+                let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+                let length: usize = headers
+                    .lines()
+                    .find_map(|l| l.strip_prefix("content-length:"))
+                    .map(|v| v.trim().parse().unwrap())
+                    .unwrap_or(0);
+                while request.len() < body_start + length {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                bodies.push(serde_json::from_slice(&request[body_start..]).unwrap());
+
+                let body = response.to_string();
+                let reply = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(reply.as_bytes()).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+            bodies
+        });
+
+        (url, server)
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_run_locally() {
+        use crate::backend_executor::tools::Toolbox;
+        use crate::config::ToolConfig;
+        use serde_json::json;
+        use std::sync::Arc;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "the answer is 42").unwrap();
+        let tools = Toolbox::from_config(
+            &[ToolConfig {
+                builtin: Some("read_file".into()),
+                ..Default::default()
+            }],
+            dir.path(),
+            3,
+        );
+
+        let (url, server) = mock_server(vec![
+            json!({
+                "choices": [{ "message": { "content": null, "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": { "name": "read_file", "arguments": "{\"path\":\"notes.txt\"}" }
+                }]}}],
+                "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+            }),
+            json!({
+                "choices": [{ "message": { "content": "42" } }],
+                "usage": { "prompt_tokens": 20, "completion_tokens": 2, "total_tokens": 22 }
+            }),
+        ])
+        .await;
+
+        let backend = HttpBackend::new("local", url);
+        let request = BackendRequest::new("What is the answer?").with_tools(Arc::new(tools));
+        let response = backend.execute(&request).await.unwrap();
+
+        assert_eq!(response.text, "42");
+        assert_eq!(response.usage.unwrap().total_tokens, Some(37));
+
+        let bodies = server.await.unwrap();
+        assert_eq!(bodies[0]["tools"][0]["function"]["name"], "read_file");
+        let tool_turn = &bodies[1]["messages"][2];
+        assert_eq!(tool_turn["role"], "tool");
+        assert_eq!(tool_turn["tool_call_id"], "call_1");
+        assert_eq!(tool_turn["content"], "the answer is 42");
+    }
+
+    #[tokio::test]
+    async fn test_tool_rounds_share_timeout() {
+        use crate::backend_executor::tools::Toolbox;
+        use crate::config::ToolConfig;
+        use serde_json::json;
+        use std::sync::Arc;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let tools = Toolbox::from_config(
+            &[ToolConfig {
+                name: Some("slow".into()),
+                run: Some("sleep 0.3".into()),
+                ..Default::default()
+            }],
+            dir.path(),
+            3,
+        );
+
+        // The model keeps asking for the slow tool; the second round is never sent
+        let (url, server) = mock_server(vec![json!({
+            "choices": [{ "message": { "content": null, "tool_calls": [{
+                "id": "call_1",
+                "type": "function",
+                "function": { "name": "slow", "arguments": "{}" }
+            }]}}]
+        })])
+        .await;
+
+        let backend = HttpBackend::new("local", url).with_timeout(Duration::from_millis(200));
+        let request = BackendRequest::new("Go slow").with_tools(Arc::new(tools));
+        let result = backend.execute(&request).await;
+
+        assert!(matches!(result, Err(BackendError::Timeout { .. })));
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+
     #[test]
     fn test_from_config() {
         let config = BackendConfig {
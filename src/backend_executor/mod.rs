@@ -21,19 +21,38 @@
 //! println!("Output: {}", response.text);
 //! ```
 
+mod cache;
+mod circuit_breaker;
 mod claude_backend;
 mod cli_backend;
 mod http_backend;
 pub mod output_parser;
+mod rate_hints;
+mod rate_limit;
 mod retry;
+mod stats;
+mod tools;
 mod types;
 
+#[allow(unused_imports)]
+pub use cache::{CacheStats, CachedExecutor, ResponseCache, with_cache};
+#[allow(unused_imports)]
+pub use circuit_breaker::{BreakerState, CircuitBreaker, CircuitBreakerExecutor};
 pub use claude_backend::ClaudeBackend;
 pub use cli_backend::CliBackend;
 pub use http_backend::HttpBackend;
+#[allow(unused_imports)]
+pub use rate_limit::{RateLimitedExecutor, RateLimiter};
 pub use retry::{RetryExecutor, with_retry};
 #[allow(unused_imports)]
-pub use types::{BackendError, BackendExecutor, BackendRequest, BackendResponse, RetryPolicy};
+pub use stats::{BackendStats, StatsExecutor, StatsStore};
+#[allow(unused_imports)]
+pub use tools::{ToolSpec, Toolbox};
+#[allow(unused_imports)]
+pub use types::{
+    BackendError, BackendExecutor, BackendRequest, BackendResponse, CacheMode,
+    DEFAULT_TRANSCRIPT_FORMAT, Message, MessageRole, RetryPolicy, TokenUsage,
+};
 
 use crate::config::BackendConfig;
 
@@ -58,11 +77,17 @@ pub fn create_executor(name: &str, config: &BackendConfig) -> Box<dyn BackendExe
 }
 
 /// Create an executor with retry logic
+///
+/// Backends with `requests_per_minute`, `tokens_per_minute` or `max_in_flight`
+/// set share a process-wide limiter, applied to every attempt.
 pub fn create_executor_with_retry(
     name: &str,
     config: &BackendConfig,
 ) -> RetryExecutor<Box<dyn BackendExecutor>> {
-    let executor = create_executor(name, config);
+    let mut executor = create_executor(name, config);
+    if let Some(limiter) = RateLimiter::shared(name, config) {
+        executor = Box::new(RateLimitedExecutor::new(executor, limiter));
+    }
     let policy = RetryPolicy::from_config(config);
     with_retry(executor, policy)
 }
@@ -29,6 +29,14 @@ pub fn extract_json(text: &str) -> Option<Value> {
     None
 }
 
+/// Look up a dotted path (`a.b.0`) in a JSON value
+pub fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, part| match value {
+        Value::Array(items) => items.get(part.parse::<usize>().ok()?),
+        _ => value.get(part),
+    })
+}
+
 /// Extract JSON from a markdown code block
 fn extract_from_code_block(text: &str, lang: &str) -> Option<Value> {
     let start_patterns: Vec<String> = if lang.is_empty() {
@@ -0,0 +1,244 @@
+//! Extract retry hints from provider rate-limit response headers
+
+use reqwest::header::HeaderMap;
+use std::time::Duration;
+
+/// Rate-limit windows reported by OpenAI- and Anthropic-style headers
+const LIMIT_KINDS: &[&str] = &["requests", "tokens", "input-tokens", "output-tokens"];
+
+/// Determine how long to wait before retrying, from response headers
+///
+/// Checks, in order:
+/// - `retry-after-ms` (milliseconds)
+/// - `Retry-After` (delay in seconds, or an HTTP date)
+/// - `x-ratelimit-reset-*` (OpenAI, e.g. `6m0s`) and
+///   `anthropic-ratelimit-*-reset` (RFC 3339 timestamp)
+///
+/// For the reset headers, windows whose `remaining` count is zero are
+/// preferred; the longest of those is returned. Delays are capped at `max_wait`,
+/// and nonsensical values (negative, infinite) are ignored.
+pub fn retry_after_from_headers(headers: &HeaderMap, max_wait: Duration) -> Option<Duration> {
+    if let Some(delay) = header_str(headers, "retry-after-ms")
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .and_then(|ms| delay_from_secs(ms / 1000.0, max_wait))
+    {
+        return Some(delay);
+    }
+
+    if let Some(value) = header_str(headers, "retry-after")
+        && let Some(delay) = parse_retry_after(value, max_wait)
+    {
+        return Some(delay);
+    }
+
+    let mut exhausted = Vec::new();
+    let mut other = Vec::new();
+
+    for kind in LIMIT_KINDS {
+        let openai = header_str(headers, &format!("x-ratelimit-reset-{}", kind))
+            .and_then(|value| parse_go_duration(value, max_wait))
+            .map(|reset| {
+                let remaining = header_str(headers, &format!("x-ratelimit-remaining-{}", kind));
+                (reset, remaining)
+            });
+        let anthropic = header_str(headers, &format!("anthropic-ratelimit-{}-reset", kind))
+            .and_then(|value| parse_timestamp(value, max_wait))
+            .map(|reset| {
+                let remaining =
+                    header_str(headers, &format!("anthropic-ratelimit-{}-remaining", kind));
+                (reset, remaining)
+            });
+
+        for (reset, remaining) in openai.into_iter().chain(anthropic) {
+            if remaining.is_some_and(|r| r.trim() == "0") {
+                exhausted.push(reset);
+            } else {
+                other.push(reset);
+            }
+        }
+    }
+
+    if exhausted.is_empty() {
+        other.into_iter().max()
+    } else {
+        exhausted.into_iter().max()
+    }
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name)?.to_str().ok()
+}
+
+/// A server-supplied delay in seconds, capped at `max_wait`
+///
+/// Negative and non-finite values are rejected rather than trusted.
+pub fn delay_from_secs(seconds: f64, max_wait: Duration) -> Option<Duration> {
+    if !seconds.is_finite() || seconds < 0.0 {
+        return None;
+    }
+    Duration::try_from_secs_f64(seconds.min(max_wait.as_secs_f64())).ok()
+}
+
+/// Parse a `Retry-After` value: delay in seconds or an HTTP date
+pub fn parse_retry_after(value: &str, max_wait: Duration) -> Option<Duration> {
+    let value = value.trim();
+
+    if let Ok(seconds) = value.parse::<f64>() {
+        return delay_from_secs(seconds, max_wait);
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some(until(date.with_timezone(&chrono::Utc)).min(max_wait))
+}
+
+/// Parse an RFC 3339 reset timestamp into a delay from now
+fn parse_timestamp(value: &str, max_wait: Duration) -> Option<Duration> {
+    let date = chrono::DateTime::parse_from_rfc3339(value.trim()).ok()?;
+    Some(until(date.with_timezone(&chrono::Utc)).min(max_wait))
+}
+
+fn until(date: chrono::DateTime<chrono::Utc>) -> Duration {
+    (date - chrono::Utc::now()).to_std().unwrap_or_default()
+}
+
+/// Parse Go-style durations used by OpenAI headers (`1s`, `6m0s`, `20ms`, `1h2m3.5s`)
+fn parse_go_duration(value: &str, max_wait: Duration) -> Option<Duration> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    let mut total = 0.0;
+    let mut rest = value;
+
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        if number_len == 0 {
+            return None;
+        }
+        let number: f64 = rest[..number_len].parse().ok()?;
+        rest = &rest[number_len..];
+
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let seconds = match &rest[..unit_len] {
+            "ms" => number / 1000.0,
+            "s" | "" => number,
+            "m" => number * 60.0,
+            "h" => number * 3600.0,
+            _ => return None,
+        };
+        total += seconds;
+        rest = &rest[unit_len..];
+    }
+
+    delay_from_secs(total, max_wait)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    const MAX: Duration = Duration::from_secs(86400);
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn test_retry_after_seconds() {
+        let map = headers(&[("retry-after", "30")]);
+        assert_eq!(
+            retry_after_from_headers(&map, MAX),
+            Some(Duration::from_secs(30))
+        );
+
+        let map = headers(&[("retry-after-ms", "1500"), ("retry-after", "30")]);
+        assert_eq!(
+            retry_after_from_headers(&map, MAX),
+            Some(Duration::from_millis(1500))
+        );
+    }
+
+    #[test]
+    fn test_retry_after_http_date() {
+        let date = (chrono::Utc::now() + chrono::Duration::seconds(120)).to_rfc2822();
+        let delay = parse_retry_after(&date, MAX).unwrap();
+        assert!(delay > Duration::from_secs(100) && delay <= Duration::from_secs(120));
+
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", MAX),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_openai_reset_headers() {
+        let map = headers(&[
+            ("x-ratelimit-remaining-requests", "10"),
+            ("x-ratelimit-reset-requests", "1s"),
+            ("x-ratelimit-remaining-tokens", "0"),
+            ("x-ratelimit-reset-tokens", "6m0s"),
+        ]);
+        assert_eq!(
+            retry_after_from_headers(&map, MAX),
+            Some(Duration::from_secs(360))
+        );
+    }
+
+    #[test]
+    fn test_anthropic_reset_headers() {
+        let reset = (chrono::Utc::now() + chrono::Duration::seconds(45)).to_rfc3339();
+        let map = headers(&[
+            ("anthropic-ratelimit-requests-remaining", "0"),
+            ("anthropic-ratelimit-requests-reset", &reset),
+        ]);
+        let delay = retry_after_from_headers(&map, MAX).unwrap();
+        assert!(delay > Duration::from_secs(40) && delay <= Duration::from_secs(45));
+    }
+
+    #[test]
+    fn test_parse_go_duration() {
+        assert_eq!(
+            parse_go_duration("20ms", MAX),
+            Some(Duration::from_millis(20))
+        );
+        assert_eq!(
+            parse_go_duration("1h2m3s", MAX),
+            Some(Duration::from_secs(3723))
+        );
+        assert_eq!(
+            parse_go_duration("0.5s", MAX),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(parse_go_duration("soon", MAX), None);
+        assert!(retry_after_from_headers(&HeaderMap::new(), MAX).is_none());
+    }
+
+    #[test]
+    fn test_hostile_values() {
+        for value in ["inf", "NaN", "1e400", "-5"] {
+            assert_eq!(parse_retry_after(value, MAX), None, "{}", value);
+            let map = headers(&[("retry-after-ms", value)]);
+            assert_eq!(retry_after_from_headers(&map, MAX), None, "{}", value);
+        }
+        assert_eq!(
+            parse_go_duration(&format!("{}s", "9".repeat(400)), MAX),
+            None
+        );
+
+        // Huge but finite delays are capped
+        assert_eq!(parse_retry_after("1e300", MAX), Some(MAX));
+        assert_eq!(parse_go_duration("100000h", MAX), Some(MAX));
+        let far = (chrono::Utc::now() + chrono::Duration::days(300)).to_rfc2822();
+        assert_eq!(parse_retry_after(&far, MAX), Some(MAX));
+    }
+}
@@ -0,0 +1,367 @@
+#![allow(dead_code)]
+
+//! Process-wide per-backend rate limiting
+
+use super::types::{BackendError, BackendExecutor, BackendRequest, BackendResponse};
+use crate::config::BackendConfig;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Limiters shared by every executor in the process, keyed by backend name
+static LIMITERS: OnceLock<Mutex<HashMap<String, Arc<RateLimiter>>>> = OnceLock::new();
+
+/// Token bucket refilled continuously over a one-minute window
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    available: f64,
+    per_second: f64,
+    last_refill: Instant,
+}
+
+impl Bucket {
+    fn per_minute(limit: u32) -> Self {
+        let capacity = f64::from(limit.max(1));
+        Self {
+            capacity,
+            available: capacity,
+            per_second: capacity / 60.0,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.available = (self.available + elapsed * self.per_second).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Take `amount` units, or return how long to wait until they are available
+    fn try_take(&mut self, amount: f64, now: Instant) -> Option<Duration> {
+        self.refill(now);
+
+        // A single request larger than the bucket can never fit; let it drain the bucket
+        let amount = amount.min(self.capacity);
+        if self.available >= amount {
+            self.available -= amount;
+            None
+        } else {
+            Some(Duration::from_secs_f64(
+                (amount - self.available) / self.per_second,
+            ))
+        }
+    }
+
+    /// Correct a previous estimate once the real cost is known
+    fn adjust(&mut self, delta: f64) {
+        self.available = (self.available - delta).clamp(-self.capacity, self.capacity);
+    }
+}
+
+/// Configured limits for a backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Limits {
+    requests_per_minute: Option<u32>,
+    tokens_per_minute: Option<u32>,
+    max_in_flight: Option<u32>,
+}
+
+impl Limits {
+    fn from_config(config: &BackendConfig) -> Self {
+        Self {
+            requests_per_minute: config.requests_per_minute,
+            tokens_per_minute: config.tokens_per_minute,
+            max_in_flight: config.max_in_flight,
+        }
+    }
+}
+
+/// Rate limiter for a single backend
+#[derive(Debug)]
+pub struct RateLimiter {
+    limits: Limits,
+    requests: Option<Mutex<Bucket>>,
+    tokens: Option<Mutex<Bucket>>,
+    in_flight: Option<Arc<Semaphore>>,
+}
+
+/// Held while a request is in flight
+pub struct RatePermit {
+    _in_flight: Option<OwnedSemaphorePermit>,
+}
+
+impl RateLimiter {
+    /// Create a limiter from a backend's configured limits
+    pub fn from_config(config: &BackendConfig) -> Self {
+        Self::new(Limits::from_config(config))
+    }
+
+    fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            requests: limits
+                .requests_per_minute
+                .map(|n| Mutex::new(Bucket::per_minute(n))),
+            tokens: limits
+                .tokens_per_minute
+                .map(|n| Mutex::new(Bucket::per_minute(n))),
+            in_flight: limits
+                .max_in_flight
+                .map(|n| Arc::new(Semaphore::new(n.max(1) as usize))),
+        }
+    }
+
+    /// Get the process-wide limiter for a backend, if it has any limits
+    ///
+    /// Every executor for the same backend name shares one limiter, so limits
+    /// hold across parallel roles, `for_each` iterations and concurrent steps.
+    pub fn shared(name: &str, config: &BackendConfig) -> Option<Arc<Self>> {
+        if !config.has_rate_limits() {
+            return None;
+        }
+
+        let limits = Limits::from_config(config);
+        let mut limiters = LIMITERS
+            .get_or_init(Default::default)
+            .lock()
+            .expect("rate limiter registry poisoned");
+
+        if let Some(existing) = limiters.get(name)
+            && existing.limits == limits
+        {
+            return Some(existing.clone());
+        }
+
+        let limiter = Arc::new(Self::new(limits));
+        limiters.insert(name.to_string(), limiter.clone());
+        Some(limiter)
+    }
+
+    /// Wait until a request costing `estimated_tokens` may proceed
+    pub async fn acquire(&self, estimated_tokens: u32) -> RatePermit {
+        let in_flight = match &self.in_flight {
+            Some(sem) => Some(
+                sem.clone()
+                    .acquire_owned()
+                    .await
+                    .expect("rate limiter semaphore closed"),
+            ),
+            None => None,
+        };
+
+        if let Some(requests) = &self.requests {
+            Self::take(requests, 1.0).await;
+        }
+        if let Some(tokens) = &self.tokens {
+            Self::take(tokens, f64::from(estimated_tokens)).await;
+        }
+
+        RatePermit {
+            _in_flight: in_flight,
+        }
+    }
+
+    /// Reconcile the token bucket with the usage a backend actually reported
+    pub fn record_usage(&self, estimated_tokens: u32, actual_tokens: u32) {
+        if let Some(tokens) = &self.tokens {
+            let delta = f64::from(actual_tokens) - f64::from(estimated_tokens);
+            tokens.lock().expect("rate limiter poisoned").adjust(delta);
+        }
+    }
+
+    async fn take(bucket: &Mutex<Bucket>, amount: f64) {
+        loop {
+            let wait = bucket
+                .lock()
+                .expect("rate limiter poisoned")
+                .try_take(amount, Instant::now());
+            match wait {
+                None => return,
+                Some(wait) => tokio::time::sleep(wait).await,
+            }
+        }
+    }
+}
+
+/// Rough token estimate for a request, including earlier turns (about four characters per token)
+pub fn estimate_tokens(request: &BackendRequest) -> u32 {
+    let chars = request.prompt.len()
+        + request.system_prompt.as_ref().map_or(0, |s| s.len())
+        + request
+            .messages
+            .iter()
+            .map(|message| message.content.len())
+            .sum::<usize>();
+    u32::try_from(chars / 4 + 1).unwrap_or(u32::MAX)
+}
+
+/// Wrapper that enforces a backend's rate limits before each request
+pub struct RateLimitedExecutor<T: BackendExecutor> {
+    inner: T,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<T: BackendExecutor> RateLimitedExecutor<T> {
+    /// Create a new rate-limited executor
+    pub fn new(inner: T, limiter: Arc<RateLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+#[async_trait]
+impl<T: BackendExecutor + 'static> BackendExecutor for RateLimitedExecutor<T> {
+    async fn execute(&self, request: &BackendRequest) -> Result<BackendResponse, BackendError> {
+        let estimated = estimate_tokens(request);
+
+        let start = Instant::now();
+        let _permit = self.limiter.acquire(estimated).await;
+        let waited = start.elapsed();
+        if waited >= Duration::from_millis(100) {
+            tracing::debug!(
+                backend = %self.inner.name(),
+                waited_ms = waited.as_millis() as u64,
+                "Waited for rate limit"
+            );
+        }
+
+        let response = self.inner.execute(request).await?;
+
+        if let Some(usage) = &response.usage {
+            let actual = usage
+                .total_tokens
+                .or_else(|| Some(usage.prompt_tokens? + usage.completion_tokens.unwrap_or(0)));
+            if let Some(actual) = actual {
+                self.limiter.record_usage(estimated, actual);
+            }
+        }
+
+        Ok(response)
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn is_available(&self) -> bool {
+        self.inner.is_available().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend_executor::Message;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Mock backend that tracks peak concurrency
+    struct SlowBackend {
+        current: AtomicU32,
+        peak: AtomicU32,
+    }
+
+    #[async_trait]
+    impl BackendExecutor for SlowBackend {
+        async fn execute(
+            &self,
+            _request: &BackendRequest,
+        ) -> Result<BackendResponse, BackendError> {
+            let now = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.current.fetch_sub(1, Ordering::SeqCst);
+            Ok(BackendResponse::new(
+                "ok".into(),
+                "slow".into(),
+                Duration::from_millis(20),
+            ))
+        }
+
+        fn name(&self) -> &str {
+            "slow"
+        }
+    }
+
+    #[test]
+    fn test_bucket_waits_when_exhausted() {
+        let now = Instant::now();
+        let mut bucket = Bucket::per_minute(60);
+
+        for _ in 0..60 {
+            assert!(bucket.try_take(1.0, now).is_none());
+        }
+
+        // One request per second refill rate
+        let wait = bucket.try_take(1.0, now).unwrap();
+        assert!(wait <= Duration::from_secs(1) && wait > Duration::from_millis(900));
+
+        assert!(bucket.try_take(1.0, now + Duration::from_secs(1)).is_none());
+    }
+
+    #[test]
+    fn test_bucket_adjust_for_actual_usage() {
+        let now = Instant::now();
+        let mut bucket = Bucket::per_minute(1000);
+
+        assert!(bucket.try_take(100.0, now).is_none());
+        bucket.adjust(900.0);
+        assert!(bucket.try_take(1.0, now).is_some());
+    }
+
+    #[test]
+    fn test_shared_limiter_is_process_wide() {
+        let config = BackendConfig {
+            requests_per_minute: Some(10),
+            ..Default::default()
+        };
+
+        let a = RateLimiter::shared("test-shared", &config).unwrap();
+        let b = RateLimiter::shared("test-shared", &config).unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+
+        assert!(RateLimiter::shared("test-unlimited", &BackendConfig::default()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_max_in_flight() {
+        let config = BackendConfig {
+            max_in_flight: Some(2),
+            ..Default::default()
+        };
+        let executor = Arc::new(RateLimitedExecutor::new(
+            SlowBackend {
+                current: AtomicU32::new(0),
+                peak: AtomicU32::new(0),
+            },
+            Arc::new(RateLimiter::from_config(&config)),
+        ));
+
+        let handles: Vec<_> = (0..6)
+            .map(|_| {
+                let executor = executor.clone();
+                tokio::spawn(async move { executor.execute(&BackendRequest::new("x")).await })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+
+        assert_eq!(executor.inner.peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        let request = BackendRequest::new("a".repeat(400)).with_system_prompt("b".repeat(40));
+        assert_eq!(estimate_tokens(&request), 111);
+
+        let request = request.with_messages(vec![
+            Message::user("c".repeat(800)),
+            Message::assistant("d".repeat(400)),
+        ]);
+        assert_eq!(estimate_tokens(&request), 411);
+    }
+}
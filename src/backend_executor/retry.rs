@@ -40,14 +40,24 @@ impl<T: BackendExecutor + 'static> BackendExecutor for RetryExecutor<T> {
                         return Err(e);
                     }
 
-                    // Calculate delay
-                    let delay = if let Some(retry_after) = e.retry_after() {
-                        // Use server-specified retry-after if available
-                        retry_after
-                    } else {
-                        self.policy.delay_for_attempt(attempt)
+                    // Calculate delay, honoring a server-specified retry-after up to the cap
+                    let (delay, reason) = match e.retry_after() {
+                        Some(retry_after) if retry_after > self.policy.max_retry_after => {
+                            (self.policy.max_retry_after, "retry-after (capped)")
+                        }
+                        Some(retry_after) => (retry_after, "retry-after"),
+                        None => (self.policy.delay_for_attempt(attempt), "backoff"),
                     };
 
+                    tracing::warn!(
+                        backend = %self.inner.name(),
+                        attempt = attempt + 1,
+                        delay_ms = delay.as_millis() as u64,
+                        reason,
+                        error = %e,
+                        "Retrying backend request"
+                    );
+
                     last_error = Some(e);
 
                     // Wait before retrying
@@ -191,6 +201,23 @@ mod tests {
         assert!(matches!(result.unwrap_err(), BackendError::Auth { .. }));
     }
 
+    #[tokio::test]
+    async fn test_retry_after_capped() {
+        let backend =
+            MockBackend::new(1, BackendError::rate_limit(Some(Duration::from_secs(3600))));
+        let policy = RetryPolicy {
+            max_retries: 1,
+            max_retry_after: Duration::from_millis(5),
+            ..Default::default()
+        };
+        let executor = RetryExecutor::new(backend, policy);
+
+        let start = std::time::Instant::now();
+        let result = executor.execute(&BackendRequest::new("test")).await;
+        assert!(result.is_ok());
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
     #[tokio::test]
     async fn test_immediate_success() {
         let backend = MockBackend::retryable(0); // Never fail
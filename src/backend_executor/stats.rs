@@ -0,0 +1,348 @@
+//! Persisted per-backend call statistics used for adaptive ordering
+
+use super::types::{BackendError, BackendExecutor, BackendRequest, BackendResponse};
+use crate::config::BackendConfig;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Number of most recent calls per backend that statistics are computed over
+const WINDOW: u32 = 200;
+
+/// Summary of a backend's recent calls
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BackendStats {
+    /// Backend name
+    pub backend: String,
+    /// Calls in the window
+    pub calls: u64,
+    /// Successful calls in the window
+    pub successes: u64,
+    /// Median latency of successful calls
+    pub p50_ms: Option<u64>,
+    /// Tokens used by calls that reported usage
+    pub tokens: u64,
+    /// Cost of those calls in USD
+    pub cost: f64,
+}
+
+impl BackendStats {
+    /// Fraction of calls that succeeded
+    pub fn success_rate(&self) -> Option<f64> {
+        (self.calls > 0).then(|| self.successes as f64 / self.calls as f64)
+    }
+
+    /// Observed cost in USD per million tokens
+    pub fn cost_per_mtok(&self) -> Option<f64> {
+        (self.tokens > 0 && self.cost > 0.0).then(|| self.cost / self.tokens as f64 * 1e6)
+    }
+}
+
+/// SQLite-backed log of backend calls
+pub struct StatsStore {
+    conn: Mutex<Connection>,
+}
+
+impl StatsStore {
+    /// Open or create a statistics database
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open backend stats at {}", path.display()))?;
+
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS backend_calls (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                backend TEXT NOT NULL,
+                success INTEGER NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                tokens INTEGER,
+                cost REAL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_backend_calls_backend ON backend_calls(backend, id);
+            "#,
+        )?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Get the default stats path (~/.config/llm-mux/memory/backend_stats.db)
+    pub fn default_path() -> Result<PathBuf> {
+        let config_dir = dirs::config_dir().context("Could not determine config directory")?;
+
+        let memory_dir = config_dir.join("llm-mux").join("memory");
+        std::fs::create_dir_all(&memory_dir).with_context(|| {
+            format!(
+                "Failed to create memory directory at {}",
+                memory_dir.display()
+            )
+        })?;
+
+        Ok(memory_dir.join("backend_stats.db"))
+    }
+
+    /// Open the stats database at its default location
+    pub fn open_default() -> Result<Self> {
+        Self::open(&Self::default_path()?)
+    }
+
+    /// Record the outcome of a call, dropping the backend's calls older than the window
+    pub fn record(
+        &self,
+        backend: &str,
+        success: bool,
+        duration: Duration,
+        tokens: Option<u32>,
+        cost: Option<f64>,
+    ) -> Result<()> {
+        let mut conn = self.conn.lock().expect("stats lock poisoned");
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO backend_calls (backend, success, duration_ms, tokens, cost, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            (
+                backend,
+                success,
+                duration.as_millis() as i64,
+                tokens,
+                cost,
+                chrono::Utc::now().to_rfc3339(),
+            ),
+        )?;
+        tx.execute(
+            "DELETE FROM backend_calls WHERE backend = ?1 AND id <= (
+                SELECT id FROM backend_calls WHERE backend = ?1
+                ORDER BY id DESC LIMIT 1 OFFSET ?2
+             )",
+            (backend, WINDOW),
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Statistics over a backend's most recent calls
+    pub fn stats(&self, backend: &str) -> Result<BackendStats> {
+        let conn = self.conn.lock().expect("stats lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT success, duration_ms, tokens, cost FROM backend_calls
+             WHERE backend = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let rows = stmt
+            .query_map((backend, WINDOW), |row| {
+                Ok((
+                    row.get::<_, bool>(0)?,
+                    row.get::<_, u64>(1)?,
+                    row.get::<_, Option<u64>>(2)?,
+                    row.get::<_, Option<f64>>(3)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut stats = BackendStats {
+            backend: backend.to_string(),
+            calls: rows.len() as u64,
+            ..Default::default()
+        };
+        let mut latencies = Vec::new();
+
+        for (success, duration_ms, tokens, cost) in rows {
+            if success {
+                stats.successes += 1;
+                latencies.push(duration_ms);
+            }
+            if let Some(tokens) = tokens {
+                stats.tokens += tokens;
+                stats.cost += cost.unwrap_or(0.0);
+            }
+        }
+
+        latencies.sort_unstable();
+        stats.p50_ms = latencies.get(latencies.len() / 2).copied();
+
+        Ok(stats)
+    }
+
+    /// Statistics for every backend that has recorded calls
+    pub fn all(&self) -> Result<Vec<BackendStats>> {
+        let backends: Vec<String> = {
+            let conn = self.conn.lock().expect("stats lock poisoned");
+            let mut stmt =
+                conn.prepare("SELECT DISTINCT backend FROM backend_calls ORDER BY backend")?;
+            stmt.query_map([], |row| row.get(0))?
+                .collect::<Result<_, _>>()?
+        };
+
+        backends.iter().map(|backend| self.stats(backend)).collect()
+    }
+}
+
+/// Wrapper that records every call's outcome, latency and cost
+pub struct StatsExecutor<T: BackendExecutor> {
+    inner: T,
+    store: Arc<StatsStore>,
+    cost_per_mtok: Option<f64>,
+}
+
+impl<T: BackendExecutor> StatsExecutor<T> {
+    /// Create a new recording executor
+    pub fn new(inner: T, store: Arc<StatsStore>, config: &BackendConfig) -> Self {
+        Self {
+            inner,
+            store,
+            cost_per_mtok: config.cost_per_mtok,
+        }
+    }
+}
+
+#[async_trait]
+impl<T: BackendExecutor + 'static> BackendExecutor for StatsExecutor<T> {
+    async fn execute(&self, request: &BackendRequest) -> Result<BackendResponse, BackendError> {
+        let start = Instant::now();
+        let result = self.inner.execute(request).await;
+
+        let tokens = result.as_ref().ok().and_then(|response| {
+            let usage = response.usage.as_ref()?;
+            usage
+                .total_tokens
+                .or_else(|| Some(usage.prompt_tokens? + usage.completion_tokens.unwrap_or(0)))
+        });
+        let cost = tokens
+            .zip(self.cost_per_mtok)
+            .map(|(tokens, price)| f64::from(tokens) * price / 1e6);
+
+        if let Err(e) = self.store.record(
+            self.inner.name(),
+            result.is_ok(),
+            start.elapsed(),
+            tokens,
+            cost,
+        ) {
+            tracing::warn!(backend = %self.inner.name(), error = %e, "Failed to record backend stats");
+        }
+
+        result
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn is_available(&self) -> bool {
+        self.inner.is_available().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend_executor::types::TokenUsage;
+    use tempfile::TempDir;
+
+    /// Mock backend reporting fixed token usage
+    struct UsageBackend;
+
+    #[async_trait]
+    impl BackendExecutor for UsageBackend {
+        async fn execute(&self, request: &BackendRequest) -> Result<BackendResponse, BackendError> {
+            if request.prompt == "fail" {
+                return Err(BackendError::network("down"));
+            }
+            Ok(
+                BackendResponse::new("ok".into(), "priced".into(), Duration::ZERO).with_usage(
+                    TokenUsage {
+                        prompt_tokens: Some(400),
+                        completion_tokens: Some(100),
+                        total_tokens: None,
+                    },
+                ),
+            )
+        }
+
+        fn name(&self) -> &str {
+            "priced"
+        }
+    }
+
+    #[test]
+    fn test_stats_summary() {
+        let dir = TempDir::new().unwrap();
+        let store = StatsStore::open(&dir.path().join("stats.db")).unwrap();
+
+        for ms in [100, 300, 200] {
+            store
+                .record("claude", true, Duration::from_millis(ms), None, None)
+                .unwrap();
+        }
+        store
+            .record("claude", false, Duration::from_millis(5000), None, None)
+            .unwrap();
+
+        let stats = store.stats("claude").unwrap();
+        assert_eq!(stats.calls, 4);
+        assert_eq!(stats.success_rate(), Some(0.75));
+        assert_eq!(stats.p50_ms, Some(200));
+        assert!(stats.cost_per_mtok().is_none());
+
+        assert_eq!(store.stats("unknown").unwrap().success_rate(), None);
+        assert_eq!(store.all().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_old_calls_are_pruned() {
+        let dir = TempDir::new().unwrap();
+        let store = StatsStore::open(&dir.path().join("stats.db")).unwrap();
+
+        for _ in 0..WINDOW + 5 {
+            store
+                .record("claude", true, Duration::ZERO, None, None)
+                .unwrap();
+        }
+        store
+            .record("codex", true, Duration::ZERO, None, None)
+            .unwrap();
+
+        let rows = |backend: &str| -> u32 {
+            let conn = store.conn.lock().unwrap();
+            conn.query_row(
+                "SELECT COUNT(*) FROM backend_calls WHERE backend = ?1",
+                [backend],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(rows("claude"), WINDOW);
+        assert_eq!(rows("codex"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_executor_records_cost() {
+        let dir = TempDir::new().unwrap();
+        let store = Arc::new(StatsStore::open(&dir.path().join("stats.db")).unwrap());
+        let config = BackendConfig {
+            cost_per_mtok: Some(3.0),
+            ..Default::default()
+        };
+        let executor = StatsExecutor::new(UsageBackend, store.clone(), &config);
+
+        executor.execute(&BackendRequest::new("hi")).await.unwrap();
+        assert!(
+            executor
+                .execute(&BackendRequest::new("fail"))
+                .await
+                .is_err()
+        );
+
+        let stats = store.stats("priced").unwrap();
+        assert_eq!(stats.calls, 2);
+        assert_eq!(stats.successes, 1);
+        assert_eq!(stats.tokens, 500);
+        assert!((stats.cost_per_mtok().unwrap() - 3.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,591 @@
+//! Local tools that HTTP backends expose to the model
+
+use crate::config::ToolConfig;
+use regex::Regex;
+use serde_json::{Value, json};
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Longest tool result returned to the model, in bytes
+const MAX_OUTPUT: usize = 32 * 1024;
+
+/// Maximum matches returned by `grep` and entries by `list_files`
+const MAX_MATCHES: usize = 200;
+
+/// Calls refused for exceeding the limit before the request fails
+const MAX_REFUSED_CALLS: u32 = 3;
+
+/// How long a `run` tool may take
+const RUN_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Variables `run` tools inherit; the rest of the environment (API keys
+/// included) is withheld from commands the model triggers
+const RUN_ENV: &[&str] = &["PATH", "HOME", "USER", "LANG", "TMPDIR"];
+
+/// Directories skipped when searching the working directory
+const SKIP_DIRS: &[&str] = &["target", "node_modules"];
+
+/// Tool definition as presented to the model
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolSpec {
+    pub name: String,
+    pub description: String,
+    /// JSON Schema of the arguments
+    pub parameters: Value,
+}
+
+/// How a tool is executed
+#[derive(Debug, Clone, PartialEq)]
+enum ToolKind {
+    ReadFile,
+    Grep,
+    ListFiles,
+    Run(String),
+}
+
+/// Tools available to a step, scoped to a working directory
+#[derive(Debug)]
+pub struct Toolbox {
+    tools: Vec<(ToolSpec, ToolKind)>,
+    working_dir: PathBuf,
+    max_calls: u32,
+}
+
+/// Tool calls made by one backend while answering one request
+pub struct ToolSession<'a> {
+    toolbox: &'a Toolbox,
+    calls: u32,
+}
+
+impl ToolSession<'_> {
+    /// Execute a tool call, refusing it once `max_calls` is used up
+    pub async fn call(&mut self, name: &str, arguments: &Value) -> String {
+        self.calls += 1;
+        if self.calls > self.toolbox.max_calls {
+            return format!(
+                "error: tool call limit of {} reached; answer with the information you have",
+                self.toolbox.max_calls
+            );
+        }
+        self.toolbox.call(name, arguments).await
+    }
+
+    /// Returns true once the model has kept calling tools after being told
+    /// the limit was reached
+    pub fn exceeded(&self) -> bool {
+        self.calls > self.toolbox.max_calls.saturating_add(MAX_REFUSED_CALLS)
+    }
+}
+
+impl Toolbox {
+    /// Build a toolbox from a step's tool configs
+    pub fn from_config(configs: &[ToolConfig], working_dir: &Path, max_calls: u32) -> Self {
+        let tools = configs
+            .iter()
+            .filter_map(|config| match (&config.builtin, &config.run) {
+                (Some(builtin), _) => builtin_tool(builtin).map(|(mut spec, kind)| {
+                    if let Some(ref name) = config.name {
+                        spec.name = name.clone();
+                    }
+                    if !config.description.is_empty() {
+                        spec.description = config.description.clone();
+                    }
+                    (spec, kind)
+                }),
+                (None, Some(run)) => Some((
+                    ToolSpec {
+                        name: config.name.clone()?,
+                        description: config.description.clone(),
+                        parameters: config
+                            .parameters
+                            .clone()
+                            .unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+                    },
+                    ToolKind::Run(run.clone()),
+                )),
+                (None, None) => None,
+            })
+            .collect();
+
+        Self {
+            tools,
+            working_dir: working_dir.to_path_buf(),
+            max_calls,
+        }
+    }
+
+    /// Definitions to send to the model
+    pub fn specs(&self) -> impl Iterator<Item = &ToolSpec> {
+        self.tools.iter().map(|(spec, _)| spec)
+    }
+
+    /// Returns true if the toolbox has no tools
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// Start counting calls for one request
+    pub fn session(&self) -> ToolSession<'_> {
+        ToolSession {
+            toolbox: self,
+            calls: 0,
+        }
+    }
+
+    /// Execute a tool call and return the text to send back to the model
+    ///
+    /// Failures are reported to the model as text rather than failing the
+    /// request, so it can correct itself.
+    pub async fn call(&self, name: &str, arguments: &Value) -> String {
+        let Some((spec, kind)) = self.tools.iter().find(|(spec, _)| spec.name == name) else {
+            return format!("error: unknown tool '{}'", name);
+        };
+
+        tracing::debug!(tool = %name, arguments = %arguments, "Calling tool");
+
+        let result = match kind {
+            ToolKind::ReadFile => self.read_file(arguments),
+            ToolKind::Grep => self.grep(arguments),
+            ToolKind::ListFiles => self.list_files(arguments),
+            ToolKind::Run(command) => self.run(command, &spec.parameters, arguments).await,
+        };
+
+        truncate(result.unwrap_or_else(|e| format!("error: {}", e)))
+    }
+
+    /// Resolve a model-supplied path, refusing anything outside the working directory
+    ///
+    /// Symlinks are followed before checking, so a link can't point outside.
+    fn resolve(&self, path: &str) -> Result<PathBuf, String> {
+        let outside = || format!("path '{}' is outside the working directory", path);
+        let relative = Path::new(path);
+        if relative.is_absolute()
+            || relative
+                .components()
+                .any(|c| matches!(c, Component::ParentDir))
+        {
+            return Err(outside());
+        }
+
+        let root = self
+            .working_dir
+            .canonicalize()
+            .map_err(|e| format!("cannot access working directory: {}", e))?;
+        let resolved = self
+            .working_dir
+            .join(relative)
+            .canonicalize()
+            .map_err(|e| format!("cannot access '{}': {}", path, e))?;
+        let inside = resolved.strip_prefix(&root).map_err(|_| outside())?;
+        Ok(self.working_dir.join(inside))
+    }
+
+    fn read_file(&self, arguments: &Value) -> Result<String, String> {
+        let path = string_arg(arguments, "path")?;
+        std::fs::read_to_string(self.resolve(path)?)
+            .map_err(|e| format!("cannot read '{}': {}", path, e))
+    }
+
+    fn grep(&self, arguments: &Value) -> Result<String, String> {
+        let pattern = string_arg(arguments, "pattern")?;
+        let regex = Regex::new(pattern).map_err(|e| format!("invalid pattern: {}", e))?;
+        let root = self.resolve(arguments["path"].as_str().unwrap_or("."))?;
+
+        let mut matches = Vec::new();
+        for file in walk(&root) {
+            let Ok(content) = std::fs::read_to_string(&file) else {
+                continue; // binary or unreadable
+            };
+            for (number, line) in content.lines().enumerate() {
+                if regex.is_match(line) {
+                    matches.push(format!("{}:{}:{}", self.display(&file), number + 1, line));
+                    if matches.len() >= MAX_MATCHES {
+                        matches.push("(more matches omitted)".into());
+                        return Ok(matches.join("\n"));
+                    }
+                }
+            }
+        }
+
+        if matches.is_empty() {
+            Ok("no matches".into())
+        } else {
+            Ok(matches.join("\n"))
+        }
+    }
+
+    fn list_files(&self, arguments: &Value) -> Result<String, String> {
+        let root = self.resolve(arguments["path"].as_str().unwrap_or("."))?;
+        if !root.is_dir() {
+            return Err(format!("'{}' is not a directory", self.display(&root)));
+        }
+
+        let mut files: Vec<String> = walk(&root).iter().map(|f| self.display(f)).collect();
+        files.sort();
+        if files.len() > MAX_MATCHES {
+            files.truncate(MAX_MATCHES);
+            files.push("(more files omitted)".into());
+        }
+        Ok(files.join("\n"))
+    }
+
+    /// Run a configured command, passing each declared argument as `ARG_<NAME>`
+    ///
+    /// Only `RUN_ENV` is inherited from the environment.
+    async fn run(
+        &self,
+        command: &str,
+        parameters: &Value,
+        arguments: &Value,
+    ) -> Result<String, String> {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(command)
+            .current_dir(&self.working_dir)
+            .env_clear()
+            .envs(
+                RUN_ENV
+                    .iter()
+                    .filter_map(|name| Some((name, std::env::var_os(name)?))),
+            )
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+
+        if let Some(arguments) = arguments.as_object() {
+            for (key, value) in arguments {
+                if parameters["properties"].get(key).is_none() {
+                    return Err(format!("unknown argument '{}'", key));
+                }
+                let value = match value {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                cmd.env(env_name(key), value);
+            }
+        }
+
+        let output = tokio::time::timeout(RUN_TIMEOUT, cmd.output())
+            .await
+            .map_err(|_| format!("command timed out after {:?}", RUN_TIMEOUT))?
+            .map_err(|e| format!("failed to run command: {}", e))?;
+
+        let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stderr.trim().is_empty() {
+            text.push_str(&stderr);
+        }
+        if !output.status.success() {
+            text.push_str(&format!("\n(exit code {:?})", output.status.code()));
+        }
+        Ok(text)
+    }
+
+    /// Path relative to the working directory, for display
+    fn display(&self, path: &Path) -> String {
+        path.strip_prefix(&self.working_dir)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    }
+}
+
+/// Spec and kind of a built-in tool
+fn builtin_tool(name: &str) -> Option<(ToolSpec, ToolKind)> {
+    let (description, parameters, kind) = match name {
+        "read_file" => (
+            "Read a file from the project, by path relative to the project root",
+            json!({
+                "type": "object",
+                "properties": { "path": { "type": "string" } },
+                "required": ["path"]
+            }),
+            ToolKind::ReadFile,
+        ),
+        "grep" => (
+            "Search project files for lines matching a regular expression",
+            json!({
+                "type": "object",
+                "properties": {
+                    "pattern": { "type": "string" },
+                    "path": { "type": "string", "description": "Directory or file to search (default: project root)" }
+                },
+                "required": ["pattern"]
+            }),
+            ToolKind::Grep,
+        ),
+        "list_files" => (
+            "List files in a project directory, recursively",
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Directory to list (default: project root)" }
+                }
+            }),
+            ToolKind::ListFiles,
+        ),
+        _ => return None,
+    };
+
+    Some((
+        ToolSpec {
+            name: name.to_string(),
+            description: description.to_string(),
+            parameters,
+        },
+        kind,
+    ))
+}
+
+/// Required string argument of a tool call
+fn string_arg<'a>(arguments: &'a Value, name: &str) -> Result<&'a str, String> {
+    arguments[name]
+        .as_str()
+        .ok_or_else(|| format!("missing string argument '{}'", name))
+}
+
+/// Environment variable a `run` tool argument is passed in
+fn env_name(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("ARG_{}", name)
+}
+
+/// Files under `root`, skipping hidden and build directories and symlinks
+fn walk(root: &Path) -> Vec<PathBuf> {
+    if root.is_file() {
+        return vec![root.to_path_buf()];
+    }
+
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with('.') || SKIP_DIRS.contains(&name.as_ref()) {
+                continue;
+            }
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+    }
+    files
+}
+
+/// Cap tool output so one call can't flood the context window
+fn truncate(mut text: String) -> String {
+    if text.len() > MAX_OUTPUT {
+        let mut end = MAX_OUTPUT;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str("\n(output truncated)");
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn toolbox(dir: &TempDir) -> Toolbox {
+        let configs = vec![
+            ToolConfig {
+                builtin: Some("read_file".into()),
+                ..Default::default()
+            },
+            ToolConfig {
+                builtin: Some("grep".into()),
+                ..Default::default()
+            },
+            ToolConfig {
+                name: Some("shout".into()),
+                description: "Upper-case a word".into(),
+                run: Some("echo \"$ARG_WORD\" | tr a-z A-Z".into()),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": { "word": { "type": "string" } }
+                })),
+                ..Default::default()
+            },
+        ];
+        Toolbox::from_config(&configs, dir.path(), 5)
+    }
+
+    #[tokio::test]
+    async fn test_builtin_tools() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(
+            dir.path().join("src/lib.rs"),
+            "fn main() {}\nfn helper() {}\n",
+        )
+        .unwrap();
+        let tools = toolbox(&dir);
+
+        assert_eq!(
+            tools.specs().map(|s| s.name.as_str()).collect::<Vec<_>>(),
+            vec!["read_file", "grep", "shout"]
+        );
+        assert_eq!(
+            tools
+                .call("read_file", &json!({ "path": "src/lib.rs" }))
+                .await,
+            "fn main() {}\nfn helper() {}\n"
+        );
+        assert_eq!(
+            tools.call("grep", &json!({ "pattern": "fn h" })).await,
+            "src/lib.rs:2:fn helper() {}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tools_stay_in_working_dir() {
+        let dir = TempDir::new().unwrap();
+        let tools = toolbox(&dir);
+
+        for path in ["/etc/passwd", "../secret"] {
+            let result = tools.call("read_file", &json!({ "path": path })).await;
+            assert!(
+                result.contains("outside the working directory"),
+                "{}",
+                result
+            );
+        }
+        assert!(
+            tools
+                .call("delete_everything", &json!({}))
+                .await
+                .starts_with("error: unknown tool")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_limit() {
+        let dir = TempDir::new().unwrap();
+        let tools = toolbox(&dir);
+        let mut session = tools.session();
+
+        for _ in 0..5 {
+            assert_eq!(
+                session.call("shout", &json!({ "word": "a" })).await.trim(),
+                "A"
+            );
+        }
+        assert!(
+            session
+                .call("shout", &json!({ "word": "a" }))
+                .await
+                .contains("limit of 5 reached")
+        );
+        assert!(!session.exceeded());
+        for _ in 0..3 {
+            session.call("shout", &json!({ "word": "a" })).await;
+        }
+        assert!(session.exceeded());
+
+        // Each request gets its own budget
+        assert!(!tools.session().exceeded());
+    }
+
+    #[tokio::test]
+    async fn test_run_tool_receives_arguments() {
+        let dir = TempDir::new().unwrap();
+        let tools = toolbox(&dir);
+
+        let result = tools.call("shout", &json!({ "word": "hello" })).await;
+        assert_eq!(result.trim(), "HELLO");
+
+        // Only declared arguments are exported, so the model can't set PATH
+        let result = tools
+            .call("shout", &json!({ "word": "hi", "path": "/tmp" }))
+            .await;
+        assert_eq!(result, "error: unknown argument 'path'");
+        assert_eq!(env_name("dry-run"), "ARG_DRY_RUN");
+    }
+
+    #[tokio::test]
+    async fn test_run_tool_environment_is_restricted() {
+        let dir = TempDir::new().unwrap();
+        let tools = Toolbox::from_config(
+            &[ToolConfig {
+                name: Some("env".into()),
+                run: Some("env".into()),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": { "word": { "type": "string" } }
+                })),
+                ..Default::default()
+            }],
+            dir.path(),
+            5,
+        );
+
+        // Cargo sets CARGO_* for the test process; none of it reaches the tool
+        assert!(std::env::var_os("CARGO_MANIFEST_DIR").is_some());
+        let output = tools.call("env", &json!({ "word": "hi" })).await;
+        let names: Vec<&str> = output
+            .lines()
+            .filter_map(|line| line.split_once('=').map(|(name, _)| name))
+            .collect();
+        assert!(names.contains(&"ARG_WORD"), "{}", output);
+        for name in names {
+            // The shell adds PWD, SHLVL and _ itself
+            assert!(
+                RUN_ENV.contains(&name) || ["ARG_WORD", "PWD", "SHLVL", "_"].contains(&name),
+                "tool saw {}",
+                name
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlinks_stay_in_working_dir() {
+        let outside = TempDir::new().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "secret too").unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("escape")).unwrap();
+        std::os::unix::fs::symlink(
+            outside.path().join("secret.txt"),
+            dir.path().join("link.txt"),
+        )
+        .unwrap();
+        let tools = toolbox(&dir);
+
+        for path in ["escape/secret.txt", "link.txt"] {
+            let result = tools.call("read_file", &json!({ "path": path })).await;
+            assert!(
+                result.contains("outside the working directory"),
+                "{}",
+                result
+            );
+        }
+        assert_eq!(
+            tools.call("grep", &json!({ "pattern": "secret" })).await,
+            "notes.txt:1:secret too"
+        );
+    }
+}
@@ -2,10 +2,12 @@
 
 //! Core types and traits for backend execution
 
-use crate::config::BackendConfig;
+use super::tools::Toolbox;
+use crate::config::{BackendConfig, ErrorClass};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
 
@@ -63,6 +65,20 @@ impl BackendError {
         )
     }
 
+    /// Classify this error
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            BackendError::Timeout { .. } => ErrorClass::Timeout,
+            BackendError::RateLimit { .. } => ErrorClass::RateLimit,
+            BackendError::Auth { .. } => ErrorClass::Auth,
+            BackendError::Network { .. } => ErrorClass::Network,
+            BackendError::Parse { .. } => ErrorClass::Parse,
+            BackendError::ExecutionFailed { .. } => ErrorClass::ExecutionFailed,
+            BackendError::Unavailable { .. } => ErrorClass::Unavailable,
+            BackendError::Config { .. } => ErrorClass::Config,
+        }
+    }
+
     /// Get suggested retry delay for rate limit errors
     pub fn retry_after(&self) -> Option<Duration> {
         match self {
@@ -135,6 +151,10 @@ pub struct BackendResponse {
 
     /// Token usage (if available)
     pub usage: Option<TokenUsage>,
+
+    /// Whether this response was served from the response cache
+    #[serde(default)]
+    pub cached: bool,
 }
 
 /// Token usage information
@@ -155,6 +175,7 @@ impl BackendResponse {
             model: None,
             duration,
             usage: None,
+            cached: false,
         }
     }
 
@@ -177,12 +198,80 @@ impl BackendResponse {
     }
 }
 
+/// Speaker of a conversation turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageRole {
+    System,
+    User,
+    Assistant,
+}
+
+impl MessageRole {
+    /// Role name as sent to chat APIs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageRole::System => "system",
+            MessageRole::User => "user",
+            MessageRole::Assistant => "assistant",
+        }
+    }
+
+    /// Capitalized role name for flattened transcripts
+    pub fn label(&self) -> &'static str {
+        match self {
+            MessageRole::System => "System",
+            MessageRole::User => "User",
+            MessageRole::Assistant => "Assistant",
+        }
+    }
+}
+
+/// A single conversation turn
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Message {
+    pub role: MessageRole,
+    pub content: String,
+}
+
+impl Message {
+    /// Create a system turn
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: MessageRole::System,
+            content: content.into(),
+        }
+    }
+
+    /// Create a user turn
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: MessageRole::User,
+            content: content.into(),
+        }
+    }
+
+    /// Create an assistant turn
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self {
+            role: MessageRole::Assistant,
+            content: content.into(),
+        }
+    }
+}
+
+/// Default transcript line format for backends without native turns
+pub const DEFAULT_TRANSCRIPT_FORMAT: &str = "{role}: {content}";
+
 /// Request to execute against a backend
 #[derive(Debug, Clone)]
 pub struct BackendRequest {
-    /// The prompt to send
+    /// The prompt to send (the final user turn)
     pub prompt: String,
 
+    /// Earlier conversation turns, sent before `prompt`
+    pub messages: Vec<Message>,
+
     /// Context files to include (backend-specific handling)
     pub context_files: Vec<PathBuf>,
 
@@ -194,6 +283,22 @@ pub struct BackendRequest {
 
     /// System prompt (if supported)
     pub system_prompt: Option<String>,
+
+    /// Response cache behaviour
+    pub cache: CacheMode,
+
+    /// Local tools the model may call (HTTP and Claude API backends)
+    pub tools: Option<Arc<Toolbox>>,
+}
+
+/// Response cache behaviour for a request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Always query the backend
+    #[default]
+    Disabled,
+    /// Serve from cache when possible, with an optional time-to-live
+    Enabled { ttl: Option<Duration> },
 }
 
 impl BackendRequest {
@@ -201,10 +306,13 @@ impl BackendRequest {
     pub fn new(prompt: impl Into<String>) -> Self {
         Self {
             prompt: prompt.into(),
+            messages: Vec::new(),
             context_files: Vec::new(),
             working_dir: None,
             timeout: None,
             system_prompt: None,
+            cache: CacheMode::Disabled,
+            tools: None,
         }
     }
 
@@ -231,6 +339,57 @@ impl BackendRequest {
         self.system_prompt = Some(prompt.into());
         self
     }
+
+    /// Enable the response cache, optionally expiring entries after `ttl`
+    pub fn with_cache(mut self, ttl: Option<Duration>) -> Self {
+        self.cache = CacheMode::Enabled { ttl };
+        self
+    }
+
+    /// Set earlier conversation turns
+    pub fn with_messages(mut self, messages: Vec<Message>) -> Self {
+        self.messages = messages;
+        self
+    }
+
+    /// Let the model call local tools
+    pub fn with_tools(mut self, tools: Arc<Toolbox>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    /// Full conversation: system prompt, earlier turns, then the prompt
+    pub fn conversation(&self) -> Vec<Message> {
+        let mut conversation = Vec::with_capacity(self.messages.len() + 2);
+        if let Some(ref system) = self.system_prompt {
+            conversation.push(Message::system(system.clone()));
+        }
+        conversation.extend(self.messages.iter().cloned());
+        conversation.push(Message::user(self.prompt.clone()));
+        conversation
+    }
+
+    /// Flatten the conversation into a single prompt
+    ///
+    /// Each turn is rendered with `format`, where `{role}` becomes the
+    /// capitalized role and `{content}` the text; turns are separated by a
+    /// blank line. A plain prompt (no system prompt or earlier turns) is
+    /// returned unchanged.
+    pub fn transcript(&self, format: &str) -> String {
+        if self.messages.is_empty() && self.system_prompt.is_none() {
+            return self.prompt.clone();
+        }
+
+        self.conversation()
+            .iter()
+            .map(|m| {
+                format
+                    .replace("{role}", m.role.label())
+                    .replace("{content}", &m.content)
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
 }
 
 /// Trait for backend executors
@@ -281,6 +440,9 @@ pub struct RetryPolicy {
 
     /// Whether to add jitter to delays
     pub jitter: bool,
+
+    /// Cap on server-requested (Retry-After) delays
+    pub max_retry_after: Duration,
 }
 
 impl Default for RetryPolicy {
@@ -291,6 +453,7 @@ impl Default for RetryPolicy {
             max_delay: Duration::from_secs(60),
             backoff_multiplier: 2.0,
             jitter: true,
+            max_retry_after: Duration::from_secs(120),
         }
     }
 }
@@ -301,6 +464,7 @@ impl RetryPolicy {
         Self {
             max_retries: config.max_retries,
             initial_delay: Duration::from_millis(config.retry_delay),
+            max_retry_after: Duration::from_secs(config.max_retry_wait),
             ..Default::default()
         }
     }
@@ -367,6 +531,34 @@ mod tests {
         assert!(request.system_prompt.is_some());
     }
 
+    #[test]
+    fn test_backend_request_transcript() {
+        let request = BackendRequest::new("And in Rust?")
+            .with_system_prompt("Be brief")
+            .with_messages(vec![
+                Message::user("How do I read a file in Python?"),
+                Message::assistant("open(path).read()"),
+            ]);
+
+        assert_eq!(request.conversation().len(), 4);
+        assert_eq!(request.conversation()[0].role, MessageRole::System);
+        assert_eq!(
+            request.transcript(DEFAULT_TRANSCRIPT_FORMAT),
+            "System: Be brief\n\nUser: How do I read a file in Python?\n\n\
+             Assistant: open(path).read()\n\nUser: And in Rust?"
+        );
+        assert_eq!(
+            request.transcript("<{role}>{content}").lines().last(),
+            Some("<User>And in Rust?")
+        );
+
+        // Single-turn requests are sent as-is
+        assert_eq!(
+            BackendRequest::new("hi").transcript(DEFAULT_TRANSCRIPT_FORMAT),
+            "hi"
+        );
+    }
+
     #[test]
     fn test_retry_policy_delays() {
         let policy = RetryPolicy {
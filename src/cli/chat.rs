@@ -0,0 +1,599 @@
+//! Interactive chat sessions against a role
+//!
+//! `llmux chat --role <name>` keeps a conversation with one backend of the
+//! role, saving the session after every turn so it can be resumed later.
+
+use crate::backend_executor::{BackendRequest, Message, MessageRole, StatsStore};
+use crate::config::{LlmuxConfig, RoleExecution};
+use crate::role::{ResolvedRole, RoleExecutor, detect_team, resolve_role};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// Options for `llmux chat`
+#[derive(Debug, Default)]
+pub struct ChatOptions<'a> {
+    /// Role to chat with (defaults to the resumed session's role)
+    pub role: Option<&'a str>,
+    /// Team override
+    pub team_override: Option<&'a str>,
+    /// Backend to start on instead of the role's first available one
+    pub backend: Option<&'a str>,
+    /// Session to resume (`latest` for the most recent)
+    pub resume: Option<&'a str>,
+}
+
+/// Token usage accumulated over a session
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SessionUsage {
+    /// Completed exchanges
+    pub turns: u32,
+    /// Prompt tokens, where the backend reported them
+    pub prompt_tokens: u64,
+    /// Completion tokens, where the backend reported them
+    pub completion_tokens: u64,
+    /// Turns whose backend reported no usage
+    pub unreported_turns: u32,
+}
+
+/// A saved chat session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatSession {
+    /// Session id (also the file name)
+    pub id: String,
+    /// Role being chatted with
+    pub role: String,
+    /// Team the role was resolved for
+    pub team: Option<String>,
+    /// Backend the conversation is pinned to
+    pub backend: Option<String>,
+    /// Conversation so far
+    pub messages: Vec<Message>,
+    /// Token usage so far
+    #[serde(default)]
+    pub usage: SessionUsage,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ChatSession {
+    /// Start a new session
+    ///
+    /// The id is the start time plus a random suffix, so sessions started in
+    /// the same second don't overwrite each other.
+    pub fn new(role: impl Into<String>, team: Option<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: format!(
+                "{}-{:06x}",
+                now.format("%Y%m%d-%H%M%S"),
+                rand::random::<u32>() & 0xff_ffff
+            ),
+            role: role.into(),
+            team,
+            backend: None,
+            messages: Vec::new(),
+            usage: SessionUsage::default(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Get the default sessions directory (~/.config/llm-mux/sessions)
+    pub fn default_dir() -> Result<PathBuf> {
+        let config_dir = dirs::config_dir().context("Could not determine config directory")?;
+        Ok(config_dir.join("llm-mux").join("sessions"))
+    }
+
+    /// Write the session to `<dir>/<id>.json`
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create sessions directory at {}", dir.display()))?;
+        let path = dir.join(format!("{}.json", self.id));
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to save session to {}", path.display()))
+    }
+
+    /// Load a session by id, or the most recently updated one for `latest`
+    pub fn load(dir: &Path, id: &str) -> Result<Self> {
+        if id == "latest" {
+            return Self::list(dir)?
+                .into_iter()
+                .next()
+                .context("No saved chat sessions");
+        }
+
+        let path = dir.join(format!("{}.json", id));
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("No chat session '{}'", id))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse session {}", path.display()))
+    }
+
+    /// All saved sessions, most recently updated first
+    pub fn list(dir: &Path) -> Result<Vec<Self>> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Ok(Vec::new());
+        };
+
+        let mut sessions: Vec<Self> = entries
+            .flatten()
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|e| {
+                let content = std::fs::read_to_string(e.path()).ok()?;
+                serde_json::from_str(&content).ok()
+            })
+            .collect();
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+        Ok(sessions)
+    }
+
+    /// Render the conversation as Markdown
+    pub fn transcript(&self) -> String {
+        let mut out = format!("# Chat with {} ({})\n", self.role, self.id);
+        for message in &self.messages {
+            let speaker = match message.role {
+                MessageRole::Assistant => self.backend.as_deref().unwrap_or("assistant"),
+                role => role.as_str(),
+            };
+            out.push_str(&format!(
+                "\n## {}\n\n{}\n",
+                speaker,
+                message.content.trim_end()
+            ));
+        }
+        out
+    }
+}
+
+/// A slash command typed at the chat prompt
+#[derive(Debug, Clone, PartialEq)]
+enum ChatCommand {
+    Help,
+    Backend(Option<String>),
+    Attach(String),
+    Usage,
+    Save(Option<String>),
+    Clear,
+    Exit,
+    Unknown(String),
+}
+
+impl ChatCommand {
+    /// Parse a line starting with `/`; other lines are messages
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.strip_prefix('/')?;
+        let (name, arg) = match line.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, Some(arg.trim().to_string()).filter(|a| !a.is_empty())),
+            None => (line, None),
+        };
+
+        Some(match (name, arg) {
+            ("help" | "?", _) => Self::Help,
+            ("backend", arg) => Self::Backend(arg),
+            ("attach", Some(path)) => Self::Attach(path),
+            ("usage", _) => Self::Usage,
+            ("save", arg) => Self::Save(arg),
+            ("clear", _) => Self::Clear,
+            ("exit" | "quit", _) => Self::Exit,
+            (name, _) => Self::Unknown(name.to_string()),
+        })
+    }
+}
+
+const HELP: &str = "\
+/backend [name]  show or switch the backend
+/attach <path>   include a file in the next message
+/usage           show token usage
+/save [path]     write the transcript as Markdown
+/clear           start over (keeps the session id)
+/exit            leave (also Ctrl-D)";
+
+/// Run an interactive chat on stdin/stdout
+pub async fn chat(
+    config: Arc<LlmuxConfig>,
+    working_dir: &Path,
+    options: ChatOptions<'_>,
+) -> Result<i32, String> {
+    let sessions_dir = ChatSession::default_dir().map_err(|e| e.to_string())?;
+
+    let mut executor = RoleExecutor::new(config.clone());
+    match StatsStore::open_default() {
+        Ok(stats) => executor = executor.with_stats(Arc::new(stats)),
+        Err(e) => tracing::warn!(error = %e, "Backend statistics unavailable"),
+    }
+
+    let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+    let mut stdout = std::io::stdout();
+    run_chat(
+        &config,
+        &executor,
+        working_dir,
+        &sessions_dir,
+        options,
+        stdin,
+        &mut stdout,
+    )
+    .await
+}
+
+/// List saved chat sessions
+pub fn list_sessions(out: &mut impl Write) -> Result<i32, String> {
+    let dir = ChatSession::default_dir().map_err(|e| e.to_string())?;
+    let sessions = ChatSession::list(&dir).map_err(|e| e.to_string())?;
+
+    if sessions.is_empty() {
+        let _ = writeln!(out, "No saved chat sessions");
+    }
+    for session in sessions {
+        let preview: String = session
+            .messages
+            .iter()
+            .find(|m| m.role == MessageRole::User)
+            .map(|m| {
+                m.content
+                    .lines()
+                    .next()
+                    .unwrap_or("")
+                    .chars()
+                    .take(60)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "{}  {:<12} {:>3} turns  {}",
+            session.id, session.role, session.usage.turns, preview
+        );
+    }
+    Ok(0)
+}
+
+/// Chat loop over any input, so it can be driven by tests
+async fn run_chat<R: AsyncBufRead + Unpin>(
+    config: &LlmuxConfig,
+    executor: &RoleExecutor,
+    working_dir: &Path,
+    sessions_dir: &Path,
+    options: ChatOptions<'_>,
+    input: R,
+    out: &mut impl Write,
+) -> Result<i32, String> {
+    let team = detect_team(working_dir, &config.teams, options.team_override);
+
+    let mut session = match options.resume {
+        Some(id) => {
+            let session = ChatSession::load(sessions_dir, id).map_err(|e| e.to_string())?;
+            let _ = writeln!(
+                out,
+                "Resumed session {} with {} ({} turns)",
+                session.id, session.role, session.usage.turns
+            );
+            session
+        }
+        None => {
+            let role = options
+                .role
+                .ok_or("--role is required unless resuming a session")?;
+            ChatSession::new(role, team.clone())
+        }
+    };
+    if let Some(role) = options.role {
+        session.role = role.to_string();
+    }
+    if let Some(backend) = options.backend {
+        pin_backend(config, &mut session, backend)?;
+    }
+
+    let role =
+        resolve_role(&session.role, session.team.as_deref(), config).map_err(|e| e.to_string())?;
+
+    let _ = writeln!(
+        out,
+        "Chatting with {} [{}]. Type /help for commands.",
+        session.role,
+        session
+            .backend
+            .clone()
+            .unwrap_or_else(|| role.backends.join(", "))
+    );
+
+    let mut attachments = Vec::new();
+    let mut lines = input.lines();
+
+    loop {
+        let _ = write!(out, "> ");
+        let _ = out.flush();
+
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => return Err(format!("Failed to read input: {}", e)),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(command) = ChatCommand::parse(line) {
+            match command {
+                ChatCommand::Help => {
+                    let _ = writeln!(out, "{}", HELP);
+                }
+                ChatCommand::Backend(None) => {
+                    let _ = writeln!(
+                        out,
+                        "backend: {} (role backends: {})",
+                        session.backend.as_deref().unwrap_or("not chosen yet"),
+                        role.backends.join(", ")
+                    );
+                }
+                ChatCommand::Backend(Some(name)) => {
+                    match pin_backend(config, &mut session, &name) {
+                        Ok(()) => {
+                            let _ = writeln!(out, "Switched to {}", name);
+                        }
+                        Err(e) => {
+                            let _ = writeln!(out, "error: {}", e);
+                        }
+                    }
+                }
+                ChatCommand::Attach(path) => match std::fs::read_to_string(working_dir.join(&path))
+                {
+                    Ok(content) => {
+                        let _ = writeln!(out, "Attached {} ({} bytes)", path, content.len());
+                        attachments.push(format!("File: {}\n```\n{}\n```", path, content));
+                    }
+                    Err(e) => {
+                        let _ = writeln!(out, "error: cannot read {}: {}", path, e);
+                    }
+                },
+                ChatCommand::Usage => {
+                    let usage = &session.usage;
+                    let _ = writeln!(
+                        out,
+                        "{} turns, {} prompt + {} completion tokens",
+                        usage.turns, usage.prompt_tokens, usage.completion_tokens
+                    );
+                    if usage.unreported_turns > 0 {
+                        let _ = writeln!(
+                            out,
+                            "({} turns from backends that don't report usage)",
+                            usage.unreported_turns
+                        );
+                    }
+                }
+                ChatCommand::Save(path) => {
+                    let path =
+                        working_dir.join(path.unwrap_or_else(|| format!("{}.md", session.id)));
+                    match std::fs::write(&path, session.transcript()) {
+                        Ok(()) => {
+                            let _ = writeln!(out, "Saved transcript to {}", path.display());
+                        }
+                        Err(e) => {
+                            let _ = writeln!(out, "error: cannot write {}: {}", path.display(), e);
+                        }
+                    }
+                }
+                ChatCommand::Clear => {
+                    session.messages.clear();
+                    attachments.clear();
+                    let _ = writeln!(out, "Conversation cleared");
+                }
+                ChatCommand::Exit => break,
+                ChatCommand::Unknown(name) => {
+                    let _ = writeln!(out, "unknown command /{} (try /help)", name);
+                }
+            }
+            continue;
+        }
+
+        // Attachments go ahead of the message they were attached to
+        let mut content = attachments.join("\n\n");
+        if !content.is_empty() {
+            content.push_str("\n\n");
+        }
+        content.push_str(line);
+
+        let request = BackendRequest::new(content.clone())
+            .with_messages(session.messages.clone())
+            .with_working_dir(working_dir.to_path_buf());
+
+        match executor
+            .execute(&chat_role(&role, &session), &request)
+            .await
+        {
+            Ok(result) => {
+                let reply = result.output.unwrap_or_default();
+                let _ = writeln!(out, "{}", reply.trim_end());
+
+                attachments.clear();
+                session.messages.push(Message::user(content));
+                session.messages.push(Message::assistant(reply));
+                session.backend = result.succeeded.first().cloned().or(session.backend);
+
+                session.usage.turns += 1;
+                match result.usage {
+                    Some(usage) => {
+                        session.usage.prompt_tokens += u64::from(usage.prompt_tokens.unwrap_or(0));
+                        session.usage.completion_tokens +=
+                            u64::from(usage.completion_tokens.unwrap_or(0));
+                    }
+                    None => session.usage.unreported_turns += 1,
+                }
+
+                session.updated_at = Utc::now();
+                if let Err(e) = session.save(sessions_dir) {
+                    let _ = writeln!(out, "warning: {}", e);
+                }
+            }
+            Err(e) => {
+                let _ = writeln!(out, "error: {}", e);
+            }
+        }
+    }
+
+    Ok(0)
+}
+
+/// Pin the conversation to a configured, enabled backend
+fn pin_backend(config: &LlmuxConfig, session: &mut ChatSession, name: &str) -> Result<(), String> {
+    match config.backends.get(name) {
+        Some(backend) if backend.enabled => {
+            session.backend = Some(name.to_string());
+            Ok(())
+        }
+        Some(_) => Err(format!("backend '{}' is disabled", name)),
+        None => Err(format!("unknown backend '{}'", name)),
+    }
+}
+
+/// The role as a chat uses it: one backend at a time, pinned once it answers
+fn chat_role(role: &ResolvedRole, session: &ChatSession) -> ResolvedRole {
+    let mut role = role.clone();
+    role.execution = RoleExecution::Fallback;
+    if let Some(ref backend) = session.backend {
+        role.backends = vec![backend.clone()];
+    }
+    role
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BackendConfig, RoleConfig};
+    use tempfile::TempDir;
+
+    fn echo_config() -> LlmuxConfig {
+        let mut config = LlmuxConfig::default();
+        for name in ["echo", "shout"] {
+            let args = if name == "shout" {
+                vec!["-c".into(), "echo \"$1\" | tr a-z A-Z".into(), "sh".into()]
+            } else {
+                Vec::new()
+            };
+            config.backends.insert(
+                name.into(),
+                BackendConfig {
+                    command: if name == "shout" { "sh" } else { "echo" }.into(),
+                    args,
+                    ..Default::default()
+                },
+            );
+        }
+        config.roles.insert(
+            "helper".into(),
+            RoleConfig {
+                backends: vec!["echo".into()],
+                ..Default::default()
+            },
+        );
+        config
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(ChatCommand::parse("hello"), None);
+        assert_eq!(ChatCommand::parse("/exit"), Some(ChatCommand::Exit));
+        assert_eq!(
+            ChatCommand::parse("/backend  codex "),
+            Some(ChatCommand::Backend(Some("codex".into())))
+        );
+        assert_eq!(ChatCommand::parse("/save"), Some(ChatCommand::Save(None)));
+        assert_eq!(
+            ChatCommand::parse("/attach"),
+            Some(ChatCommand::Unknown("attach".into()))
+        );
+    }
+
+    #[test]
+    fn test_session_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let mut older = ChatSession::new("helper", None);
+        older.id = "older".into();
+        older.updated_at = Utc::now() - chrono::Duration::hours(1);
+        older.save(dir.path()).unwrap();
+
+        let mut newer = ChatSession::new("reviewer", Some("rust".into()));
+        // Sessions started in the same second still get their own file
+        assert_ne!(newer.id, ChatSession::new("reviewer", None).id);
+        newer.id = "newer".into();
+        newer.messages.push(Message::user("hi"));
+        newer.save(dir.path()).unwrap();
+
+        assert_eq!(ChatSession::load(dir.path(), "latest").unwrap().id, "newer");
+        let loaded = ChatSession::load(dir.path(), "older").unwrap();
+        assert_eq!(loaded.role, "helper");
+        assert!(ChatSession::load(dir.path(), "missing").is_err());
+        assert!(newer.transcript().contains("## user\n\nhi"));
+    }
+
+    #[tokio::test]
+    async fn test_chat_session() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "remember me").unwrap();
+        let input = "/attach notes.txt\nhello\n/backend shout\nagain\n/usage\n/save chat.md\n";
+        let mut out = Vec::new();
+
+        let options = ChatOptions {
+            role: Some("helper"),
+            ..Default::default()
+        };
+        let config = echo_config();
+        let executor = RoleExecutor::new(Arc::new(config.clone()));
+        let code = run_chat(
+            &config,
+            &executor,
+            dir.path(),
+            &dir.path().join("sessions"),
+            options,
+            input.as_bytes(),
+            &mut out,
+        )
+        .await
+        .unwrap();
+        assert_eq!(code, 0);
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("File: notes.txt\n```\nremember me\n```\n\nhello"));
+        // The second turn replays the first through the new backend
+        assert!(out.contains("ASSISTANT: FILE: NOTES.TXT"));
+        assert!(out.contains("2 turns, 0 prompt + 0 completion tokens"));
+
+        let session = ChatSession::load(&dir.path().join("sessions"), "latest").unwrap();
+        assert_eq!(session.messages.len(), 4);
+        assert_eq!(session.backend.as_deref(), Some("shout"));
+        assert!(
+            std::fs::read_to_string(dir.path().join("chat.md"))
+                .unwrap()
+                .contains("## shout")
+        );
+
+        // Resuming picks up the saved conversation
+        let mut out = Vec::new();
+        let options = ChatOptions {
+            resume: Some("latest"),
+            ..Default::default()
+        };
+        run_chat(
+            &config,
+            &executor,
+            dir.path(),
+            &dir.path().join("sessions"),
+            options,
+            "/usage\n".as_bytes(),
+            &mut out,
+        )
+        .await
+        .unwrap();
+        assert!(
+            String::from_utf8(out)
+                .unwrap()
+                .contains("with helper (2 turns)")
+        );
+    }
+}
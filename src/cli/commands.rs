@@ -1,12 +1,26 @@
 //! CLI command implementations
 
 use super::output::{OutputEvent, OutputHandler};
-use crate::config::{LlmuxConfig, load_workflow};
-use crate::role::detect_team;
-use crate::workflow::WorkflowRunner;
+use crate::backend_executor::{
+    BackendStats, BreakerState, CircuitBreaker, ResponseCache, StatsStore,
+};
+use crate::config::{
+    LlmuxConfig, RoleConfig, StepConfig, StepResult, StepType, WorkflowConfig, check_sub_workflows,
+    load_workflow,
+};
+use crate::eval::{EvalReport, EvalRunner, EvalSuite, EvalTarget};
+use crate::role::{ParallelProgress, RoleError, RoleExecutor, detect_team, resolve_role_for};
+use crate::template::TemplateContext;
+use crate::workflow::{
+    ExecutionContext, StepProgressCallback, TEST_FILE_SUFFIX, WorkflowRunner, discover_tests,
+    execute_step, run_test_file, usage,
+};
+use minijinja::Value;
+use serde::Serialize;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Project type detection and configuration
 #[derive(Debug, Clone, PartialEq)]
@@ -117,35 +131,78 @@ impl ProjectType {
     }
 }
 
+/// Options for a workflow run
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunOptions<'a> {
+    /// Team to use instead of auto-detection
+    pub team_override: Option<&'a str>,
+
+    /// Write the final output to this file
+    pub output_file: Option<&'a Path>,
+
+    /// Bypass the response cache
+    pub no_cache: bool,
+}
+
 /// Run a workflow
 pub async fn run_workflow(
     workflow_name: &str,
     args: Vec<String>,
     working_dir: &Path,
-    team_override: Option<&str>,
     config: Arc<LlmuxConfig>,
     handler: &dyn OutputHandler,
-    output_file: Option<&Path>,
+    options: RunOptions<'_>,
 ) -> Result<i32, String> {
+    let RunOptions {
+        team_override,
+        output_file,
+        no_cache,
+    } = options;
+
     // Load workflow
     let workflow = load_workflow(workflow_name, Some(working_dir))
         .map_err(|e| format!("Failed to load workflow '{}': {}", workflow_name, e))?;
 
-    // Parse workflow args (simple key=value for now)
-    let parsed_args = parse_workflow_args(&args);
+    // Parse workflow args, reading @file and - values
+    let mut parsed_args = parse_workflow_args(&args);
+    load_arg_values(&mut parsed_args, working_dir)?;
 
     handler.emit(OutputEvent::WorkflowStart {
         name: workflow.name.clone(),
         steps: workflow.steps.len(),
     });
 
+    // Forward parallel progress from the runner to the handler
+    let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+    let progress: StepProgressCallback = Arc::new(move |step: &str, p: ParallelProgress| {
+        let _ = progress_tx.send(OutputEvent::ParallelProgress {
+            step: step.to_string(),
+            backends: p.backends,
+            completed: p.completed,
+        });
+    });
+
     // Create runner and execute
-    let runner = WorkflowRunner::new(config.clone());
+    let mut runner = WorkflowRunner::new(config.clone())
+        .with_cache(!no_cache)
+        .with_progress(progress);
+    match StatsStore::open_default() {
+        Ok(stats) => runner = runner.with_stats(Arc::new(stats)),
+        Err(e) => tracing::warn!(error = %e, "Backend statistics unavailable"),
+    }
 
-    let result = runner
-        .run(workflow.clone(), parsed_args, working_dir, team_override)
-        .await
-        .map_err(|e| format!("Workflow execution failed: {}", e))?;
+    let run = runner.run(workflow.clone(), parsed_args, working_dir, team_override);
+    tokio::pin!(run);
+    let result = loop {
+        tokio::select! {
+            result = &mut run => break result,
+            Some(event) = progress_rx.recv() => handler.emit(event),
+        }
+    };
+    while let Ok(event) = progress_rx.try_recv() {
+        handler.emit(event);
+    }
+    let result = result.map_err(|e| format!("Workflow execution failed: {}", e))?;
 
     // Emit completion event
     handler.emit(OutputEvent::WorkflowComplete {
@@ -211,6 +268,436 @@ pub async fn run_workflow(
     Ok(if result.success { 0 } else { 1 })
 }
 
+/// Options for `llmux ask`
+#[derive(Debug, Default)]
+pub struct AskOptions<'a> {
+    /// The question
+    pub prompt: &'a str,
+    /// Role to ask
+    pub role: Option<&'a str>,
+    /// Ask a single backend instead of a role
+    pub backend: Option<&'a str>,
+    /// Ask every backend of the role
+    pub parallel: bool,
+    /// System prompt
+    pub system: Option<&'a str>,
+    /// JSON output schema file to validate the answer against
+    pub schema: Option<&'a Path>,
+    /// Files to include with the question
+    pub context_files: &'a [PathBuf],
+    /// Input piped on stdin
+    pub stdin: Option<String>,
+    /// Team override
+    pub team_override: Option<&'a str>,
+    /// Print the full result as JSON
+    pub json: bool,
+}
+
+/// Result of `llmux ask` in JSON output mode
+#[derive(Debug, Serialize)]
+struct AskResult<'a> {
+    success: bool,
+    output: Option<&'a str>,
+    outputs: &'a HashMap<String, String>,
+    backends: &'a [String],
+    error: Option<&'a str>,
+    duration_ms: u64,
+}
+
+/// Ask a role (or a single backend) a one-off question
+pub async fn ask(
+    config: Arc<LlmuxConfig>,
+    working_dir: &Path,
+    handler: &dyn OutputHandler,
+    options: AskOptions<'_>,
+) -> Result<i32, String> {
+    let stats = match StatsStore::open_default() {
+        Ok(stats) => Some(Arc::new(stats)),
+        Err(e) => {
+            tracing::warn!(error = %e, "Backend statistics unavailable");
+            None
+        }
+    };
+    let result = run_ask(&config, stats, working_dir, &options).await?;
+
+    // Single answer, or every backend's answer under its own heading
+    let combined = result.output.clone().or_else(|| {
+        let mut outputs: Vec<_> = result.outputs.iter().collect();
+        outputs.sort();
+        (!outputs.is_empty()).then(|| {
+            outputs
+                .iter()
+                .map(|(backend, output)| format!("=== {} ===\n{}", backend, output.trim_end()))
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        })
+    });
+
+    if options.json {
+        let ask_result = AskResult {
+            success: !result.failed,
+            output: combined.as_deref(),
+            outputs: &result.outputs,
+            backends: &result.backends,
+            error: result.error.as_deref(),
+            duration_ms: result.duration_ms,
+        };
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&ask_result).map_err(|e| e.to_string())?
+        );
+    } else {
+        if let Some(ref error) = result.error {
+            handler.emit(OutputEvent::StepError {
+                name: "ask".into(),
+                error: error.clone(),
+            });
+        }
+        handler.result(!result.failed, combined.as_deref());
+    }
+
+    Ok(if result.failed { 1 } else { 0 })
+}
+
+/// Run the ad-hoc query step behind `llmux ask`
+async fn run_ask(
+    config: &LlmuxConfig,
+    stats: Option<Arc<StatsStore>>,
+    working_dir: &Path,
+    options: &AskOptions<'_>,
+) -> Result<StepResult, String> {
+    // A single backend is asked through an ad-hoc role of its own
+    let mut config = config.clone();
+    let role = match (options.role, options.backend) {
+        (_, Some(backend)) => {
+            if !config.backends.contains_key(backend) {
+                return Err(format!("Unknown backend '{}'", backend));
+            }
+            let role = format!("backend:{}", backend);
+            config.roles.insert(
+                role.clone(),
+                RoleConfig {
+                    backends: vec![backend.to_string()],
+                    ..Default::default()
+                },
+            );
+            role
+        }
+        (Some(role), None) => role.to_string(),
+        (None, None) => return Err("Either --role or --backend is required".into()),
+    };
+
+    let output_schema = match options.schema {
+        Some(path) => {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read schema {}: {}", path.display(), e))?;
+            Some(
+                serde_json::from_str(&content)
+                    .map_err(|e| format!("Invalid schema {}: {}", path.display(), e))?,
+            )
+        }
+        None => None,
+    };
+
+    // The question is passed as a variable so it is never rendered as a template
+    let input = prompt_with_input(
+        options.prompt,
+        options.context_files,
+        options.stdin.as_deref(),
+        working_dir,
+    )?;
+    let mut template_ctx = TemplateContext::new();
+    template_ctx.set_local("input", Value::from(input));
+    if let Some(system) = options.system {
+        template_ctx.set_local("system", Value::from(system));
+    }
+
+    let step = StepConfig {
+        name: "ask".into(),
+        step_type: StepType::Query,
+        role: Some(role),
+        parallel: options.parallel,
+        prompt: Some("{{ input }}".into()),
+        system_prompt: options.system.map(|_| "{{ system }}".into()),
+        output_schema,
+        ..Default::default()
+    };
+
+    let mut ctx = ExecutionContext::new(Arc::new(config.clone()));
+    if let Some(stats) = stats {
+        ctx = ctx.with_stats(stats);
+    }
+
+    let team = detect_team(working_dir, &config.teams, options.team_override);
+    execute_step(&step, &ctx, &template_ctx, team.as_deref(), working_dir)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Append context files and piped input to a one-off prompt
+pub fn prompt_with_input(
+    prompt: &str,
+    context_files: &[PathBuf],
+    stdin: Option<&str>,
+    working_dir: &Path,
+) -> Result<String, String> {
+    let mut input = prompt.to_string();
+    for file in context_files {
+        let content = std::fs::read_to_string(working_dir.join(file))
+            .map_err(|e| format!("Failed to read context file {}: {}", file.display(), e))?;
+        input.push_str(&format!(
+            "\n\nFile: {}\n```\n{}\n```",
+            file.display(),
+            content
+        ));
+    }
+    if let Some(stdin) = stdin {
+        input.push_str("\n\n");
+        input.push_str(stdin);
+    }
+    Ok(input)
+}
+
+/// How long piped stdin may stay silent before it is taken to be an idle pipe
+const STDIN_WAIT: Duration = Duration::from_millis(500);
+
+/// Read input piped on stdin
+///
+/// With `explicit` (a `-` argument) stdin is always read to the end. Otherwise
+/// a terminal is ignored, and so is a pipe that sends nothing within
+/// `STDIN_WAIT`: under CI, cron or ssh stdin is often an open pipe that never
+/// reaches EOF.
+pub fn read_stdin(explicit: bool) -> Option<String> {
+    use std::io::{IsTerminal, Read};
+
+    let stdin = std::io::stdin();
+    if explicit {
+        let mut input = String::new();
+        stdin.lock().read_to_string(&mut input).ok()?;
+        return (!input.trim().is_empty()).then_some(input);
+    }
+    if stdin.is_terminal() {
+        return None;
+    }
+    read_ready_input(stdin, STDIN_WAIT)
+}
+
+/// Read `reader` to the end if it produces data or EOF within `wait`
+fn read_ready_input<R: std::io::Read + Send + 'static>(
+    mut reader: R,
+    wait: Duration,
+) -> Option<String> {
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    let (done_tx, done_rx) = std::sync::mpsc::channel();
+
+    // A reader that never answers leaves this thread blocked; it doesn't keep
+    // the process alive
+    std::thread::spawn(move || {
+        let mut input = Vec::new();
+        let mut chunk = [0u8; 8192];
+        let mut first = true;
+        loop {
+            let read = reader.read(&mut chunk);
+            if first {
+                first = false;
+                let _ = ready_tx.send(());
+            }
+            match read {
+                Ok(0) | Err(_) => break,
+                Ok(n) => input.extend_from_slice(&chunk[..n]),
+            }
+        }
+        let _ = done_tx.send(String::from_utf8_lossy(&input).into_owned());
+    });
+
+    if ready_rx.recv_timeout(wait).is_err() {
+        tracing::warn!("Ignoring stdin: nothing was piped (pass - to wait for it)");
+        return None;
+    }
+    let input = done_rx.recv().ok()?;
+    (!input.trim().is_empty()).then_some(input)
+}
+
+/// Options for `llmux eval`
+#[derive(Debug, Default)]
+pub struct EvalOptions<'a> {
+    /// Roles to evaluate instead of the suite's targets
+    pub roles: &'a [String],
+    /// Backends to evaluate instead of the suite's targets
+    pub backends: &'a [String],
+    /// Report to compare against (defaults to the suite's last saved run)
+    pub baseline: Option<&'a Path>,
+    /// Don't save the report
+    pub no_save: bool,
+    /// Fail if any target's pass rate is below this percentage
+    pub fail_under: Option<f64>,
+    /// Team override
+    pub team_override: Option<&'a str>,
+    /// Print the report as JSON
+    pub json: bool,
+}
+
+/// Run an eval suite and report pass rates, latency and cost per target
+pub async fn eval(
+    suite_name: &str,
+    config: Arc<LlmuxConfig>,
+    working_dir: &Path,
+    options: EvalOptions<'_>,
+) -> Result<i32, String> {
+    let path = eval_suite_path(suite_name, working_dir);
+    let mut suite = EvalSuite::load(&path).map_err(|e| format!("{:#}", e))?;
+
+    // Targets from the command line replace the suite's
+    if !options.roles.is_empty() || !options.backends.is_empty() {
+        suite.targets = options
+            .roles
+            .iter()
+            .map(|role| EvalTarget {
+                role: Some(role.clone()),
+                ..Default::default()
+            })
+            .chain(options.backends.iter().map(|backend| EvalTarget {
+                backend: Some(backend.clone()),
+                ..Default::default()
+            }))
+            .collect();
+    }
+
+    let mut executor = RoleExecutor::new(config.clone());
+    match StatsStore::open_default() {
+        Ok(stats) => executor = executor.with_stats(Arc::new(stats)),
+        Err(e) => tracing::warn!(error = %e, "Backend statistics unavailable"),
+    }
+    let team = detect_team(working_dir, &config.teams, options.team_override);
+    let runner = EvalRunner::new(config, executor, working_dir.to_path_buf()).with_team(team);
+
+    let json = options.json;
+    let report = runner
+        .run(&suite, |result| {
+            if !json {
+                eprint!("{}", if result.passed { "." } else { "F" });
+            }
+        })
+        .await
+        .map_err(|e| format!("{:#}", e))?;
+    if !json {
+        eprintln!();
+    }
+
+    let reports_dir = EvalReport::default_dir().map_err(|e| e.to_string())?;
+    let baseline = match options.baseline {
+        Some(path) => Some(EvalReport::load(path).map_err(|e| format!("{:#}", e))?),
+        None => EvalReport::latest(&reports_dir, &suite.name).unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "Could not read previous eval reports");
+            None
+        }),
+    };
+
+    let saved = if options.no_save {
+        None
+    } else {
+        Some(report.save(&reports_dir).map_err(|e| format!("{:#}", e))?)
+    };
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?
+        );
+    } else {
+        let mut stdout = std::io::stdout();
+        report.render(&mut stdout);
+        if let Some(ref baseline) = baseline {
+            report.render_comparison(baseline, &mut stdout);
+        }
+        if let Some(path) = saved {
+            println!("\nSaved report to {}", path.display());
+        }
+    }
+
+    let below = options.fail_under.is_some_and(|threshold| {
+        report
+            .targets
+            .iter()
+            .any(|target| target.pass_rate * 100.0 < threshold)
+    });
+    Ok(if below { 1 } else { 0 })
+}
+
+/// Resolve an eval suite argument: a path, or a name under `.llm-mux/evals/`
+fn eval_suite_path(suite: &str, working_dir: &Path) -> PathBuf {
+    let path = working_dir.join(suite);
+    if path.exists() {
+        return path;
+    }
+    working_dir
+        .join(".llm-mux/evals")
+        .join(format!("{}.toml", suite))
+}
+
+/// Run workflow tests (`*.test.toml` next to workflows) and print a summary
+pub async fn test_workflows(
+    targets: &[String],
+    filter: Option<&str>,
+    working_dir: &Path,
+    config: Arc<LlmuxConfig>,
+    json: bool,
+) -> Result<i32, String> {
+    let workflows_dir = working_dir.join(".llm-mux/workflows");
+    let files: Vec<PathBuf> = if targets.is_empty() {
+        discover_tests(&workflows_dir)
+    } else {
+        // A target is a test file, or the name of a workflow with one
+        targets
+            .iter()
+            .map(|target| {
+                let path = working_dir.join(target);
+                if path.is_file() {
+                    path
+                } else {
+                    workflows_dir.join(format!("{}{}", target, TEST_FILE_SUFFIX))
+                }
+            })
+            .collect()
+    };
+    if files.is_empty() {
+        return Err(format!(
+            "No workflow tests found in {}",
+            workflows_dir.display()
+        ));
+    }
+
+    let mut outcomes = Vec::new();
+    for file in &files {
+        let file_outcomes = run_test_file(file, config.clone(), filter)
+            .await
+            .map_err(|e| format!("{}: {:#}", file.display(), e))?;
+        if !json {
+            println!("{}", file.display());
+            for outcome in &file_outcomes {
+                let status = if outcome.passed() { "ok  " } else { "FAIL" };
+                println!("  {}  {} ({}ms)", status, outcome.name, outcome.duration_ms);
+                for failure in &outcome.failures {
+                    println!("        {}", failure);
+                }
+            }
+        }
+        outcomes.extend(file_outcomes);
+    }
+
+    let failed = outcomes.iter().filter(|o| !o.passed()).count();
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&outcomes).map_err(|e| e.to_string())?
+        );
+    } else {
+        println!("\n{} passed, {} failed", outcomes.len() - failed, failed);
+    }
+
+    Ok(if failed > 0 { 1 } else { 0 })
+}
+
 /// Parse workflow arguments from CLI
 fn parse_workflow_args(args: &[String]) -> HashMap<String, String> {
     let mut parsed = HashMap::new();
@@ -227,16 +714,64 @@ fn parse_workflow_args(args: &[String]) -> HashMap<String, String> {
     parsed
 }
 
+/// Replace `@file` values with the file's contents and a `-` value with stdin
+///
+/// A leading `@@` stands for a literal `@`.
+fn load_arg_values(args: &mut HashMap<String, String>, working_dir: &Path) -> Result<(), String> {
+    use std::io::Read;
+
+    let mut stdin_used = false;
+    for (name, value) in args.iter_mut() {
+        if value == "-" {
+            if stdin_used {
+                return Err("only one argument can be read from stdin".into());
+            }
+            stdin_used = true;
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|e| format!("Failed to read argument '{}' from stdin: {}", name, e))?;
+            *value = input;
+        } else if let Some(literal) = value.strip_prefix("@@") {
+            *value = format!("@{}", literal);
+        } else if let Some(path) = value.strip_prefix('@') {
+            let path = working_dir.join(path);
+            *value = std::fs::read_to_string(&path).map_err(|e| {
+                format!(
+                    "Failed to read argument '{}' from {}: {}",
+                    name,
+                    path.display(),
+                    e
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Print a workflow's usage and arguments
+pub fn workflow_help(workflow_name: &str, working_dir: &Path) -> Result<i32, String> {
+    let workflow = load_workflow(workflow_name, Some(working_dir))
+        .map_err(|e| format!("Failed to load workflow '{}': {}", workflow_name, e))?;
+    print!("{}", usage(workflow_name, &workflow));
+    Ok(0)
+}
+
 /// Validate a workflow
 pub fn validate_workflow(
     workflow_name: &str,
     working_dir: Option<&Path>,
+    config: &LlmuxConfig,
     handler: &dyn OutputHandler,
 ) -> Result<i32, String> {
     match load_workflow(workflow_name, working_dir) {
         Ok(wf) => {
-            // Run validation
-            match wf.validate() {
+            // Run validation, including the workflows it calls
+            match wf
+                .validate()
+                .and_then(|()| wf.validate_roles(config))
+                .and_then(|()| check_sub_workflows(&wf, None, working_dir))
+            {
                 Ok(()) => {
                     handler.emit(OutputEvent::Info {
                         message: format!(
@@ -245,6 +780,11 @@ pub fn validate_workflow(
                             wf.steps.len()
                         ),
                     });
+                    for warning in capability_warnings(&wf, config) {
+                        handler.emit(OutputEvent::Info {
+                            message: format!("  ⚠ {}", warning),
+                        });
+                    }
                     Ok(0)
                 }
                 Err(errors) => {
@@ -269,6 +809,33 @@ pub fn validate_workflow(
     }
 }
 
+/// Warn about query steps whose role has no backend with the required capabilities
+fn capability_warnings(workflow: &WorkflowConfig, config: &LlmuxConfig) -> Vec<String> {
+    workflow
+        .all_steps()
+        .into_iter()
+        .filter(|step| {
+            matches!(step.step_type, StepType::Query | StepType::MapReduce)
+                && !step.requires.is_empty()
+        })
+        .flat_map(|step| {
+            // A matrix role axis replaces the step's role in every combination
+            let roles = match step.matrix.get("role") {
+                Some(values) => values.as_slice(),
+                None => step.role.as_slice(),
+            };
+            roles.iter().filter_map(move |role| {
+                match resolve_role_for(role, None, config, &step.requires, &step.prefers) {
+                    Err(e @ RoleError::NoCapableBackend { .. }) => {
+                        Some(format!("step '{}': {}", step.name, e))
+                    }
+                    _ => None,
+                }
+            })
+        })
+        .collect()
+}
+
 /// Check backend availability
 pub async fn doctor(config: &LlmuxConfig, working_dir: &Path, handler: &dyn OutputHandler) -> i32 {
     handler.emit(OutputEvent::Info {
@@ -300,6 +867,20 @@ pub async fn doctor(config: &LlmuxConfig, working_dir: &Path, handler: &dyn Outp
         };
 
         handler.emit(OutputEvent::Info { message: status });
+
+        if let Some(breaker) = CircuitBreaker::shared(name, backend) {
+            let state = breaker.state();
+            if state != BreakerState::Closed {
+                all_ok = false;
+            }
+            handler.emit(OutputEvent::Info {
+                message: format!(
+                    "  circuit {} ({} consecutive failures)",
+                    state,
+                    breaker.consecutive_failures()
+                ),
+            });
+        }
     }
 
     if config.backends.is_empty() {
@@ -348,6 +929,66 @@ pub fn list_backends(config: &LlmuxConfig, handler: &dyn OutputHandler) {
     }
 }
 
+/// Show recorded per-backend statistics used for adaptive ordering
+pub fn backend_stats(config: &LlmuxConfig, handler: &dyn OutputHandler) -> i32 {
+    let store = match StatsStore::open_default() {
+        Ok(store) => store,
+        Err(e) => {
+            handler.emit(OutputEvent::WorkflowError {
+                error: format!("Failed to open backend stats: {}", e),
+            });
+            return 1;
+        }
+    };
+
+    let mut recorded: HashMap<String, BackendStats> = match store.all() {
+        Ok(all) => all.into_iter().map(|s| (s.backend.clone(), s)).collect(),
+        Err(e) => {
+            handler.emit(OutputEvent::WorkflowError {
+                error: format!("Failed to read backend stats: {}", e),
+            });
+            return 1;
+        }
+    };
+
+    let mut names: Vec<&String> = config.backends.keys().collect();
+    names.sort();
+
+    handler.emit(OutputEvent::Info {
+        message: format!(
+            "{:<16} {:>6} {:>8} {:>9} {:>10}  circuit",
+            "backend", "calls", "success", "p50", "$/Mtok"
+        ),
+    });
+
+    for name in names {
+        let backend = &config.backends[name];
+        let stats = recorded.remove(name).unwrap_or_default();
+
+        let success = stats
+            .success_rate()
+            .map_or("-".to_string(), |r| format!("{:.0}%", r * 100.0));
+        let p50 = stats
+            .p50_ms
+            .map_or("-".to_string(), |ms| format!("{}ms", ms));
+        let cost = stats
+            .cost_per_mtok()
+            .or(backend.cost_per_mtok)
+            .map_or("-".to_string(), |c| format!("{:.2}", c));
+        let circuit = CircuitBreaker::shared(name, backend)
+            .map_or("-".to_string(), |breaker| breaker.state().to_string());
+
+        handler.emit(OutputEvent::Info {
+            message: format!(
+                "{:<16} {:>6} {:>8} {:>9} {:>10}  {}",
+                name, stats.calls, success, p50, cost, circuit
+            ),
+        });
+    }
+
+    0
+}
+
 /// List configured teams
 pub fn list_teams(config: &LlmuxConfig, handler: &dyn OutputHandler) {
     if config.teams.is_empty() {
@@ -442,6 +1083,50 @@ pub fn list_ecosystems(config: &LlmuxConfig, handler: &dyn OutputHandler) {
     }
 }
 
+/// Show response cache statistics
+pub fn cache_stats(handler: &dyn OutputHandler) -> Result<i32, String> {
+    let cache =
+        ResponseCache::open_default().map_err(|e| format!("Failed to open cache: {}", e))?;
+    let stats = cache
+        .stats()
+        .map_err(|e| format!("Failed to read cache: {}", e))?;
+
+    handler.emit(OutputEvent::Info {
+        message: format!("Cache: {}", cache.path().display()),
+    });
+    handler.emit(OutputEvent::Info {
+        message: format!(
+            "  entries: {} ({} expired), hits: {}, size: {} KiB",
+            stats.entries,
+            stats.expired,
+            stats.hits,
+            stats.size_bytes / 1024
+        ),
+    });
+    for (backend, count) in &stats.by_backend {
+        handler.emit(OutputEvent::Info {
+            message: format!("  {}: {}", backend, count),
+        });
+    }
+
+    Ok(0)
+}
+
+/// Remove cached responses
+pub fn cache_clear(expired_only: bool, handler: &dyn OutputHandler) -> Result<i32, String> {
+    let cache =
+        ResponseCache::open_default().map_err(|e| format!("Failed to open cache: {}", e))?;
+    let removed = cache
+        .clear(expired_only)
+        .map_err(|e| format!("Failed to clear cache: {}", e))?;
+
+    handler.emit(OutputEvent::Info {
+        message: format!("✓ Removed {} cached response(s)", removed),
+    });
+
+    Ok(0)
+}
+
 /// Initialize llmux configuration interactively
 pub async fn init_config(
     working_dir: &Path,
@@ -761,6 +1446,58 @@ mod tests {
         assert_eq!(parsed.get("key"), Some(&"value".to_string()));
     }
 
+    #[test]
+    fn test_piped_input_read_without_dash() {
+        let piped = std::io::Cursor::new(b"diff --git a/x b/x\n".to_vec());
+        assert_eq!(
+            read_ready_input(piped, Duration::from_secs(5)).as_deref(),
+            Some("diff --git a/x b/x\n")
+        );
+
+        // An empty pipe or /dev/null adds nothing
+        let empty = std::io::Cursor::new(Vec::new());
+        assert_eq!(read_ready_input(empty, Duration::from_secs(5)), None);
+    }
+
+    #[test]
+    fn test_idle_pipe_ignored() {
+        /// Reader that blocks like an open pipe nobody writes to
+        struct IdlePipe(std::sync::mpsc::Receiver<()>);
+
+        impl std::io::Read for IdlePipe {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                let _ = self.0.recv();
+                Ok(0)
+            }
+        }
+
+        let (_writer, rx) = std::sync::mpsc::channel();
+        let start = std::time::Instant::now();
+        assert_eq!(
+            read_ready_input(IdlePipe(rx), Duration::from_millis(50)),
+            None
+        );
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_load_arg_values() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("diff.txt"), "+added\n").unwrap();
+
+        let mut args = parse_workflow_args(&["diff=@diff.txt".into(), "a=b".into()]);
+        load_arg_values(&mut args, dir.path()).unwrap();
+        assert_eq!(args["diff"], "+added\n");
+        assert_eq!(args["a"], "b");
+
+        let mut args = parse_workflow_args(&["diff=@missing.txt".into()]);
+        assert!(load_arg_values(&mut args, dir.path()).is_err());
+
+        let mut args = parse_workflow_args(&["user=@@octocat".into()]);
+        load_arg_values(&mut args, dir.path()).unwrap();
+        assert_eq!(args["user"], "@octocat");
+    }
+
     #[test]
     fn test_list_backends_empty() {
         let config = LlmuxConfig::default();
@@ -793,4 +1530,97 @@ mod tests {
         let events = handler.events();
         assert_eq!(events.len(), 1);
     }
+
+    #[test]
+    fn test_capability_warnings() {
+        use crate::config::{BackendConfig, BackendRequirements, RoleConfig, StepConfig};
+
+        let mut config = LlmuxConfig::default();
+        config
+            .backends
+            .insert("claude".into(), BackendConfig::default());
+        config.roles.insert(
+            "analyzer".into(),
+            RoleConfig {
+                backends: vec!["claude".into()],
+                ..Default::default()
+            },
+        );
+
+        let step = StepConfig {
+            name: "look".into(),
+            step_type: StepType::Query,
+            role: Some("analyzer".into()),
+            prompt: Some("describe".into()),
+            requires: BackendRequirements {
+                vision: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let workflow = WorkflowConfig {
+            steps: vec![step],
+            ..Default::default()
+        };
+
+        let warnings = capability_warnings(&workflow, &config);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("vision"));
+
+        config.backends.get_mut("claude").unwrap().supports_vision = true;
+        assert!(capability_warnings(&workflow, &config).is_empty());
+
+        // Matrix roles are checked too
+        config
+            .backends
+            .insert("codex".into(), BackendConfig::default());
+        config.roles.insert(
+            "coder".into(),
+            RoleConfig {
+                backends: vec!["codex".into()],
+                ..Default::default()
+            },
+        );
+        let mut workflow = workflow;
+        workflow.steps[0].role = None;
+        workflow.steps[0]
+            .matrix
+            .insert("role".into(), vec!["analyzer".into(), "coder".into()]);
+        let warnings = capability_warnings(&workflow, &config);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("coder"));
+    }
+
+    #[tokio::test]
+    async fn test_ask_backend_with_stdin() {
+        use crate::config::BackendConfig;
+
+        let mut config = LlmuxConfig::default();
+        config.backends.insert(
+            "echo".into(),
+            BackendConfig {
+                command: "echo".into(),
+                ..Default::default()
+            },
+        );
+        let dir = tempfile::TempDir::new().unwrap();
+
+        let options = AskOptions {
+            prompt: "review this",
+            backend: Some("echo"),
+            stdin: Some("diff --git a/x b/x".into()),
+            ..Default::default()
+        };
+        let result = run_ask(&config, None, dir.path(), &options).await.unwrap();
+        assert!(!result.failed);
+        let output = result.output.unwrap();
+        assert!(output.contains("review this"));
+        assert!(output.contains("diff --git a/x b/x"));
+
+        let options = AskOptions {
+            prompt: "hi",
+            ..Default::default()
+        };
+        assert!(run_ask(&config, None, dir.path(), &options).await.is_err());
+    }
 }
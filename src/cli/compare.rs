@@ -0,0 +1,557 @@
+//! Side-by-side backend comparison
+//!
+//! `llmux compare "prompt" --backends a,b,c` sends one prompt to several
+//! backends at once and shows the answers next to each other, with latency,
+//! token usage and an optional line diff between two of them.
+
+use crate::backend_executor::{BackendRequest, StatsStore, TokenUsage};
+use crate::config::{LlmuxConfig, RoleExecution};
+use crate::role::{ExecutionError, ResolvedRole, RoleExecutor, detect_team, resolve_role};
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Console width used when `COLUMNS` is not set
+const DEFAULT_WIDTH: usize = 120;
+
+/// Narrowest column worth rendering
+const MIN_COLUMN_WIDTH: usize = 20;
+
+/// Options for `llmux compare`
+#[derive(Debug, Default)]
+pub struct CompareOptions<'a> {
+    /// The prompt sent to every backend
+    pub prompt: &'a str,
+    /// Backends to compare
+    pub backends: &'a [String],
+    /// Compare the backends of this role instead
+    pub role: Option<&'a str>,
+    /// Two backends whose answers are diffed line by line
+    pub diff: Option<(&'a str, &'a str)>,
+    /// Console width (defaults to `COLUMNS`)
+    pub width: Option<usize>,
+    /// Files to include with the prompt
+    pub context_files: &'a [PathBuf],
+    /// Input piped on stdin
+    pub stdin: Option<String>,
+    /// Team override
+    pub team_override: Option<&'a str>,
+    /// Print the comparison as JSON
+    pub json: bool,
+}
+
+/// One backend's answer
+#[derive(Debug, Clone, Serialize)]
+pub struct Answer {
+    pub backend: String,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    pub usage: Option<TokenUsage>,
+}
+
+/// A line of a diff between two answers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+impl std::fmt::Display for DiffLine<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffLine::Same(line) => write!(f, "  {}", line),
+            DiffLine::Removed(line) => write!(f, "- {}", line),
+            DiffLine::Added(line) => write!(f, "+ {}", line),
+        }
+    }
+}
+
+/// Comparison written in JSON mode
+#[derive(Debug, Serialize)]
+struct Comparison<'a> {
+    prompt: &'a str,
+    answers: &'a [Answer],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<Vec<String>>,
+}
+
+/// Compare backends on stdout
+pub async fn compare(
+    config: Arc<LlmuxConfig>,
+    working_dir: &Path,
+    options: CompareOptions<'_>,
+) -> Result<i32, String> {
+    let mut executor = RoleExecutor::new(config.clone());
+    match StatsStore::open_default() {
+        Ok(stats) => executor = executor.with_stats(Arc::new(stats)),
+        Err(e) => tracing::warn!(error = %e, "Backend statistics unavailable"),
+    }
+
+    let mut stdout = std::io::stdout();
+    run_compare(&config, &executor, working_dir, &options, &mut stdout).await
+}
+
+/// Run the comparison and render it, so it can be driven by tests
+async fn run_compare(
+    config: &LlmuxConfig,
+    executor: &RoleExecutor,
+    working_dir: &Path,
+    options: &CompareOptions<'_>,
+    out: &mut impl Write,
+) -> Result<i32, String> {
+    let base = match options.role {
+        Some(role) => {
+            let team = detect_team(working_dir, &config.teams, options.team_override);
+            resolve_role(role, team.as_deref(), config).map_err(|e| e.to_string())?
+        }
+        None => ResolvedRole {
+            name: "compare".into(),
+            ..Default::default()
+        },
+    };
+    let backends = if options.backends.is_empty() {
+        base.backends.clone()
+    } else {
+        options.backends.to_vec()
+    };
+    if backends.is_empty() {
+        return Err("Either --backends or --role is required".into());
+    }
+    for backend in &backends {
+        if !config.backends.contains_key(backend) {
+            return Err(format!("Unknown backend '{}'", backend));
+        }
+    }
+    if let Some((a, b)) = options.diff {
+        for name in [a, b] {
+            if !backends.iter().any(|backend| backend == name) {
+                return Err(format!("--diff backend '{}' is not being compared", name));
+            }
+        }
+    }
+
+    let prompt = super::commands::prompt_with_input(
+        options.prompt,
+        options.context_files,
+        options.stdin.as_deref(),
+        working_dir,
+    )?;
+    let request = BackendRequest::new(prompt).with_working_dir(working_dir.to_path_buf());
+
+    let answers = ask_all(executor, &base, &backends, &request).await;
+
+    let diff = options.diff.map(|(a, b)| {
+        let text = |name: &str| {
+            answers
+                .iter()
+                .find(|answer| answer.backend == name)
+                .and_then(|answer| answer.output.as_deref())
+                .unwrap_or("")
+        };
+        (a, b, line_diff(text(a), text(b)))
+    });
+
+    if options.json {
+        let comparison = Comparison {
+            prompt: options.prompt,
+            answers: &answers,
+            diff: diff
+                .as_ref()
+                .map(|(_, _, lines)| lines.iter().map(ToString::to_string).collect()),
+        };
+        let json = serde_json::to_string_pretty(&comparison).map_err(|e| e.to_string())?;
+        let _ = writeln!(out, "{}", json);
+    } else {
+        let width = options
+            .width
+            .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+            .unwrap_or(DEFAULT_WIDTH);
+        render_columns(&answers, width, out);
+        if let Some((a, b, lines)) = diff {
+            let _ = writeln!(out, "\n--- {}\n+++ {}", a, b);
+            for line in lines {
+                let _ = writeln!(out, "{}", line);
+            }
+        }
+    }
+
+    Ok(if answers.iter().all(|answer| answer.error.is_some()) {
+        1
+    } else {
+        0
+    })
+}
+
+/// Send the request to every backend at once, keeping the given order
+async fn ask_all(
+    executor: &RoleExecutor,
+    base: &ResolvedRole,
+    backends: &[String],
+    request: &BackendRequest,
+) -> Vec<Answer> {
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, backend) in backends.iter().enumerate() {
+        let executor = executor.clone();
+        let request = request.clone();
+        let role = ResolvedRole {
+            backends: vec![backend.clone()],
+            execution: RoleExecution::First,
+            ..base.clone()
+        };
+        let backend = backend.clone();
+        tasks.spawn(async move {
+            let start = std::time::Instant::now();
+            let result = executor.execute(&role, &request).await;
+            let duration_ms = start.elapsed().as_millis() as u64;
+            let answer = match result {
+                Ok(result) => Answer {
+                    backend,
+                    output: result.output,
+                    error: None,
+                    duration_ms,
+                    usage: result.usage,
+                },
+                Err(e) => {
+                    let error = match e {
+                        ExecutionError::AllFailed { ref errors } => errors.get(&backend).cloned(),
+                        _ => None,
+                    };
+                    Answer {
+                        error: Some(error.unwrap_or_else(|| e.to_string())),
+                        backend,
+                        output: None,
+                        duration_ms,
+                        usage: None,
+                    }
+                }
+            };
+            (index, answer)
+        });
+    }
+
+    let mut answers = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok(answer) = joined {
+            answers.push(answer);
+        }
+    }
+    answers.sort_by_key(|(index, _)| *index);
+    answers.into_iter().map(|(_, answer)| answer).collect()
+}
+
+/// Render answers as columns: name, latency and usage, then the wrapped text
+fn render_columns(answers: &[Answer], width: usize, out: &mut impl Write) {
+    const SEPARATOR: &str = " │ ";
+    // Display width, not byte length: `│` is three bytes
+    const SEPARATOR_WIDTH: usize = 3;
+
+    let count = answers.len().max(1);
+    let column =
+        (width.saturating_sub(SEPARATOR_WIDTH * (count - 1)) / count).max(MIN_COLUMN_WIDTH);
+
+    let columns: Vec<Vec<String>> = answers
+        .iter()
+        .map(|answer| {
+            let mut lines = vec![answer.backend.clone(), summary(answer)];
+            lines.push("─".repeat(column));
+            let body = match (&answer.output, &answer.error) {
+                (_, Some(error)) => format!("error: {}", error),
+                (Some(output), None) => output.trim_end().to_string(),
+                (None, None) => String::new(),
+            };
+            lines.extend(body.lines().flat_map(|line| wrap(line, column)));
+            lines
+        })
+        .collect();
+
+    let rows = columns.iter().map(Vec::len).max().unwrap_or(0);
+    for row in 0..rows {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|lines| pad(lines.get(row).map(String::as_str).unwrap_or(""), column))
+            .collect();
+        let _ = writeln!(out, "{}", cells.join(SEPARATOR).trim_end());
+    }
+}
+
+/// Latency and token usage of an answer
+fn summary(answer: &Answer) -> String {
+    let latency = format!("{:.1}s", answer.duration_ms as f64 / 1000.0);
+    match &answer.usage {
+        Some(usage) => format!(
+            "{} · {} in / {} out tok",
+            latency,
+            usage.prompt_tokens.unwrap_or(0),
+            usage.completion_tokens.unwrap_or(0)
+        ),
+        None if answer.error.is_some() => format!("{} · failed", latency),
+        None => latency,
+    }
+}
+
+/// Split a line into chunks of at most `width` characters
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let chars: Vec<char> = line.chars().collect();
+    if chars.is_empty() {
+        return vec![String::new()];
+    }
+    chars
+        .chunks(width)
+        .map(|chunk| chunk.iter().collect())
+        .collect()
+}
+
+/// Pad a cell to `width` characters
+fn pad(cell: &str, width: usize) -> String {
+    let len = cell.chars().count();
+    format!("{}{}", cell, " ".repeat(width.saturating_sub(len)))
+}
+
+/// Line-level diff between two answers (longest common subsequence)
+///
+/// Uses Hirschberg's algorithm, so memory stays linear in the number of lines.
+pub fn line_diff<'a>(a: &'a str, b: &'a str) -> Vec<DiffLine<'a>> {
+    let a: Vec<&str> = a.lines().collect();
+    let b: Vec<&str> = b.lines().collect();
+    let mut diff = Vec::new();
+    diff_lines(&a, &b, &mut diff);
+    diff
+}
+
+/// Append the diff of `a` against `b`, splitting `a` in half until one line is left
+fn diff_lines<'a>(a: &[&'a str], b: &[&'a str], diff: &mut Vec<DiffLine<'a>>) {
+    // Common prefix and suffix need no search
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    diff.extend(a[..prefix].iter().map(|line| DiffLine::Same(line)));
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    match a_mid {
+        [] => diff.extend(b_mid.iter().map(|line| DiffLine::Added(line))),
+        [line] => match b_mid.iter().position(|other| other == line) {
+            Some(k) => {
+                diff.extend(b_mid[..k].iter().map(|line| DiffLine::Added(line)));
+                diff.push(DiffLine::Same(line));
+                diff.extend(b_mid[k + 1..].iter().map(|line| DiffLine::Added(line)));
+            }
+            None => {
+                diff.push(DiffLine::Removed(line));
+                diff.extend(b_mid.iter().map(|line| DiffLine::Added(line)));
+            }
+        },
+        _ if b_mid.is_empty() => diff.extend(a_mid.iter().map(|line| DiffLine::Removed(line))),
+        _ => {
+            // Split b where the two halves of a together keep the longest common subsequence
+            let half = a_mid.len() / 2;
+            let front = lcs_lengths(a_mid[..half].iter().copied(), b_mid.iter().copied());
+            let back = lcs_lengths(
+                a_mid[half..].iter().rev().copied(),
+                b_mid.iter().rev().copied(),
+            );
+            let split = (0..=b_mid.len())
+                .max_by_key(|&j| (front[j] + back[b_mid.len() - j], std::cmp::Reverse(j)))
+                .unwrap_or(0);
+            diff_lines(&a_mid[..half], &b_mid[..split], diff);
+            diff_lines(&a_mid[half..], &b_mid[split..], diff);
+        }
+    }
+
+    diff.extend(
+        a[a.len() - suffix..]
+            .iter()
+            .map(|line| DiffLine::Same(line)),
+    );
+}
+
+/// Length of the longest common subsequence of `a` and each prefix of `b`
+fn lcs_lengths<'a>(
+    a: impl Iterator<Item = &'a str>,
+    b: impl Iterator<Item = &'a str>,
+) -> Vec<usize> {
+    let b: Vec<&str> = b.collect();
+    let mut row = vec![0; b.len() + 1];
+    for line in a {
+        let mut diagonal = 0;
+        for (j, other) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if line == *other {
+                diagonal + 1
+            } else {
+                above.max(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BackendConfig;
+    use tempfile::TempDir;
+
+    fn echo_config() -> LlmuxConfig {
+        let mut config = LlmuxConfig::default();
+        config.backends.insert(
+            "echo".into(),
+            BackendConfig {
+                command: "echo".into(),
+                ..Default::default()
+            },
+        );
+        config.backends.insert(
+            "shout".into(),
+            BackendConfig {
+                command: "sh".into(),
+                args: vec!["-c".into(), "echo \"$1\" | tr a-z A-Z".into(), "sh".into()],
+                ..Default::default()
+            },
+        );
+        config
+    }
+
+    #[test]
+    fn test_line_diff() {
+        let diff = line_diff("a\nb\nc", "a\nx\nc\nd");
+        assert_eq!(
+            diff,
+            vec![
+                DiffLine::Same("a"),
+                DiffLine::Removed("b"),
+                DiffLine::Added("x"),
+                DiffLine::Same("c"),
+                DiffLine::Added("d"),
+            ]
+        );
+        assert_eq!(diff[1].to_string(), "- b");
+    }
+
+    #[test]
+    fn test_line_diff_keeps_longest_match() {
+        let a = "1\n2\n3\n4\n5\n6";
+        let b = "0\n2\n3\nx\n5\n6\n7";
+        let diff = line_diff(a, b);
+        assert_eq!(
+            diff.iter()
+                .filter(|l| matches!(l, DiffLine::Same(_)))
+                .count(),
+            4
+        );
+
+        // Applying the diff to either side reproduces it
+        let old: Vec<_> = diff
+            .iter()
+            .filter_map(|l| match l {
+                DiffLine::Same(s) | DiffLine::Removed(s) => Some(*s),
+                DiffLine::Added(_) => None,
+            })
+            .collect();
+        let new: Vec<_> = diff
+            .iter()
+            .filter_map(|l| match l {
+                DiffLine::Same(s) | DiffLine::Added(s) => Some(*s),
+                DiffLine::Removed(_) => None,
+            })
+            .collect();
+        assert_eq!(old.join("\n"), a);
+        assert_eq!(new.join("\n"), b);
+    }
+
+    #[test]
+    fn test_wrap_and_pad() {
+        assert_eq!(wrap("abcdef", 4), vec!["abcd", "ef"]);
+        assert_eq!(wrap("", 4), vec![""]);
+        assert_eq!(pad("ab", 4), "ab  ");
+    }
+
+    #[test]
+    fn test_render_columns_fills_width() {
+        let answer = |backend: &str| Answer {
+            backend: backend.into(),
+            output: Some("x".repeat(200)),
+            error: None,
+            duration_ms: 0,
+            usage: None,
+        };
+        let mut out = Vec::new();
+        render_columns(&[answer("a"), answer("b")], 83, &mut out);
+
+        // Two 40-column cells and a 3-column separator fill 83 columns exactly
+        let out = String::from_utf8(out).unwrap();
+        let widest = out.lines().map(|l| l.chars().count()).max().unwrap();
+        assert_eq!(widest, 83);
+    }
+
+    #[tokio::test]
+    async fn test_compare_backends() {
+        let config = echo_config();
+        let executor = RoleExecutor::new(Arc::new(config.clone()));
+        let dir = TempDir::new().unwrap();
+        let backends = vec!["echo".to_string(), "shout".to_string()];
+
+        let options = CompareOptions {
+            prompt: "hello",
+            backends: &backends,
+            diff: Some(("echo", "shout")),
+            width: Some(60),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let code = run_compare(&config, &executor, dir.path(), &options, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(code, 0);
+
+        let out = String::from_utf8(out).unwrap();
+        let first = out.lines().next().unwrap();
+        assert!(first.starts_with("echo"));
+        assert!(first.contains("shout"));
+        assert!(out.contains("- hello"));
+        assert!(out.contains("+ HELLO"));
+
+        let options = CompareOptions {
+            prompt: "hello",
+            backends: &backends,
+            json: true,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        run_compare(&config, &executor, dir.path(), &options, &mut out)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json["answers"][0]["backend"], "echo");
+        assert_eq!(
+            json["answers"][1]["output"].as_str().unwrap().trim(),
+            "HELLO"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compare_unknown_backend() {
+        let config = echo_config();
+        let executor = RoleExecutor::new(Arc::new(config.clone()));
+        let dir = TempDir::new().unwrap();
+        let backends = vec!["missing".to_string()];
+
+        let options = CompareOptions {
+            prompt: "hello",
+            backends: &backends,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let err = run_compare(&config, &executor, dir.path(), &options, &mut out)
+            .await
+            .unwrap_err();
+        assert!(err.contains("missing"));
+    }
+}
@@ -2,6 +2,8 @@
 //!
 //! This module provides:
 //! - Command implementations (run, validate, doctor, etc.)
+//! - Interactive chat sessions
+//! - Side-by-side backend comparison
 //! - Output handlers (console, JSON, quiet)
 //! - Signal handling for graceful shutdown
 //!
@@ -11,10 +13,13 @@
 //! use llmux::cli::{commands, output, signals};
 //!
 //! let handler = output::create_handler(output::OutputMode::Console, false);
-//! let exit_code = commands::run_workflow("my-workflow", args, dir, None, config, &*handler).await?;
+//! let options = commands::RunOptions::default();
+//! let exit_code = commands::run_workflow("my-workflow", args, dir, config, &*handler, options).await?;
 //! ```
 
+pub mod chat;
 pub mod commands;
+pub mod compare;
 pub mod output;
 pub mod signals;
 
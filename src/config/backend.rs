@@ -49,6 +49,10 @@ pub struct BackendConfig {
     #[serde(default = "default_true")]
     pub retry_rate_limit: bool,
 
+    /// Longest server-requested retry delay to honor, in seconds
+    #[serde(default = "default_max_retry_wait")]
+    pub max_retry_wait: u64,
+
     /// Whether to auto-retry on timeouts
     #[serde(default)]
     pub retry_timeout: bool,
@@ -56,6 +60,102 @@ pub struct BackendConfig {
     /// Additional environment variables for the command
     #[serde(default)]
     pub env: Vec<(String, String)>,
+
+    /// Maximum requests per minute, shared across the whole process
+    pub requests_per_minute: Option<u32>,
+
+    /// Maximum estimated tokens per minute, shared across the whole process
+    pub tokens_per_minute: Option<u32>,
+
+    /// Maximum concurrent requests to this backend
+    pub max_in_flight: Option<u32>,
+
+    /// Consecutive failures before the circuit breaker opens (disabled if unset)
+    pub breaker_threshold: Option<u32>,
+
+    /// Seconds an open circuit waits before probing the backend again
+    #[serde(default = "default_breaker_cooldown")]
+    pub breaker_cooldown: u64,
+
+    /// Persist circuit breaker state across runs
+    #[serde(default)]
+    pub breaker_persist: bool,
+
+    /// Context window in tokens
+    pub context_window: Option<u32>,
+
+    /// Accepts a separate system prompt
+    #[serde(default)]
+    pub supports_system: bool,
+
+    /// Can be constrained to emit JSON
+    #[serde(default)]
+    pub supports_json: bool,
+
+    /// Accepts images
+    #[serde(default)]
+    pub supports_vision: bool,
+
+    /// Runs on this machine, so prompts never leave it
+    #[serde(default)]
+    pub local: bool,
+
+    /// Price in USD per million tokens (for adaptive ordering by cost)
+    pub cost_per_mtok: Option<f64>,
+
+    /// Turn format for flattening conversations (CLI backends), with `{role}` and `{content}`
+    pub transcript_format: Option<String>,
+}
+
+/// Capabilities a step needs from (or prefers in) a backend
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct BackendRequirements {
+    /// Separate system prompt
+    #[serde(default)]
+    pub system: bool,
+
+    /// JSON output mode
+    #[serde(default)]
+    pub json: bool,
+
+    /// Image input
+    #[serde(default)]
+    pub vision: bool,
+
+    /// Local execution
+    #[serde(default)]
+    pub local: bool,
+
+    /// Minimum context window in tokens
+    pub min_context: Option<u32>,
+}
+
+impl BackendRequirements {
+    /// Returns true if nothing is required
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl std::fmt::Display for BackendRequirements {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        for (set, name) in [
+            (self.system, "system"),
+            (self.json, "json"),
+            (self.vision, "vision"),
+            (self.local, "local"),
+        ] {
+            if set {
+                parts.push(name.to_string());
+            }
+        }
+        if let Some(min) = self.min_context {
+            parts.push(format!("context >= {}", min));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
 }
 
 fn default_enabled() -> bool {
@@ -74,6 +174,14 @@ fn default_retry_delay() -> u64 {
     1000 // 1 second
 }
 
+fn default_max_retry_wait() -> u64 {
+    120 // 2 minutes
+}
+
+fn default_breaker_cooldown() -> u64 {
+    60 // 1 minute
+}
+
 fn default_true() -> bool {
     true
 }
@@ -92,8 +200,22 @@ impl Default for BackendConfig {
             max_retries: default_max_retries(),
             retry_delay: default_retry_delay(),
             retry_rate_limit: true,
+            max_retry_wait: default_max_retry_wait(),
             retry_timeout: false,
             env: Vec::new(),
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            max_in_flight: None,
+            breaker_threshold: None,
+            breaker_cooldown: default_breaker_cooldown(),
+            breaker_persist: false,
+            context_window: None,
+            supports_system: false,
+            supports_json: false,
+            supports_vision: false,
+            local: false,
+            cost_per_mtok: None,
+            transcript_format: None,
         }
     }
 }
@@ -115,6 +237,24 @@ impl BackendConfig {
     pub fn is_cli(&self) -> bool {
         !self.is_http() && !self.is_claude_api()
     }
+
+    /// Returns true if this backend has every capability in `requirements`
+    pub fn satisfies(&self, requirements: &BackendRequirements) -> bool {
+        (!requirements.system || self.supports_system)
+            && (!requirements.json || self.supports_json)
+            && (!requirements.vision || self.supports_vision)
+            && (!requirements.local || self.local)
+            && requirements
+                .min_context
+                .is_none_or(|min| self.context_window.is_some_and(|window| window >= min))
+    }
+
+    /// Returns true if any rate limit is configured
+    pub fn has_rate_limits(&self) -> bool {
+        self.requests_per_minute.is_some()
+            || self.tokens_per_minute.is_some()
+            || self.max_in_flight.is_some()
+    }
 }
 
 #[cfg(test)]
@@ -133,6 +273,36 @@ mod tests {
         assert_eq!(config.max_retries, 3);
     }
 
+    #[test]
+    fn test_capabilities() {
+        let toml = r#"
+            command = "http://localhost:11434"
+            context_window = 32000
+            supports_json = true
+            local = true
+        "#;
+        let config: BackendConfig = toml::from_str(toml).unwrap();
+
+        let mut requirements = BackendRequirements {
+            json: true,
+            local: true,
+            min_context: Some(16000),
+            ..Default::default()
+        };
+        assert!(config.satisfies(&requirements));
+        assert!(config.satisfies(&BackendRequirements::default()));
+
+        requirements.min_context = Some(100_000);
+        assert!(!config.satisfies(&requirements));
+        assert_eq!(requirements.to_string(), "json, local, context >= 100000");
+
+        let vision = BackendRequirements {
+            vision: true,
+            ..Default::default()
+        };
+        assert!(!config.satisfies(&vision));
+    }
+
     #[test]
     fn test_deserialize_full() {
         let toml = r#"
@@ -162,6 +332,35 @@ mod tests {
         assert_eq!(config.model, Some("qwen3-coder".into()));
     }
 
+    #[test]
+    fn test_deserialize_rate_limits() {
+        let toml = r#"
+            command = "https://api.openai.com/v1"
+            requests_per_minute = 60
+            tokens_per_minute = 90000
+            max_in_flight = 4
+        "#;
+        let config: BackendConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.requests_per_minute, Some(60));
+        assert_eq!(config.tokens_per_minute, Some(90000));
+        assert_eq!(config.max_in_flight, Some(4));
+        assert!(config.has_rate_limits());
+        assert!(!BackendConfig::default().has_rate_limits());
+    }
+
+    #[test]
+    fn test_deserialize_circuit_breaker() {
+        let toml = r#"
+            command = "http://localhost:11434"
+            breaker_threshold = 3
+            breaker_persist = true
+        "#;
+        let config: BackendConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.breaker_threshold, Some(3));
+        assert_eq!(config.breaker_cooldown, 60);
+        assert!(config.breaker_persist);
+    }
+
     #[test]
     fn test_reject_unknown_fields() {
         let toml = r#"
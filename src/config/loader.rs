@@ -3,6 +3,7 @@
 //! Configuration loading with multi-layer merge
 
 use super::{BackendConfig, EcosystemConfig, RoleConfig, TeamConfig, WorkflowConfig};
+use crate::backend_executor::Message;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -90,6 +91,30 @@ pub struct StepResult {
 
     /// Backends that executed (for parallel)
     pub backends: Vec<String>,
+
+    /// Backends whose response was served from the response cache
+    pub cache_hits: Vec<String>,
+
+    /// Winning normalized answer (for vote mode)
+    pub vote: Option<String>,
+
+    /// Share of the vote behind the winning answer (for vote mode)
+    pub agreement: Option<f64>,
+
+    /// Backends that disagreed with the winning answer (for vote mode)
+    pub dissenters: Vec<String>,
+
+    /// Conversation that produced `output`, for `continue_from` (query steps)
+    pub messages: Vec<Message>,
+
+    /// Results of the sub-workflow's steps (workflow steps)
+    pub steps: HashMap<String, StepResult>,
+
+    /// Result of each iteration, in item order (for_each steps)
+    pub items: Vec<StepResult>,
+
+    /// Result of each combination, keyed like `claude/security` (matrix steps)
+    pub matrix: HashMap<String, StepResult>,
 }
 
 impl StepResult {
@@ -264,7 +289,73 @@ pub fn load_workflow(name: &str, project_dir: Option<&Path>) -> Result<WorkflowC
     anyhow::bail!("workflow '{}' not found", name)
 }
 
-fn load_workflow_file(path: &Path) -> Result<WorkflowConfig> {
+/// Load a workflow called by a workflow step, preferring `dir` over the usual locations
+pub fn load_sub_workflow(
+    name: &str,
+    dir: Option<&Path>,
+    project_dir: Option<&Path>,
+) -> Result<WorkflowConfig> {
+    if let Some(dir) = dir {
+        let path = dir.join(format!("{}.toml", name));
+        if path.exists() {
+            return load_workflow_file(&path);
+        }
+    }
+    load_workflow(name, project_dir)
+}
+
+/// Check that the workflows a workflow calls exist and don't call each other in a cycle
+pub fn check_sub_workflows(
+    workflow: &WorkflowConfig,
+    dir: Option<&Path>,
+    project_dir: Option<&Path>,
+) -> std::result::Result<(), Vec<String>> {
+    fn visit(
+        workflow: &WorkflowConfig,
+        dir: Option<&Path>,
+        project_dir: Option<&Path>,
+        stack: &mut Vec<String>,
+        errors: &mut Vec<String>,
+    ) {
+        for step in workflow.all_steps() {
+            let Some(ref child) = step.workflow else {
+                continue;
+            };
+            if let Some(start) = stack.iter().position(|name| name == child) {
+                errors.push(format!(
+                    "workflow cycle: {} -> {}",
+                    stack[start..].join(" -> "),
+                    child
+                ));
+                continue;
+            }
+            match load_sub_workflow(child, dir, project_dir) {
+                Ok(sub) => {
+                    stack.push(child.clone());
+                    visit(&sub, dir, project_dir, stack, errors);
+                    stack.pop();
+                }
+                Err(e) => errors.push(format!(
+                    "step '{}' calls workflow '{}': {:#}",
+                    step.name, child, e
+                )),
+            }
+        }
+    }
+
+    let mut errors = Vec::new();
+    let mut stack = vec![workflow.name.clone()];
+    visit(workflow, dir, project_dir, &mut stack, &mut errors);
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Load and validate a workflow from a file
+pub fn load_workflow_file(path: &Path) -> Result<WorkflowConfig> {
     let contents =
         std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
     let workflow: WorkflowConfig =
@@ -370,4 +461,34 @@ mod tests {
         assert!(failure.failed);
         assert_eq!(failure.error, Some("timeout".into()));
     }
+
+    #[test]
+    fn test_check_sub_workflows() {
+        let dir = TempDir::new().unwrap();
+        let write = |name: &str, calls: &str| {
+            std::fs::write(
+                dir.path().join(format!("{}.toml", name)),
+                format!(
+                    "name = \"{}\"\n[[steps]]\nname = \"call\"\ntype = \"workflow\"\nworkflow = \"{}\"\n",
+                    name, calls
+                ),
+            )
+            .unwrap();
+        };
+        write("a", "b");
+        write("b", "a");
+        write("c", "missing");
+
+        let a = load_workflow_file(&dir.path().join("a.toml")).unwrap();
+        let errors = check_sub_workflows(&a, Some(dir.path()), Some(dir.path())).unwrap_err();
+        assert_eq!(errors, vec!["workflow cycle: a -> b -> a"]);
+
+        let c = load_workflow_file(&dir.path().join("c.toml")).unwrap();
+        let errors = check_sub_workflows(&c, Some(dir.path()), Some(dir.path())).unwrap_err();
+        assert!(
+            errors[0].contains("workflow 'missing' not found"),
+            "{:?}",
+            errors
+        );
+    }
 }
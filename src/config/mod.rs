@@ -7,11 +7,21 @@ mod loader;
 mod role;
 mod workflow;
 
-pub use backend::BackendConfig;
+#[allow(unused_imports)]
+pub use backend::{BackendConfig, BackendRequirements};
 #[allow(unused_imports)]
 pub use ecosystem::{EcosystemConfig, ProjectConfig};
-pub use loader::{LlmuxConfig, StepResult, load_workflow};
+pub use loader::{
+    LlmuxConfig, StepResult, check_sub_workflows, load_sub_workflow, load_workflow,
+    load_workflow_file,
+};
 #[allow(unused_imports)]
-pub use role::{RoleConfig, RoleExecution, RoleOverride, TeamConfig};
+pub use role::{
+    BackendOrder, ErrorClass, Optimize, QuorumMode, RoleConfig, RoleExecution, RoleOverride,
+    TeamConfig, default_fallback_on,
+};
 #[allow(unused_imports)]
-pub use workflow::{OutputSchema, PropertySchema, StepConfig, StepType, WorkflowConfig};
+pub use workflow::{
+    ArgDef, ArgType, BUILTIN_TOOLS, CacheSetting, ChunkBy, OutputSchema, PropertySchema,
+    StepConfig, StepType, ToolConfig, WorkflowConfig, parse_duration,
+};
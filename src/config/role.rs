@@ -16,6 +16,100 @@ pub enum RoleExecution {
     Parallel,
     /// Try each backend until one succeeds
     Fallback,
+    /// Start backends concurrently, take the first success and cancel the rest
+    Race,
+    /// Run all backends in parallel and pick the majority answer
+    Vote,
+}
+
+impl RoleExecution {
+    /// Name as written in config
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RoleExecution::First => "first",
+            RoleExecution::Parallel => "parallel",
+            RoleExecution::Fallback => "fallback",
+            RoleExecution::Race => "race",
+            RoleExecution::Vote => "vote",
+        }
+    }
+}
+
+impl std::fmt::Display for RoleExecution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Order in which a role tries its backends
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendOrder {
+    /// As listed in `backends` (default)
+    #[default]
+    Config,
+    /// Healthy backends first, ranked by recorded statistics
+    Adaptive,
+}
+
+/// What adaptive ordering optimizes for
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Optimize {
+    /// Lowest median latency first (default)
+    #[default]
+    Latency,
+    /// Lowest cost per token first
+    Cost,
+}
+
+/// When a parallel role stops waiting for its backends
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum QuorumMode {
+    /// Wait for every backend (default)
+    #[default]
+    Wait,
+    /// Return once `min_success` backends succeed and cancel the rest
+    Cancel,
+    /// Return once `min_success` backends succeed; let the rest finish in the background
+    Background,
+}
+
+/// Class of backend error, used to decide whether `fallback` moves on
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    Timeout,
+    RateLimit,
+    Auth,
+    Network,
+    Parse,
+    ExecutionFailed,
+    Unavailable,
+    Config,
+}
+
+impl ErrorClass {
+    /// Name as written in config
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorClass::Timeout => "timeout",
+            ErrorClass::RateLimit => "rate_limit",
+            ErrorClass::Auth => "auth",
+            ErrorClass::Network => "network",
+            ErrorClass::Parse => "parse",
+            ErrorClass::ExecutionFailed => "execution_failed",
+            ErrorClass::Unavailable => "unavailable",
+            ErrorClass::Config => "config",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// Configuration for a role (task type)
@@ -33,22 +127,73 @@ pub struct RoleConfig {
     #[serde(default)]
     pub execution: RoleExecution,
 
+    /// Order in which backends are tried
+    #[serde(default)]
+    pub order: BackendOrder,
+
+    /// What `order = "adaptive"` optimizes for
+    #[serde(default)]
+    pub optimize: Optimize,
+
     /// Minimum successful backends required (for parallel mode)
     #[serde(default = "default_min_success")]
     pub min_success: u32,
+
+    /// Return as soon as `min_success` is reached (for parallel and vote modes)
+    #[serde(default)]
+    pub quorum: QuorumMode,
+
+    /// Number of backends to race (for race mode, default: all)
+    pub race_width: Option<u32>,
+
+    /// Only start the next racer if no backend has answered within this many ms
+    pub hedge_after_ms: Option<u64>,
+
+    /// Error classes that make `fallback` move on to the next backend
+    #[serde(default = "default_fallback_on")]
+    pub fallback_on: Vec<ErrorClass>,
+
+    /// Vote weight per backend (for vote mode, default: 1.0)
+    #[serde(default)]
+    pub weights: HashMap<String, f64>,
+
+    /// Backend whose answer wins a tied vote (default: earliest in `backends`)
+    pub tie_break: Option<String>,
+
+    /// Role that merges parallel outputs into a single answer
+    pub judge: Option<String>,
 }
 
 fn default_min_success() -> u32 {
     1
 }
 
+/// Transient errors worth trying another backend for; auth and bad requests are not
+pub fn default_fallback_on() -> Vec<ErrorClass> {
+    vec![
+        ErrorClass::Timeout,
+        ErrorClass::RateLimit,
+        ErrorClass::Network,
+        ErrorClass::Unavailable,
+    ]
+}
+
 impl Default for RoleConfig {
     fn default() -> Self {
         Self {
             description: String::new(),
             backends: Vec::new(),
             execution: RoleExecution::First,
+            order: BackendOrder::Config,
+            optimize: Optimize::Latency,
             min_success: 1,
+            quorum: QuorumMode::Wait,
+            race_width: None,
+            hedge_after_ms: None,
+            fallback_on: default_fallback_on(),
+            weights: HashMap::new(),
+            tie_break: None,
+            judge: None,
         }
     }
 }
@@ -127,6 +272,95 @@ mod tests {
         assert_eq!(config.min_success, 2);
     }
 
+    #[test]
+    fn test_role_config_quorum() {
+        let config: RoleConfig = toml::from_str(r#"backends = ["claude"]"#).unwrap();
+        assert_eq!(config.quorum, QuorumMode::Wait);
+
+        let toml = r#"
+            backends = ["claude", "codex", "gemini"]
+            execution = "parallel"
+            min_success = 2
+            quorum = "background"
+        "#;
+        let config: RoleConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.quorum, QuorumMode::Background);
+    }
+
+    #[test]
+    fn test_role_config_adaptive_order() {
+        let config: RoleConfig = toml::from_str(r#"backends = ["claude"]"#).unwrap();
+        assert_eq!(config.order, BackendOrder::Config);
+
+        let toml = r#"
+            backends = ["claude", "codex", "ollama"]
+            order = "adaptive"
+            optimize = "cost"
+        "#;
+        let config: RoleConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.order, BackendOrder::Adaptive);
+        assert_eq!(config.optimize, Optimize::Cost);
+    }
+
+    #[test]
+    fn test_role_config_race() {
+        let toml = r#"
+            backends = ["claude", "codex", "gemini"]
+            execution = "race"
+            race_width = 2
+            hedge_after_ms = 1500
+        "#;
+        let config: RoleConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.execution, RoleExecution::Race);
+        assert_eq!(config.race_width, Some(2));
+        assert_eq!(config.hedge_after_ms, Some(1500));
+    }
+
+    #[test]
+    fn test_role_config_vote() {
+        let toml = r#"
+            backends = ["claude", "codex", "gemini"]
+            execution = "vote"
+            tie_break = "claude"
+
+            [weights]
+            claude = 2.0
+        "#;
+        let config: RoleConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.execution, RoleExecution::Vote);
+        assert_eq!(config.tie_break.as_deref(), Some("claude"));
+        assert_eq!(config.weights.get("claude"), Some(&2.0));
+    }
+
+    #[test]
+    fn test_role_config_judge() {
+        let toml = r#"
+            backends = ["claude", "codex"]
+            execution = "parallel"
+            judge = "synthesizer"
+        "#;
+        let config: RoleConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.judge.as_deref(), Some("synthesizer"));
+    }
+
+    #[test]
+    fn test_role_config_fallback_on() {
+        let config: RoleConfig = toml::from_str(r#"backends = ["claude"]"#).unwrap();
+        assert!(config.fallback_on.contains(&ErrorClass::RateLimit));
+        assert!(!config.fallback_on.contains(&ErrorClass::Auth));
+
+        let toml = r#"
+            backends = ["claude", "codex"]
+            execution = "fallback"
+            fallback_on = ["rate_limit", "execution_failed"]
+        "#;
+        let config: RoleConfig = toml::from_str(toml).unwrap();
+        assert_eq!(
+            config.fallback_on,
+            vec![ErrorClass::RateLimit, ErrorClass::ExecutionFailed]
+        );
+    }
+
     #[test]
     fn test_team_config() {
         let toml = r#"
@@ -1,7 +1,11 @@
 //! Workflow and step configuration
 
+use super::backend::BackendRequirements;
+use super::loader::LlmuxConfig;
+use super::role::RoleExecution;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
 
 /// Step type - explicit, not inferred
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
@@ -17,22 +21,179 @@ pub enum StepType {
     Input,
     /// Store data in memory database
     Store,
+    /// Run another workflow
+    Workflow,
+    /// Repeat a list of steps until a condition holds
+    Loop,
+    /// Query each chunk of a large input, then combine the answers
+    #[serde(rename = "map_reduce")]
+    MapReduce,
+}
+
+/// How a map_reduce step splits its input
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkBy {
+    /// A number of lines per chunk
+    Lines,
+    /// A number of files per chunk, split at `diff --git` headers
+    Files,
+    /// About a number of tokens per chunk
+    #[default]
+    Tokens,
+}
+
+/// Response cache setting for a query step
+///
+/// `cache = true` caches responses without expiry, `cache = false` disables
+/// caching, and `cache = "24h"` caches responses for the given duration.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum CacheSetting {
+    Enabled(bool),
+    Ttl(String),
+}
+
+/// Built-in tools that query steps can expose to the model
+pub const BUILTIN_TOOLS: &[&str] = &["read_file", "grep", "list_files"];
+
+/// A tool a query step exposes to the model
+///
+/// Either a built-in (`builtin = "read_file"`) or a shell command (`run`)
+/// whose declared arguments are passed as `ARG_<NAME>` environment variables,
+/// upper-cased with other characters mapped to `_` (`dry-run` becomes
+/// `ARG_DRY_RUN`). Commands only inherit `PATH`, `HOME`, `USER`, `LANG` and
+/// `TMPDIR` from the environment.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ToolConfig {
+    /// Built-in tool to expose
+    pub builtin: Option<String>,
+
+    /// Name shown to the model (defaults to the built-in's name)
+    pub name: Option<String>,
+
+    /// What the tool does, shown to the model
+    #[serde(default)]
+    pub description: String,
+
+    /// JSON Schema of the tool's arguments (for `run` tools)
+    pub parameters: Option<serde_json::Value>,
+
+    /// Shell command to run in the working directory
+    pub run: Option<String>,
+}
+
+/// Type of a workflow argument
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArgType {
+    /// Any text
+    #[default]
+    String,
+    /// A whole number
+    Int,
+    /// true/false, yes/no or 1/0
+    Bool,
+    /// One of `choices`
+    Enum,
+    /// An existing file or directory
+    Path,
+    /// An existing file
+    File,
+    /// A JSON value
+    Json,
+}
+
+impl ArgType {
+    /// Name shown in usage text
+    pub fn name(self) -> &'static str {
+        match self {
+            ArgType::String => "string",
+            ArgType::Int => "int",
+            ArgType::Bool => "bool",
+            ArgType::Enum => "enum",
+            ArgType::Path => "path",
+            ArgType::File => "file",
+            ArgType::Json => "json",
+        }
+    }
 }
 
 /// Argument definition for a workflow
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct ArgDef {
+    /// Value type
+    #[serde(default, rename = "type")]
+    pub arg_type: ArgType,
+
     /// Whether this argument is required
     #[serde(default)]
     pub required: bool,
 
     /// Default value if not provided
-    pub default: Option<String>,
+    pub default: Option<toml::Value>,
 
     /// Description for help text
     #[serde(default)]
     pub description: String,
+
+    /// Allowed values (for enum arguments)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<String>,
+
+    /// Regular expression the value must match
+    pub pattern: Option<String>,
+}
+
+impl ArgDef {
+    /// Default value as it would be given on the command line
+    pub fn default_value(&self) -> Option<String> {
+        self.default.as_ref().map(|value| match value {
+            toml::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+    }
+
+    /// Check a value against the type, choices and pattern, returning its typed form
+    ///
+    /// Paths are not checked for existence here.
+    pub fn parse(&self, value: &str) -> Result<serde_json::Value, String> {
+        if let Some(ref pattern) = self.pattern {
+            let re = regex::Regex::new(pattern)
+                .map_err(|e| format!("invalid pattern /{}/: {}", pattern, e))?;
+            if !re.is_match(value) {
+                return Err(format!("'{}' does not match /{}/", value, pattern));
+            }
+        }
+        if !self.choices.is_empty() && !self.choices.iter().any(|choice| choice == value) {
+            return Err(format!(
+                "'{}' is not one of: {}",
+                value,
+                self.choices.join(", ")
+            ));
+        }
+
+        match self.arg_type {
+            ArgType::Int => value
+                .trim()
+                .parse::<i64>()
+                .map(serde_json::Value::from)
+                .map_err(|_| format!("'{}' is not an integer", value)),
+            ArgType::Bool => match value.trim().to_lowercase().as_str() {
+                "true" | "yes" | "1" => Ok(serde_json::Value::Bool(true)),
+                "false" | "no" | "0" => Ok(serde_json::Value::Bool(false)),
+                _ => Err(format!("'{}' is not true or false", value)),
+            },
+            ArgType::Json => {
+                serde_json::from_str(value).map_err(|e| format!("invalid JSON: {}", e))
+            }
+            ArgType::String | ArgType::Enum | ArgType::Path | ArgType::File => {
+                Ok(serde_json::Value::String(value.to_string()))
+            }
+        }
+    }
 }
 
 /// Configuration for a workflow step
@@ -49,6 +210,9 @@ pub struct StepConfig {
     /// Role to use (for query steps)
     pub role: Option<String>,
 
+    /// Single backend to use instead of a role (for query steps)
+    pub backend: Option<String>,
+
     /// Run all backends in role in parallel
     #[serde(default)]
     pub parallel: bool,
@@ -59,6 +223,9 @@ pub struct StepConfig {
     /// Prompt template (for query steps)
     pub prompt: Option<String>,
 
+    /// System prompt template (for query steps)
+    pub system_prompt: Option<String>,
+
     /// Command to run (for shell steps)
     pub run: Option<String>,
 
@@ -90,10 +257,27 @@ pub struct StepConfig {
     /// Iterate over an array
     pub for_each: Option<String>,
 
+    /// Run once per combination of these values; `role` and `backend` keys pick what is queried
+    #[serde(default)]
+    pub matrix: BTreeMap<String, Vec<String>>,
+
+    /// Iterations run at once (default 1)
+    pub for_each_concurrency: Option<usize>,
+
+    /// Failed iterations tolerated; past this, the step fails and unstarted iterations are skipped
+    pub for_each_max_failures: Option<usize>,
+
+    /// Successful iterations needed for the step to succeed
+    pub for_each_min_success: Option<usize>,
+
     /// Continue workflow if this step fails
     #[serde(default)]
     pub continue_on_error: bool,
 
+    /// Handler steps to run if this step fails
+    #[serde(default)]
+    pub on_failure: Vec<String>,
+
     /// Timeout in milliseconds
     pub timeout: Option<u64>,
 
@@ -110,21 +294,100 @@ pub struct StepConfig {
 
     /// Human-readable options (for input steps)
     pub options: Option<Vec<String>>,
+
+    /// Cache backend responses (for query steps)
+    pub cache: Option<CacheSetting>,
+
+    /// JSON path of the answer to compare (for query steps with a vote role)
+    pub extract: Option<String>,
+
+    /// Role that merges parallel outputs into one answer (overrides the role's `judge`)
+    pub synthesize: Option<String>,
+
+    /// Merge prompt template for the synthesizer (sees `prompt` and `outputs`)
+    pub synthesize_prompt: Option<String>,
+
+    /// Capabilities a backend must have to run this step (for query steps)
+    #[serde(default)]
+    pub requires: BackendRequirements,
+
+    /// Capabilities that move a backend ahead in the role's order (for query steps)
+    #[serde(default)]
+    pub prefers: BackendRequirements,
+
+    /// Query step whose conversation this step continues with a new user turn
+    pub continue_from: Option<String>,
+
+    /// Tools the model may call while answering (for query steps)
+    #[serde(default)]
+    pub tools: Vec<ToolConfig>,
+
+    /// Maximum tool calls before the model must answer
+    #[serde(default = "default_max_tool_calls")]
+    pub max_tool_calls: u32,
+
+    /// Workflow to run (for workflow steps)
+    pub workflow: Option<String>,
+
+    /// Arguments passed to the sub-workflow, as templates (for workflow steps)
+    #[serde(default)]
+    pub with: HashMap<String, String>,
+
+    /// Steps repeated on each iteration, in order (for loop steps)
+    #[serde(default)]
+    pub steps: Vec<StepConfig>,
+
+    /// Condition checked after each iteration; the loop stops once it holds (for loop steps)
+    pub until: Option<String>,
+
+    /// Iterations run at most (for loop steps)
+    #[serde(default = "default_max_iterations")]
+    pub max_iterations: u32,
+
+    /// Fail the loop if `max_iterations` is reached before `until` holds (for loop steps)
+    #[serde(default)]
+    pub fail_on_max: bool,
+
+    /// Input template to split into chunks (for map_reduce steps)
+    pub input: Option<String>,
+
+    /// How to split the input (for map_reduce steps)
+    #[serde(default)]
+    pub chunk_by: ChunkBy,
+
+    /// Lines, files or approximate tokens per chunk (for map_reduce steps)
+    pub chunk_size: Option<usize>,
+
+    /// Prompt combining the mapped outputs, which it sees as `outputs` (for map_reduce steps)
+    pub reduce_prompt: Option<String>,
+
+    /// Chunks or matrix combinations run at once (for map_reduce and matrix steps, default 4)
+    pub concurrency: Option<usize>,
 }
 
 fn default_retry_delay() -> u64 {
     1000
 }
 
+fn default_max_tool_calls() -> u32 {
+    10
+}
+
+fn default_max_iterations() -> u32 {
+    5
+}
+
 impl Default for StepConfig {
     fn default() -> Self {
         Self {
             name: String::new(),
             step_type: StepType::Shell,
             role: None,
+            backend: None,
             parallel: false,
             min_success: None,
             prompt: None,
+            system_prompt: None,
             run: None,
             source: None,
             verify: None,
@@ -134,16 +397,107 @@ impl Default for StepConfig {
             depends_on: Vec::new(),
             condition: None,
             for_each: None,
+            matrix: BTreeMap::new(),
+            for_each_concurrency: None,
+            for_each_max_failures: None,
+            for_each_min_success: None,
             continue_on_error: false,
+            on_failure: Vec::new(),
             timeout: None,
             retries: 0,
             retry_delay: default_retry_delay(),
             output_schema: None,
             options: None,
+            cache: None,
+            extract: None,
+            synthesize: None,
+            synthesize_prompt: None,
+            requires: BackendRequirements::default(),
+            prefers: BackendRequirements::default(),
+            continue_from: None,
+            tools: Vec::new(),
+            max_tool_calls: default_max_tool_calls(),
+            workflow: None,
+            with: HashMap::new(),
+            steps: Vec::new(),
+            until: None,
+            max_iterations: default_max_iterations(),
+            fail_on_max: false,
+            input: None,
+            chunk_by: ChunkBy::default(),
+            chunk_size: None,
+            reduce_prompt: None,
+            concurrency: None,
+        }
+    }
+}
+
+impl StepConfig {
+    /// Whether the step names a role or backend to query, directly or through its matrix
+    pub fn has_role(&self) -> bool {
+        self.role.is_some()
+            || self.backend.is_some()
+            || self.matrix.contains_key("role")
+            || self.matrix.contains_key("backend")
+    }
+
+    /// Every combination of the matrix values, in key order, with its label (`claude/security`)
+    pub fn matrix_combinations(&self) -> Vec<(String, BTreeMap<String, String>)> {
+        let mut combinations = vec![BTreeMap::new()];
+        for (key, values) in &self.matrix {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|combination| {
+                    values.iter().map(move |value| {
+                        let mut combination = combination.clone();
+                        combination.insert(key.clone(), value.clone());
+                        combination
+                    })
+                })
+                .collect();
+        }
+
+        combinations
+            .into_iter()
+            .map(|combination| {
+                let label = combination.values().cloned().collect::<Vec<_>>().join("/");
+                (label, combination)
+            })
+            .collect()
+    }
+
+    /// Resolve the cache setting for this step
+    ///
+    /// Returns `None` when caching is disabled, `Some(None)` to cache without
+    /// expiry, and `Some(Some(ttl))` to cache with a time-to-live.
+    pub fn cache_ttl(&self) -> Option<Option<Duration>> {
+        match self.cache.as_ref()? {
+            CacheSetting::Enabled(true) => Some(None),
+            CacheSetting::Enabled(false) => None,
+            CacheSetting::Ttl(ttl) => parse_duration(ttl).map(Some),
         }
     }
 }
 
+/// Parse a human-readable duration like `500ms`, `30s`, `15m`, `24h` or `7d`
+///
+/// A bare number is interpreted as seconds.
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value: u64 = value.parse().ok()?;
+
+    match unit.trim() {
+        "ms" => Some(Duration::from_millis(value)),
+        "" | "s" => Some(Duration::from_secs(value)),
+        "m" => value.checked_mul(60).map(Duration::from_secs),
+        "h" => value.checked_mul(60 * 60).map(Duration::from_secs),
+        "d" => value.checked_mul(60 * 60 * 24).map(Duration::from_secs),
+        _ => None,
+    }
+}
+
 /// JSON Schema subset for output validation
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
@@ -193,19 +547,142 @@ pub struct WorkflowConfig {
     #[serde(default)]
     pub continue_on_error: bool,
 
+    /// Handler steps to run if the workflow fails
+    #[serde(default)]
+    pub on_failure: Vec<String>,
+
+    /// Steps that always run, in order, after the others
+    #[serde(default)]
+    pub finally: Vec<StepConfig>,
+
     /// Steps in this workflow
     #[serde(default)]
     pub steps: Vec<StepConfig>,
 }
 
 impl WorkflowConfig {
+    /// All steps, including `finally` steps and those nested in loop bodies
+    pub fn all_steps(&self) -> Vec<&StepConfig> {
+        fn collect<'a>(steps: &'a [StepConfig], all: &mut Vec<&'a StepConfig>) {
+            for step in steps {
+                all.push(step);
+                collect(&step.steps, all);
+            }
+        }
+
+        let mut all = Vec::new();
+        collect(&self.steps, &mut all);
+        collect(&self.finally, &mut all);
+        all
+    }
+
+    /// Steps named by an `on_failure` hook, which only run when it fires
+    pub fn handlers(&self) -> std::collections::HashSet<&str> {
+        self.on_failure
+            .iter()
+            .chain(self.steps.iter().flat_map(|step| &step.on_failure))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Check steps against the roles they use
+    ///
+    /// Roles a team overrides are checked again when the step runs.
+    pub fn validate_roles(&self, config: &LlmuxConfig) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        for step in self.all_steps() {
+            let backends = step
+                .backend
+                .iter()
+                .chain(step.matrix.get("backend").into_iter().flatten());
+            for name in backends {
+                let Some(backend) = config.backends.get(name) else {
+                    continue;
+                };
+                if !backend.satisfies(&step.requires) {
+                    errors.push(format!(
+                        "step '{}' requires {}, which backend '{}' lacks",
+                        step.name, step.requires, name
+                    ));
+                }
+                if !step.tools.is_empty() && backend.is_cli() {
+                    errors.push(format!(
+                        "step '{}' has tools, which CLI backend '{}' can't call",
+                        step.name, name
+                    ));
+                }
+            }
+
+            // A matrix role axis replaces the step's role in every combination
+            let roles = match step.matrix.get("role") {
+                Some(values) => values.as_slice(),
+                None => step.role.as_slice(),
+            };
+            for name in roles {
+                let Some(role) = config.roles.get(name) else {
+                    let in_team = config
+                        .teams
+                        .values()
+                        .any(|team| team.roles.contains_key(name));
+                    if step.matrix.contains_key("role") && !in_team {
+                        errors.push(format!(
+                            "matrix role '{}' of step '{}' is not defined",
+                            name, step.name
+                        ));
+                    }
+                    continue;
+                };
+                if !step.tools.is_empty()
+                    && role
+                        .backends
+                        .iter()
+                        .all(|name| config.backends.get(name).is_none_or(|b| b.is_cli()))
+                {
+                    errors.push(format!(
+                        "step '{}' has tools, but role '{}' has no HTTP or Claude API backend to call them",
+                        step.name, name
+                    ));
+                }
+                if (step.parallel || step.min_success.is_some())
+                    && !matches!(
+                        role.execution,
+                        RoleExecution::First | RoleExecution::Fallback | RoleExecution::Parallel
+                    )
+                {
+                    errors.push(format!(
+                        "step '{}' sets 'parallel' or 'min_success', but role '{}' runs in {} mode",
+                        step.name, name, role.execution
+                    ));
+                }
+                let execution = if step.parallel {
+                    RoleExecution::Parallel
+                } else {
+                    role.execution
+                };
+                if step.synthesize.is_some() && execution != RoleExecution::Parallel {
+                    errors.push(format!(
+                        "step '{}' sets 'synthesize', but role '{}' runs in {} mode, not parallel",
+                        step.name, name, execution
+                    ));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     /// Validate the workflow configuration
     pub fn validate(&self) -> Result<(), Vec<String>> {
         let mut errors = Vec::new();
 
         // Check for duplicate step names
         let mut seen_names = std::collections::HashSet::new();
-        for step in &self.steps {
+        for step in self.steps.iter().chain(&self.finally) {
             if !seen_names.insert(&step.name) {
                 errors.push(format!("duplicate step name: {}", step.name));
             }
@@ -225,8 +702,154 @@ impl WorkflowConfig {
             }
         }
 
-        // Check step type requirements
+        // Check argument definitions
+        let mut arg_names: Vec<_> = self.args.keys().collect();
+        arg_names.sort();
+        for name in arg_names {
+            let arg = &self.args[name];
+            if arg.arg_type == ArgType::Enum && arg.choices.is_empty() {
+                errors.push(format!("enum argument '{}' has no 'choices'", name));
+            }
+            if let Some(ref pattern) = arg.pattern
+                && let Err(e) = regex::Regex::new(pattern)
+            {
+                errors.push(format!(
+                    "argument '{}' has an invalid pattern /{}/: {}",
+                    name, pattern, e
+                ));
+            } else if let Some(default) = arg.default_value()
+                && let Err(e) = arg.parse(&default)
+            {
+                errors.push(format!("default of argument '{}' is invalid: {}", name, e));
+            }
+        }
+
+        // Check on_failure hooks
+        let hooks = self
+            .on_failure
+            .iter()
+            .map(|handler| ("the workflow".to_string(), handler));
+        let step_hooks = self.steps.iter().flat_map(|step| {
+            step.on_failure
+                .iter()
+                .map(move |handler| (format!("step '{}'", step.name), handler))
+        });
+        let all_steps = self.all_steps();
+        for (owner, handler) in hooks.chain(step_hooks) {
+            if step_names.contains(handler.as_str()) {
+                continue;
+            }
+            if all_steps.iter().any(|step| &step.name == handler) {
+                errors.push(format!(
+                    "on_failure of {} names '{}', which is not a top-level step",
+                    owner, handler
+                ));
+            } else {
+                errors.push(format!(
+                    "on_failure of {} names unknown step '{}'",
+                    owner, handler
+                ));
+            }
+        }
+        // Hooks only fire for top-level steps
+        for step in &all_steps {
+            let top_level = self.steps.iter().any(|top| std::ptr::eq(top, *step));
+            if !step.on_failure.is_empty() && !top_level {
+                errors.push(format!(
+                    "step '{}' sets 'on_failure', but only top-level steps run failure hooks",
+                    step.name
+                ));
+            }
+        }
+        let handlers = self.handlers();
         for step in &self.steps {
+            for dep in &step.depends_on {
+                if handlers.contains(dep.as_str()) {
+                    errors.push(format!(
+                        "step '{}' depends on '{}', which only runs on failure",
+                        step.name, dep
+                    ));
+                }
+            }
+        }
+
+        // Synthesizing needs several outputs to merge
+        for step in self.all_steps() {
+            if step.synthesize.is_none() {
+                continue;
+            }
+            if step.step_type != StepType::Query {
+                errors.push(format!(
+                    "'synthesize' is only supported on query steps (step '{}')",
+                    step.name
+                ));
+            } else if step.backend.is_some() {
+                errors.push(format!(
+                    "step '{}' sets 'synthesize' but asks a single backend",
+                    step.name
+                ));
+            }
+        }
+
+        // Check for_each options
+        for step in self.all_steps() {
+            let has_options = step.for_each_concurrency.is_some()
+                || step.for_each_max_failures.is_some()
+                || step.for_each_min_success.is_some();
+            if has_options && step.for_each.is_none() {
+                errors.push(format!(
+                    "step '{}' sets for_each options without 'for_each'",
+                    step.name
+                ));
+            }
+            // The backend would win in every combination, leaving the role axis unused
+            if step.matrix.contains_key("role")
+                && (step.backend.is_some() || step.matrix.contains_key("backend"))
+            {
+                errors.push(format!(
+                    "step '{}' cannot combine a matrix 'role' axis with a backend",
+                    step.name
+                ));
+            }
+            if !step.matrix.is_empty() && step.for_each.is_some() {
+                errors.push(format!(
+                    "step '{}' cannot use both 'matrix' and 'for_each'",
+                    step.name
+                ));
+            }
+            for (key, values) in &step.matrix {
+                if values.is_empty() {
+                    errors.push(format!(
+                        "matrix key '{}' of step '{}' has no values",
+                        key, step.name
+                    ));
+                }
+                // Values make up the combination labels results are stored under
+                let mut seen = std::collections::HashSet::new();
+                for value in values {
+                    if value.contains('/') {
+                        errors.push(format!(
+                            "matrix value '{}' of step '{}' cannot contain '/'",
+                            value, step.name
+                        ));
+                    } else if !seen.insert(value) {
+                        errors.push(format!(
+                            "matrix key '{}' of step '{}' repeats value '{}'",
+                            key, step.name, value
+                        ));
+                    }
+                }
+            }
+            if step.for_each_concurrency == Some(0) {
+                errors.push(format!(
+                    "step '{}' needs 'for_each_concurrency' of at least 1",
+                    step.name
+                ));
+            }
+        }
+
+        // Check step type requirements
+        for step in self.all_steps() {
             match step.step_type {
                 StepType::Shell => {
                     if step.run.is_none() {
@@ -234,12 +857,52 @@ impl WorkflowConfig {
                     }
                 }
                 StepType::Query => {
+                    if let Some(CacheSetting::Ttl(ref ttl)) = step.cache
+                        && parse_duration(ttl).is_none()
+                    {
+                        errors.push(format!(
+                            "query step '{}' has invalid cache duration '{}'",
+                            step.name, ttl
+                        ));
+                    }
                     if step.prompt.is_none() {
                         errors.push(format!("query step '{}' missing 'prompt' field", step.name));
                     }
-                    if step.role.is_none() {
+                    if !step.has_role() {
                         errors.push(format!("query step '{}' missing 'role' field", step.name));
                     }
+                    for tool in &step.tools {
+                        match (&tool.builtin, &tool.run) {
+                            (Some(builtin), None) => {
+                                if !BUILTIN_TOOLS.contains(&builtin.as_str()) {
+                                    errors.push(format!(
+                                        "query step '{}' uses unknown built-in tool '{}'",
+                                        step.name, builtin
+                                    ));
+                                }
+                            }
+                            (None, Some(_)) => {
+                                if tool.name.is_none() {
+                                    errors.push(format!(
+                                        "query step '{}' has a 'run' tool without a 'name'",
+                                        step.name
+                                    ));
+                                }
+                            }
+                            _ => errors.push(format!(
+                                "query step '{}' has a tool that needs exactly one of 'builtin' or 'run'",
+                                step.name
+                            )),
+                        }
+                    }
+                    if let Some(ref from) = step.continue_from
+                        && !step.depends_on.contains(from)
+                    {
+                        errors.push(format!(
+                            "query step '{}' continues from '{}' but does not depend on it",
+                            step.name, from
+                        ));
+                    }
                 }
                 StepType::Apply => {
                     if step.source.is_none() {
@@ -259,6 +922,76 @@ impl WorkflowConfig {
                         ));
                     }
                 }
+                StepType::Workflow => {
+                    if step.workflow.is_none() {
+                        errors.push(format!(
+                            "workflow step '{}' missing 'workflow' field",
+                            step.name
+                        ));
+                    }
+                }
+                StepType::MapReduce => {
+                    for (field, missing) in [
+                        ("input", step.input.is_none()),
+                        ("prompt", step.prompt.is_none()),
+                        ("role", !step.has_role()),
+                    ] {
+                        if missing {
+                            errors.push(format!(
+                                "map_reduce step '{}' missing '{}' field",
+                                step.name, field
+                            ));
+                        }
+                    }
+                    for (field, value) in [
+                        ("chunk_size", step.chunk_size),
+                        ("concurrency", step.concurrency),
+                    ] {
+                        if value == Some(0) {
+                            errors.push(format!(
+                                "map_reduce step '{}' needs '{}' of at least 1",
+                                step.name, field
+                            ));
+                        }
+                    }
+                }
+                StepType::Loop => {
+                    if step.until.is_none() {
+                        errors.push(format!("loop step '{}' missing 'until' field", step.name));
+                    }
+                    if step.steps.is_empty() {
+                        errors.push(format!("loop step '{}' has no steps", step.name));
+                    }
+                    if step.max_iterations == 0 {
+                        errors.push(format!(
+                            "loop step '{}' needs 'max_iterations' of at least 1",
+                            step.name
+                        ));
+                    }
+                    let mut earlier = std::collections::HashSet::new();
+                    for inner in &step.steps {
+                        if inner.for_each.is_some() {
+                            errors.push(format!(
+                                "step '{}' in loop '{}' cannot use 'for_each'",
+                                inner.name, step.name
+                            ));
+                        }
+                        for dep in &inner.depends_on {
+                            if !earlier.contains(dep.as_str()) {
+                                errors.push(format!(
+                                    "step '{}' in loop '{}' depends on '{}', which is not an earlier step of the loop",
+                                    inner.name, step.name, dep
+                                ));
+                            }
+                        }
+                        if !earlier.insert(inner.name.as_str()) {
+                            errors.push(format!(
+                                "duplicate step name in loop '{}': {}",
+                                step.name, inner.name
+                            ));
+                        }
+                    }
+                }
             }
         }
 
@@ -327,6 +1060,576 @@ mod tests {
         assert_eq!(workflow.steps.len(), 1);
     }
 
+    #[test]
+    fn test_step_config_cache() {
+        let step: StepConfig = toml::from_str(
+            r#"
+            name = "analyze"
+            type = "query"
+            cache = true
+        "#,
+        )
+        .unwrap();
+        assert_eq!(step.cache_ttl(), Some(None));
+
+        let step: StepConfig = toml::from_str(
+            r#"
+            name = "analyze"
+            type = "query"
+            cache = "24h"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(step.cache_ttl(), Some(Some(Duration::from_secs(86400))));
+
+        let step: StepConfig = toml::from_str(
+            r#"
+            name = "analyze"
+            type = "query"
+            cache = false
+        "#,
+        )
+        .unwrap();
+        assert_eq!(step.cache_ttl(), None);
+
+        // An overflowing TTL is a validation error rather than a panic
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+            name = "huge-ttl"
+
+            [[steps]]
+            name = "analyze"
+            type = "query"
+            role = "analyzer"
+            prompt = "Analyze"
+            cache = "999999999999999d"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(workflow.steps[0].cache_ttl(), None);
+        let errors = workflow.validate().unwrap_err();
+        assert!(errors.iter().any(|e| e.contains("invalid cache duration")));
+    }
+
+    #[test]
+    fn test_step_config_requires() {
+        let toml = r#"
+            name = "describe"
+            type = "query"
+            role = "vision"
+            prompt = "Describe the screenshot"
+
+            [requires]
+            vision = true
+            min_context = 32000
+
+            [prefers]
+            local = true
+        "#;
+        let step: StepConfig = toml::from_str(toml).unwrap();
+        assert!(step.requires.vision);
+        assert_eq!(step.requires.min_context, Some(32000));
+        assert!(step.prefers.local);
+        assert!(StepConfig::default().requires.is_empty());
+    }
+
+    #[test]
+    fn test_step_config_tools() {
+        let toml = r#"
+            name = "investigate"
+            type = "query"
+            role = "analyzer"
+            prompt = "Why does the build fail?"
+            max_tool_calls = 4
+
+            [[tools]]
+            builtin = "read_file"
+
+            [[tools]]
+            name = "check"
+            description = "Run cargo check on a package"
+            run = "cargo check -p $PACKAGE"
+            parameters = { type = "object", properties = { package = { type = "string" } } }
+        "#;
+        let step: StepConfig = toml::from_str(toml).unwrap();
+        assert_eq!(step.tools.len(), 2);
+        assert_eq!(step.max_tool_calls, 4);
+        assert_eq!(
+            step.tools[1].parameters.as_ref().unwrap()["properties"]["package"]["type"],
+            "string"
+        );
+        assert_eq!(StepConfig::default().max_tool_calls, 10);
+
+        let workflow = WorkflowConfig {
+            name: "tools".into(),
+            steps: vec![StepConfig {
+                tools: vec![
+                    ToolConfig {
+                        builtin: Some("rm_rf".into()),
+                        ..step.tools[0].clone()
+                    },
+                    ToolConfig {
+                        name: None,
+                        ..step.tools[1].clone()
+                    },
+                ],
+                ..step
+            }],
+            ..Default::default()
+        };
+        let errors = workflow.validate().unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("unknown built-in tool 'rm_rf'"));
+    }
+
+    #[test]
+    fn test_continue_from_requires_dependency() {
+        let toml = r#"
+            name = "followup"
+
+            [[steps]]
+            name = "analyze"
+            type = "query"
+            role = "analyzer"
+            prompt = "Analyze the bug"
+
+            [[steps]]
+            name = "fix"
+            type = "query"
+            role = "analyzer"
+            prompt = "Now propose a fix"
+            continue_from = "analyze"
+        "#;
+        let mut workflow: WorkflowConfig = toml::from_str(toml).unwrap();
+        let errors = workflow.validate().unwrap_err();
+        assert!(errors[0].contains("continues from 'analyze'"));
+
+        workflow.steps[1].depends_on = vec!["analyze".into()];
+        assert!(workflow.validate().is_ok());
+    }
+
+    #[test]
+    fn test_loop_validation() {
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "refine"
+
+[[steps]]
+name = "refine"
+type = "loop"
+
+[[steps.steps]]
+name = "revise"
+type = "query"
+role = "coder"
+prompt = "Revise"
+depends_on = ["critique"]
+
+[[steps.steps]]
+name = "critique"
+type = "shell"
+"#,
+        )
+        .unwrap();
+
+        let errors = workflow.validate().unwrap_err();
+        assert!(errors.contains(&"loop step 'refine' missing 'until' field".to_string()));
+        assert!(
+            errors
+                .iter()
+                .any(|e| e.contains("not an earlier step of the loop"))
+        );
+        assert!(errors.contains(&"shell step 'critique' missing 'run' field".to_string()));
+        assert_eq!(workflow.all_steps().len(), 3);
+    }
+
+    #[test]
+    fn test_failure_handler_validation() {
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "deploy"
+on_failure = ["page"]
+
+[[steps]]
+name = "deploy"
+type = "shell"
+run = "make deploy"
+on_failure = ["rollback"]
+
+[[steps]]
+name = "rollback"
+type = "shell"
+run = "make rollback"
+
+[[steps]]
+name = "announce"
+type = "shell"
+run = "echo done"
+depends_on = ["rollback"]
+
+[[finally]]
+name = "deploy"
+type = "shell"
+run = "make clean"
+"#,
+        )
+        .unwrap();
+
+        let errors = workflow.validate().unwrap_err();
+        assert!(
+            errors
+                .iter()
+                .any(|e| e.contains("names unknown step 'page'"))
+        );
+        assert!(
+            errors
+                .iter()
+                .any(|e| e.contains("which only runs on failure"))
+        );
+        assert!(errors.contains(&"duplicate step name: deploy".to_string()));
+        assert_eq!(
+            workflow.handlers(),
+            ["page", "rollback"].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn test_failure_hooks_only_on_top_level_steps() {
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "refine"
+on_failure = ["cleanup"]
+
+[[steps]]
+name = "refine"
+type = "loop"
+until = "true"
+
+[[steps.steps]]
+name = "attempt"
+type = "shell"
+run = "make"
+on_failure = ["refine"]
+
+[[finally]]
+name = "cleanup"
+type = "shell"
+run = "make clean"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            workflow.validate().unwrap_err(),
+            vec![
+                "on_failure of the workflow names 'cleanup', which is not a top-level step",
+                "step 'attempt' sets 'on_failure', but only top-level steps run failure hooks",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arg_validation() {
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "args"
+
+[args.mode]
+type = "enum"
+
+[args.count]
+type = "int"
+default = "lots"
+
+[args.id]
+pattern = "("
+
+[[steps]]
+name = "noop"
+type = "shell"
+run = "true"
+"#,
+        )
+        .unwrap();
+
+        let errors = workflow.validate().unwrap_err();
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors.contains(&"enum argument 'mode' has no 'choices'".to_string()));
+        assert!(
+            errors
+                .iter()
+                .any(|e| e.contains("'lots' is not an integer"))
+        );
+        assert!(errors.iter().any(|e| e.contains("invalid pattern /(/")));
+
+        let count = &workflow.args["count"];
+        assert_eq!(count.parse(" 7 "), Ok(serde_json::json!(7)));
+    }
+
+    #[test]
+    fn test_synthesize_needs_parallel_role() {
+        let mut workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "merge"
+
+[[steps]]
+name = "ask"
+type = "query"
+role = "solo"
+prompt = "Hi"
+synthesize = "judge"
+
+[[steps]]
+name = "direct"
+type = "query"
+backend = "claude"
+prompt = "Hi"
+synthesize = "judge"
+"#,
+        )
+        .unwrap();
+
+        let errors = workflow.validate().unwrap_err();
+        assert_eq!(
+            errors,
+            vec!["step 'direct' sets 'synthesize' but asks a single backend"]
+        );
+
+        let mut config = LlmuxConfig::default();
+        config.roles.insert(
+            "solo".into(),
+            crate::config::RoleConfig {
+                backends: vec!["claude".into()],
+                ..Default::default()
+            },
+        );
+        let errors = workflow.validate_roles(&config).unwrap_err();
+        assert_eq!(
+            errors,
+            vec!["step 'ask' sets 'synthesize', but role 'solo' runs in first mode, not parallel"]
+        );
+
+        workflow.steps[0].parallel = true;
+        assert!(workflow.validate_roles(&config).is_ok());
+
+        // Forcing parallel would silently replace a vote or race
+        config.roles.get_mut("solo").unwrap().execution = RoleExecution::Vote;
+        let errors = workflow.validate_roles(&config).unwrap_err();
+        assert_eq!(
+            errors,
+            vec!["step 'ask' sets 'parallel' or 'min_success', but role 'solo' runs in vote mode"]
+        );
+    }
+
+    #[test]
+    fn test_tools_need_api_backends() {
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "investigate"
+
+[[steps]]
+name = "direct"
+type = "query"
+backend = "codex"
+prompt = "Why?"
+tools = [{ builtin = "grep" }]
+
+[[steps]]
+name = "via-role"
+type = "query"
+role = "coders"
+prompt = "Why?"
+tools = [{ builtin = "grep" }]
+"#,
+        )
+        .unwrap();
+        workflow.validate().unwrap();
+
+        let mut config = LlmuxConfig::default();
+        config.backends.insert(
+            "codex".into(),
+            crate::config::BackendConfig {
+                command: "codex".into(),
+                ..Default::default()
+            },
+        );
+        config.roles.insert(
+            "coders".into(),
+            crate::config::RoleConfig {
+                backends: vec!["codex".into()],
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            workflow.validate_roles(&config).unwrap_err(),
+            vec![
+                "step 'direct' has tools, which CLI backend 'codex' can't call",
+                "step 'via-role' has tools, but role 'coders' has no HTTP or Claude API backend to call them",
+            ]
+        );
+
+        // A role with an API backend is fine; its CLI backends are skipped
+        config.backends.insert(
+            "local".into(),
+            crate::config::BackendConfig {
+                command: "http://localhost:8080/v1".into(),
+                ..Default::default()
+            },
+        );
+        config
+            .roles
+            .get_mut("coders")
+            .unwrap()
+            .backends
+            .push("local".into());
+        assert_eq!(workflow.validate_roles(&config).unwrap_err().len(), 1);
+    }
+
+    #[test]
+    fn test_matrix_combinations() {
+        let step: StepConfig = toml::from_str(
+            r#"
+name = "review"
+type = "query"
+prompt = "Review for {{ matrix.focus }}"
+matrix = { backend = ["claude", "codex"], focus = ["security", "perf"] }
+"#,
+        )
+        .unwrap();
+
+        assert!(step.has_role());
+        let labels: Vec<String> = step
+            .matrix_combinations()
+            .into_iter()
+            .map(|(label, _)| label)
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                "claude/security",
+                "claude/perf",
+                "codex/security",
+                "codex/perf"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_matrix_values_must_label_uniquely() {
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "review"
+
+[[steps]]
+name = "review"
+type = "query"
+prompt = "Review for {{ matrix.focus }}"
+requires = { json = true }
+matrix = { backend = ["claude", "codex"], focus = ["perf", "a/b", "perf"] }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            workflow.validate().unwrap_err(),
+            vec![
+                "matrix value 'a/b' of step 'review' cannot contain '/'",
+                "matrix key 'focus' of step 'review' repeats value 'perf'",
+            ]
+        );
+
+        // Matrix backends are held to the step's requirements like a role's
+        let mut config = LlmuxConfig::default();
+        for (name, json) in [("claude", true), ("codex", false)] {
+            config.backends.insert(
+                name.into(),
+                crate::config::BackendConfig {
+                    supports_json: json,
+                    ..Default::default()
+                },
+            );
+        }
+        assert_eq!(
+            workflow.validate_roles(&config).unwrap_err(),
+            vec!["step 'review' requires json, which backend 'codex' lacks"]
+        );
+    }
+
+    #[test]
+    fn test_matrix_roles() {
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "review"
+
+[[steps]]
+name = "review"
+type = "query"
+backend = "claude"
+prompt = "Review as {{ matrix.role }}"
+matrix = { role = ["security", "perf"] }
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            workflow.validate().unwrap_err(),
+            vec!["step 'review' cannot combine a matrix 'role' axis with a backend"]
+        );
+
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "review"
+
+[[steps]]
+name = "review"
+type = "query"
+prompt = "Review as {{ matrix.role }}"
+synthesize = "Merge the reviews"
+matrix = { role = ["security", "perf", "style"] }
+"#,
+        )
+        .unwrap();
+        workflow.validate().unwrap();
+
+        // Each matrix role is checked as if the step named it
+        let mut config = LlmuxConfig::default();
+        config.roles.insert(
+            "security".into(),
+            crate::config::RoleConfig {
+                backends: vec!["claude".into()],
+                execution: RoleExecution::Parallel,
+                ..Default::default()
+            },
+        );
+        config.roles.insert(
+            "perf".into(),
+            crate::config::RoleConfig {
+                backends: vec!["claude".into()],
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            workflow.validate_roles(&config).unwrap_err(),
+            vec![
+                "step 'review' sets 'synthesize', but role 'perf' runs in first mode, not parallel",
+                "matrix role 'style' of step 'review' is not defined",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("30"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("15m"), Some(Duration::from_secs(900)));
+        assert_eq!(parse_duration("7d"), Some(Duration::from_secs(604800)));
+        assert_eq!(parse_duration("soon"), None);
+        assert_eq!(parse_duration("5w"), None);
+
+        // Overflowing durations are rejected, not wrapped
+        assert_eq!(parse_duration("999999999999999d"), None);
+        assert_eq!(parse_duration("999999999999999999999"), None);
+    }
+
     #[test]
     fn test_workflow_validation() {
         let workflow = WorkflowConfig {
@@ -336,15 +1639,19 @@ mod tests {
             args: HashMap::new(),
             timeout: None,
             continue_on_error: false,
+            on_failure: Vec::new(),
+            finally: Vec::new(),
             steps: vec![
                 StepConfig {
                     name: "good".into(),
                     step_type: StepType::Shell,
                     run: Some("echo test".into()),
                     role: None,
+                    backend: None,
                     parallel: false,
                     min_success: None,
                     prompt: None,
+                    system_prompt: None,
                     source: None,
                     verify: None,
                     verify_retries: 0,
@@ -353,21 +1660,48 @@ mod tests {
                     depends_on: vec![],
                     condition: None,
                     for_each: None,
+                    matrix: BTreeMap::new(),
+                    for_each_concurrency: None,
+                    for_each_max_failures: None,
+                    for_each_min_success: None,
                     continue_on_error: false,
+                    on_failure: Vec::new(),
                     timeout: None,
                     retries: 0,
                     retry_delay: 1000,
                     output_schema: None,
                     options: None,
+                    cache: None,
+                    extract: None,
+                    synthesize: None,
+                    synthesize_prompt: None,
+                    requires: BackendRequirements::default(),
+                    prefers: BackendRequirements::default(),
+                    continue_from: None,
+                    tools: Vec::new(),
+                    max_tool_calls: default_max_tool_calls(),
+                    workflow: None,
+                    with: HashMap::new(),
+                    steps: Vec::new(),
+                    until: None,
+                    max_iterations: default_max_iterations(),
+                    fail_on_max: false,
+                    input: None,
+                    chunk_by: ChunkBy::default(),
+                    chunk_size: None,
+                    reduce_prompt: None,
+                    concurrency: None,
                 },
                 StepConfig {
                     name: "bad".into(),
                     step_type: StepType::Query,
                     run: None,
                     role: None, // Missing!
+                    backend: None,
                     parallel: false,
                     min_success: None,
                     prompt: None, // Missing!
+                    system_prompt: None,
                     source: None,
                     verify: None,
                     verify_retries: 0,
@@ -376,12 +1710,37 @@ mod tests {
                     depends_on: vec!["nonexistent".into()], // Invalid!
                     condition: None,
                     for_each: None,
+                    matrix: BTreeMap::new(),
+                    for_each_concurrency: None,
+                    for_each_max_failures: None,
+                    for_each_min_success: None,
                     continue_on_error: false,
+                    on_failure: Vec::new(),
                     timeout: None,
                     retries: 0,
                     retry_delay: 1000,
                     output_schema: None,
                     options: None,
+                    cache: None,
+                    extract: None,
+                    synthesize: None,
+                    synthesize_prompt: None,
+                    requires: BackendRequirements::default(),
+                    prefers: BackendRequirements::default(),
+                    continue_from: None,
+                    tools: Vec::new(),
+                    max_tool_calls: default_max_tool_calls(),
+                    workflow: None,
+                    with: HashMap::new(),
+                    steps: Vec::new(),
+                    until: None,
+                    max_iterations: default_max_iterations(),
+                    fail_on_max: false,
+                    input: None,
+                    chunk_by: ChunkBy::default(),
+                    chunk_size: None,
+                    reduce_prompt: None,
+                    concurrency: None,
                 },
             ],
         };
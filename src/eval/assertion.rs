@@ -0,0 +1,238 @@
+//! Assertions checked against an answer
+
+use crate::backend_executor::output_parser::{extract_json, lookup};
+use crate::config::OutputSchema;
+use crate::workflow::validate_json_schema;
+use serde::{Deserialize, Serialize};
+
+/// An expectation about an answer
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Assertion {
+    /// The answer contains `value`
+    Contains {
+        value: String,
+        #[serde(default)]
+        ignore_case: bool,
+    },
+
+    /// The answer does not contain `value`
+    NotContains {
+        value: String,
+        #[serde(default)]
+        ignore_case: bool,
+    },
+
+    /// The answer matches a regular expression
+    Regex { pattern: String },
+
+    /// The answer is JSON matching a schema
+    Schema { schema: OutputSchema },
+
+    /// A field of the JSON answer (dotted path) equals `value`
+    Equals {
+        field: String,
+        value: serde_json::Value,
+    },
+
+    /// A judge role decides whether the answer meets the criteria
+    Judge { role: String, criteria: String },
+}
+
+/// Outcome of one assertion
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Check {
+    /// What was checked
+    pub assertion: String,
+
+    /// Whether it held
+    pub passed: bool,
+
+    /// Why it failed (or the judge's reasoning)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl Check {
+    fn pass(assertion: String) -> Self {
+        Self {
+            assertion,
+            passed: true,
+            reason: None,
+        }
+    }
+
+    fn fail(assertion: String, reason: impl Into<String>) -> Self {
+        Self {
+            assertion,
+            passed: false,
+            reason: Some(reason.into()),
+        }
+    }
+}
+
+impl Assertion {
+    /// Short description for reports
+    pub fn describe(&self) -> String {
+        match self {
+            Assertion::Contains { value, .. } => format!("contains {:?}", value),
+            Assertion::NotContains { value, .. } => format!("not_contains {:?}", value),
+            Assertion::Regex { pattern } => format!("regex /{}/", pattern),
+            Assertion::Schema { .. } => "schema".to_string(),
+            Assertion::Equals { field, value } => format!("{} == {}", field, value),
+            Assertion::Judge { role, .. } => format!("judge ({})", role),
+        }
+    }
+
+    /// Check the assertion's own configuration
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Assertion::Regex { pattern } => regex::Regex::new(pattern)
+                .map(|_| ())
+                .map_err(|e| format!("invalid regex /{}/: {}", pattern, e)),
+            Assertion::Equals { field, .. } if field.is_empty() => {
+                Err("'equals' assertion needs a 'field'".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check an answer locally; `None` for judge assertions, which need a backend
+    pub fn check(&self, output: &str) -> Option<Check> {
+        let assertion = self.describe();
+        let check = match self {
+            Assertion::Contains { value, ignore_case } => {
+                if contains(output, value, *ignore_case) {
+                    Check::pass(assertion)
+                } else {
+                    Check::fail(assertion, "not found in answer")
+                }
+            }
+            Assertion::NotContains { value, ignore_case } => {
+                if contains(output, value, *ignore_case) {
+                    Check::fail(assertion, "found in answer")
+                } else {
+                    Check::pass(assertion)
+                }
+            }
+            Assertion::Regex { pattern } => match regex::Regex::new(pattern) {
+                Ok(re) if re.is_match(output) => Check::pass(assertion),
+                Ok(_) => Check::fail(assertion, "no match"),
+                Err(e) => Check::fail(assertion, e.to_string()),
+            },
+            Assertion::Schema { schema } => match validate_json_schema(output, schema) {
+                Ok(()) => Check::pass(assertion),
+                Err(e) => Check::fail(assertion, e),
+            },
+            Assertion::Equals { field, value } => {
+                match extract_json(output)
+                    .as_ref()
+                    .map(|json| lookup(json, field))
+                {
+                    Some(Some(actual)) if actual == value => Check::pass(assertion),
+                    Some(Some(actual)) => Check::fail(assertion, format!("got {}", actual)),
+                    Some(None) => Check::fail(assertion, format!("no field '{}'", field)),
+                    None => Check::fail(assertion, "answer is not JSON"),
+                }
+            }
+            Assertion::Judge { .. } => return None,
+        };
+        Some(check)
+    }
+}
+
+fn contains(output: &str, value: &str, ignore_case: bool) -> bool {
+    if ignore_case {
+        output.to_lowercase().contains(&value.to_lowercase())
+    } else {
+        output.contains(value)
+    }
+}
+
+/// Prompt asking a judge role to grade an answer
+pub fn judge_prompt(input: &str, output: &str, criteria: &str) -> String {
+    format!(
+        "You are grading an answer to a request.\n\n\
+         Request:\n{}\n\n\
+         Answer:\n{}\n\n\
+         Criteria:\n{}\n\n\
+         Reply with PASS or FAIL on the first line, then one sentence explaining why.",
+        input, output, criteria
+    )
+}
+
+/// Read a judge's verdict: passed, and its explanation
+pub fn parse_verdict(reply: &str) -> (bool, Option<String>) {
+    let reply = reply.trim();
+    let (first, rest) = reply.split_once('\n').unwrap_or((reply, ""));
+    let passed = first
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_uppercase()
+        .starts_with("PASS");
+    let reason = if rest.trim().is_empty() {
+        first.trim()
+    } else {
+        rest.trim()
+    };
+    (passed, (!reason.is_empty()).then(|| reason.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assertion(toml: &str) -> Assertion {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_text_assertions() {
+        let contains = assertion("type = \"contains\"\nvalue = \"windows\"\nignore_case = true");
+        assert!(contains.check("Fails on Windows").unwrap().passed);
+
+        let not_contains = assertion("type = \"not_contains\"\nvalue = \"sorry\"");
+        assert!(!not_contains.check("sorry, no").unwrap().passed);
+
+        let regex = assertion("type = \"regex\"\npattern = \"^\\\\d+$\"");
+        assert!(regex.check("42").unwrap().passed);
+        assert!(!regex.check("forty-two").unwrap().passed);
+    }
+
+    #[test]
+    fn test_json_assertions() {
+        let equals = assertion("type = \"equals\"\nfield = \"issue.severity\"\nvalue = \"high\"");
+        let output = "```json\n{\"issue\": {\"severity\": \"high\"}}\n```";
+        assert!(equals.check(output).unwrap().passed);
+
+        let check = equals
+            .check("{\"issue\": {\"severity\": \"low\"}}")
+            .unwrap();
+        assert!(!check.passed);
+        assert_eq!(check.reason.as_deref(), Some("got \"low\""));
+        assert!(!equals.check("not json").unwrap().passed);
+
+        let schema =
+            assertion("type = \"schema\"\n[schema]\ntype = \"object\"\nrequired = [\"summary\"]");
+        assert!(schema.check("{\"summary\": \"ok\"}").unwrap().passed);
+        assert!(!schema.check("{}").unwrap().passed);
+    }
+
+    #[test]
+    fn test_judge_verdict() {
+        let judge = assertion("type = \"judge\"\nrole = \"judge\"\ncriteria = \"polite\"");
+        assert!(judge.check("anything").is_none());
+
+        assert_eq!(
+            parse_verdict("PASS\nIt is polite."),
+            (true, Some("It is polite.".to_string()))
+        );
+        assert!(!parse_verdict("**FAIL** - rude").0);
+        assert!(parse_verdict("pass").0);
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        let regex = assertion("type = \"regex\"\npattern = \"(\"");
+        assert!(regex.validate().is_err());
+    }
+}
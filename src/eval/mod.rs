@@ -0,0 +1,37 @@
+//! Evaluation harness for roles, backends and prompt variants
+//!
+//! An eval suite is a TOML file of targets (roles or backends, optionally with
+//! their own prompt) and cases (inputs with assertions). Every case runs
+//! against every target through the role executor, and the scored matrix is
+//! saved so later runs can be compared against it.
+//!
+//! ```toml
+//! dataset = "tickets.jsonl"   # optional, one case per line
+//!
+//! [[targets]]
+//! role = "analyzer"
+//!
+//! [[targets]]
+//! name = "analyzer-terse"
+//! role = "analyzer"
+//! prompt = "Answer in one line: {{ input }}"
+//!
+//! [[cases]]
+//! input = "Why does the build fail on Windows?"
+//! [[cases.assert]]
+//! type = "contains"
+//! value = "path"
+//! ```
+
+mod assertion;
+mod report;
+mod runner;
+mod suite;
+
+#[allow(unused_imports)]
+pub use assertion::{Assertion, Check};
+#[allow(unused_imports)]
+pub use report::{CaseResult, EvalReport, TargetSummary};
+pub use runner::EvalRunner;
+#[allow(unused_imports)]
+pub use suite::{EvalCase, EvalSuite, EvalTarget};
@@ -0,0 +1,351 @@
+//! Scored eval results, saved to disk for comparison across runs
+
+use super::assertion::Check;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Result of one case against one target
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CaseResult {
+    pub case: String,
+    pub target: String,
+    /// Backend that answered
+    pub backend: Option<String>,
+    pub output: Option<String>,
+    /// Why no answer was produced
+    pub error: Option<String>,
+    pub checks: Vec<Check>,
+    pub passed: bool,
+    pub duration_ms: u64,
+    pub tokens: Option<u32>,
+    pub cost: Option<f64>,
+}
+
+/// Totals for one target
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TargetSummary {
+    pub target: String,
+    pub cases: usize,
+    pub passed: usize,
+    pub pass_rate: f64,
+    pub avg_latency_ms: u64,
+    pub tokens: u64,
+    /// Total cost, where backends have a price
+    pub cost: Option<f64>,
+}
+
+/// A complete eval run
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EvalReport {
+    pub suite: String,
+    pub started_at: DateTime<Utc>,
+    pub targets: Vec<TargetSummary>,
+    pub results: Vec<CaseResult>,
+}
+
+impl EvalReport {
+    /// Build a report, summarizing results per target in the given order
+    pub fn new(
+        suite: impl Into<String>,
+        started_at: DateTime<Utc>,
+        targets: &[String],
+        results: Vec<CaseResult>,
+    ) -> Self {
+        let targets = targets
+            .iter()
+            .map(|target| {
+                let results: Vec<_> = results.iter().filter(|r| &r.target == target).collect();
+                let cases = results.len();
+                let passed = results.iter().filter(|r| r.passed).count();
+                let costs: Vec<f64> = results.iter().filter_map(|r| r.cost).collect();
+                TargetSummary {
+                    target: target.clone(),
+                    cases,
+                    passed,
+                    pass_rate: if cases == 0 {
+                        0.0
+                    } else {
+                        passed as f64 / cases as f64
+                    },
+                    avg_latency_ms: results
+                        .iter()
+                        .map(|r| r.duration_ms)
+                        .sum::<u64>()
+                        .checked_div(cases as u64)
+                        .unwrap_or(0),
+                    tokens: results.iter().filter_map(|r| r.tokens).map(u64::from).sum(),
+                    cost: (!costs.is_empty()).then(|| costs.iter().sum()),
+                }
+            })
+            .collect();
+
+        Self {
+            suite: suite.into(),
+            started_at,
+            targets,
+            results,
+        }
+    }
+
+    /// Default directory for saved reports
+    pub fn default_dir() -> Result<PathBuf> {
+        let config_dir = dirs::config_dir().context("Could not determine config directory")?;
+        Ok(config_dir.join("llm-mux").join("evals"))
+    }
+
+    /// Write the report to `<dir>/<suite>-<timestamp>.json`
+    pub fn save(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create evals directory at {}", dir.display()))?;
+        let path = dir.join(format!(
+            "{}-{}.json",
+            self.suite,
+            self.started_at.format("%Y%m%d-%H%M%S")
+        ));
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(&path, json).with_context(|| format!("writing {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Load a saved report
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_str(&contents).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Most recent saved report for a suite
+    pub fn latest(dir: &Path, suite: &str) -> Result<Option<Self>> {
+        if !dir.exists() {
+            return Ok(None);
+        }
+        let prefix = format!("{}-", suite);
+        let mut reports = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let is_suite = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(".json"));
+            if !is_suite {
+                continue;
+            }
+            match Self::load(&path) {
+                Ok(report) if report.suite == suite => reports.push(report),
+                Ok(_) => {}
+                Err(e) => tracing::warn!(error = %e, "Skipping unreadable eval report"),
+            }
+        }
+        Ok(reports.into_iter().max_by_key(|report| report.started_at))
+    }
+
+    /// Result of a case against a target
+    pub fn result(&self, case: &str, target: &str) -> Option<&CaseResult> {
+        self.results
+            .iter()
+            .find(|r| r.case == case && r.target == target)
+    }
+
+    /// Print the case × target matrix followed by per-target totals
+    pub fn render(&self, out: &mut impl Write) {
+        let mut cases: Vec<&str> = Vec::new();
+        for result in &self.results {
+            if !cases.contains(&result.case.as_str()) {
+                cases.push(&result.case);
+            }
+        }
+
+        let case_width = cases
+            .iter()
+            .map(|case| case.len())
+            .chain(["pass rate".len()])
+            .max()
+            .unwrap_or(0);
+        let widths: Vec<usize> = self
+            .targets
+            .iter()
+            .map(|summary| summary.target.len().max(8))
+            .collect();
+
+        let _ = write!(out, "{:<case_width$}", "");
+        for (summary, width) in self.targets.iter().zip(&widths) {
+            let _ = write!(out, "  {:>width$}", summary.target);
+        }
+        let _ = writeln!(out);
+
+        for case in &cases {
+            let _ = write!(out, "{:<case_width$}", case);
+            for (summary, width) in self.targets.iter().zip(&widths) {
+                let cell = match self.result(case, &summary.target) {
+                    Some(result) if result.passed => "pass",
+                    Some(result) if result.error.is_some() => "error",
+                    Some(_) => "FAIL",
+                    None => "-",
+                };
+                let _ = write!(out, "  {:>width$}", cell);
+            }
+            let _ = writeln!(out);
+        }
+
+        let _ = writeln!(out);
+        for label in ["pass rate", "latency", "tokens", "cost"] {
+            let _ = write!(out, "{:<case_width$}", label);
+            for (summary, width) in self.targets.iter().zip(&widths) {
+                let cell = match label {
+                    "pass rate" => format!(
+                        "{:.0}% ({}/{})",
+                        summary.pass_rate * 100.0,
+                        summary.passed,
+                        summary.cases
+                    ),
+                    "latency" => format!("{}ms", summary.avg_latency_ms),
+                    "tokens" => summary.tokens.to_string(),
+                    _ => summary
+                        .cost
+                        .map(|cost| format!("${:.4}", cost))
+                        .unwrap_or_else(|| "-".into()),
+                };
+                let _ = write!(out, "  {:>width$}", cell);
+            }
+            let _ = writeln!(out);
+        }
+
+        for result in self.results.iter().filter(|r| !r.passed) {
+            let _ = writeln!(out, "\n{} × {}:", result.case, result.target);
+            if let Some(ref error) = result.error {
+                let _ = writeln!(out, "  error: {}", error);
+            }
+            for check in result.checks.iter().filter(|c| !c.passed) {
+                let _ = writeln!(
+                    out,
+                    "  {}: {}",
+                    check.assertion,
+                    check.reason.as_deref().unwrap_or("failed")
+                );
+            }
+        }
+    }
+
+    /// Print pass-rate changes and cases that regressed since a previous run
+    pub fn render_comparison(&self, previous: &EvalReport, out: &mut impl Write) {
+        let before: HashMap<&str, &TargetSummary> = previous
+            .targets
+            .iter()
+            .map(|summary| (summary.target.as_str(), summary))
+            .collect();
+
+        let _ = writeln!(
+            out,
+            "\nCompared with {}:",
+            previous.started_at.format("%Y-%m-%d %H:%M")
+        );
+        for summary in &self.targets {
+            match before.get(summary.target.as_str()) {
+                Some(old) => {
+                    let _ = writeln!(
+                        out,
+                        "  {}: {:.0}% -> {:.0}% ({:+.0} pts)",
+                        summary.target,
+                        old.pass_rate * 100.0,
+                        summary.pass_rate * 100.0,
+                        (summary.pass_rate - old.pass_rate) * 100.0
+                    );
+                }
+                None => {
+                    let _ = writeln!(out, "  {}: new target", summary.target);
+                }
+            }
+        }
+
+        for (result, change) in self.changes(previous) {
+            let _ = writeln!(out, "  {} {} × {}", change, result.case, result.target);
+        }
+    }
+
+    /// Cases whose outcome flipped since a previous run
+    pub fn changes<'a>(&'a self, previous: &EvalReport) -> Vec<(&'a CaseResult, &'static str)> {
+        self.results
+            .iter()
+            .filter_map(|result| {
+                let old = previous.result(&result.case, &result.target)?;
+                match (old.passed, result.passed) {
+                    (true, false) => Some((result, "regressed:")),
+                    (false, true) => Some((result, "fixed:")),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn result(case: &str, target: &str, passed: bool) -> CaseResult {
+        CaseResult {
+            case: case.into(),
+            target: target.into(),
+            passed,
+            duration_ms: 100,
+            tokens: Some(10),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_summaries() {
+        let report = EvalReport::new(
+            "demo",
+            Utc::now(),
+            &["a".into(), "b".into()],
+            vec![
+                result("one", "a", true),
+                result("two", "a", false),
+                result("one", "b", true),
+            ],
+        );
+
+        assert_eq!(report.targets[0].passed, 1);
+        assert!((report.targets[0].pass_rate - 0.5).abs() < 1e-9);
+        assert_eq!(report.targets[0].tokens, 20);
+        assert!(report.targets[0].cost.is_none());
+        assert!((report.targets[1].pass_rate - 1.0).abs() < 1e-9);
+
+        let mut out = Vec::new();
+        report.render(&mut out);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("FAIL"));
+        assert!(out.contains("50% (1/2)"));
+    }
+
+    #[test]
+    fn test_save_and_compare() {
+        let dir = TempDir::new().unwrap();
+        let targets = vec!["a".to_string()];
+        let earlier = Utc::now() - chrono::Duration::hours(1);
+
+        let before = EvalReport::new("demo", earlier, &targets, vec![result("one", "a", true)]);
+        before.save(dir.path()).unwrap();
+        let after = EvalReport::new(
+            "demo",
+            Utc::now(),
+            &targets,
+            vec![result("one", "a", false)],
+        );
+        after.save(dir.path()).unwrap();
+
+        let latest = EvalReport::latest(dir.path(), "demo").unwrap().unwrap();
+        assert_eq!(latest.started_at, after.started_at);
+        assert!(EvalReport::latest(dir.path(), "other").unwrap().is_none());
+
+        let changes = after.changes(&before);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].1, "regressed:");
+    }
+}
@@ -0,0 +1,289 @@
+//! Run an eval suite through the role executor
+
+use super::assertion::{Assertion, Check, judge_prompt, parse_verdict};
+use super::report::{CaseResult, EvalReport};
+use super::suite::{EvalCase, EvalSuite, EvalTarget};
+use crate::backend_executor::BackendRequest;
+use crate::config::{LlmuxConfig, RoleExecution};
+use crate::role::{ResolvedRole, RoleExecutor, resolve_role};
+use crate::template::{TemplateContext, TemplateEngine};
+use anyhow::{Result, bail};
+use minijinja::Value;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Runs every case of a suite against every target
+pub struct EvalRunner {
+    config: Arc<LlmuxConfig>,
+    executor: RoleExecutor,
+    engine: TemplateEngine,
+    working_dir: PathBuf,
+    team: Option<String>,
+}
+
+impl EvalRunner {
+    /// Create a runner
+    pub fn new(config: Arc<LlmuxConfig>, executor: RoleExecutor, working_dir: PathBuf) -> Self {
+        Self {
+            config,
+            executor,
+            engine: TemplateEngine::new(),
+            working_dir,
+            team: None,
+        }
+    }
+
+    /// Resolve roles for a team
+    pub fn with_team(mut self, team: Option<String>) -> Self {
+        self.team = team;
+        self
+    }
+
+    /// Run the suite, calling `progress` as each result comes in
+    pub async fn run(
+        &self,
+        suite: &EvalSuite,
+        progress: impl Fn(&CaseResult),
+    ) -> Result<EvalReport> {
+        if suite.targets.is_empty() {
+            bail!("eval suite '{}' has no targets", suite.name);
+        }
+
+        let started_at = chrono::Utc::now();
+        let mut results = Vec::new();
+        for target in &suite.targets {
+            let role = self.target_role(target)?;
+            for case in &suite.cases {
+                let result = self.run_case(suite, target, &role, case).await?;
+                progress(&result);
+                results.push(result);
+            }
+        }
+
+        let targets: Vec<String> = suite.targets.iter().map(EvalTarget::label).collect();
+        Ok(EvalReport::new(&suite.name, started_at, &targets, results))
+    }
+
+    /// The role a target is asked through
+    fn target_role(&self, target: &EvalTarget) -> Result<ResolvedRole> {
+        if let Some(ref backend) = target.backend {
+            if !self.config.backends.contains_key(backend) {
+                bail!("unknown backend '{}'", backend);
+            }
+            return Ok(ResolvedRole {
+                name: format!("backend:{}", backend),
+                backends: vec![backend.clone()],
+                execution: RoleExecution::First,
+                ..Default::default()
+            });
+        }
+        let role = target.role.as_deref().unwrap_or_default();
+        Ok(resolve_role(role, self.team.as_deref(), &self.config)?)
+    }
+
+    async fn run_case(
+        &self,
+        suite: &EvalSuite,
+        target: &EvalTarget,
+        role: &ResolvedRole,
+        case: &EvalCase,
+    ) -> Result<CaseResult> {
+        let mut template_ctx = TemplateContext::new();
+        template_ctx.set_local("input", Value::from(case.input.as_str()));
+        for (name, value) in &case.vars {
+            template_ctx.set_local(name.clone(), Value::from_serialize(value));
+        }
+
+        let prompt = self.engine.render(target.prompt(), &template_ctx)?;
+        let mut request =
+            BackendRequest::new(prompt.clone()).with_working_dir(self.working_dir.clone());
+        if let Some(ref system_prompt) = target.system_prompt {
+            request = request.with_system_prompt(self.engine.render(system_prompt, &template_ctx)?);
+        }
+
+        let mut result = CaseResult {
+            case: case.name.clone(),
+            target: target.label(),
+            ..Default::default()
+        };
+
+        let start = Instant::now();
+        let answer = self.executor.execute(role, &request).await;
+        result.duration_ms = start.elapsed().as_millis() as u64;
+
+        let answer = match answer {
+            Ok(answer) => answer,
+            Err(e) => {
+                result.error = Some(e.to_string());
+                return Ok(result);
+            }
+        };
+
+        result.backend = answer.succeeded.first().cloned();
+        result.tokens = answer.usage.as_ref().and_then(|usage| {
+            usage
+                .total_tokens
+                .or_else(|| Some(usage.prompt_tokens? + usage.completion_tokens.unwrap_or(0)))
+        });
+        result.cost = result
+            .tokens
+            .zip(
+                result
+                    .backend
+                    .as_ref()
+                    .and_then(|backend| self.config.backends.get(backend))
+                    .and_then(|backend| backend.cost_per_mtok),
+            )
+            .map(|(tokens, price)| f64::from(tokens) * price / 1e6);
+
+        let mut outputs: Vec<_> = answer.outputs.iter().collect();
+        outputs.sort();
+        let output = answer.output.clone().unwrap_or_else(|| {
+            outputs
+                .iter()
+                .map(|(backend, output)| format!("=== {} ===\n{}", backend, output))
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        });
+
+        for assertion in suite.asserts.iter().chain(&case.asserts) {
+            let check = match assertion.check(&output) {
+                Some(check) => check,
+                None => self.judge(assertion, &prompt, &output).await,
+            };
+            result.checks.push(check);
+        }
+        result.passed = result.checks.iter().all(|check| check.passed);
+        result.output = Some(output);
+
+        Ok(result)
+    }
+
+    /// Ask a judge role to grade an answer
+    async fn judge(&self, assertion: &Assertion, input: &str, output: &str) -> Check {
+        let description = assertion.describe();
+        let Assertion::Judge { role, criteria } = assertion else {
+            return Check {
+                assertion: description,
+                passed: false,
+                reason: Some("not a judge assertion".into()),
+            };
+        };
+
+        let reply = match resolve_role(role, self.team.as_deref(), &self.config) {
+            Ok(judge) => {
+                let request = BackendRequest::new(judge_prompt(input, output, criteria))
+                    .with_working_dir(self.working_dir.clone());
+                self.executor
+                    .execute(&judge, &request)
+                    .await
+                    .map_err(|e| e.to_string())
+                    .and_then(|result| result.output.ok_or_else(|| "judge gave no answer".into()))
+            }
+            Err(e) => Err(e.to_string()),
+        };
+
+        match reply {
+            Ok(reply) => {
+                let (passed, reason) = parse_verdict(&reply);
+                Check {
+                    assertion: description,
+                    passed,
+                    reason,
+                }
+            }
+            Err(e) => Check {
+                assertion: description,
+                passed: false,
+                reason: Some(format!("judge failed: {}", e)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BackendConfig, RoleConfig};
+
+    fn echo_config() -> LlmuxConfig {
+        let mut config = LlmuxConfig::default();
+        config.backends.insert(
+            "echo".into(),
+            BackendConfig {
+                command: "echo".into(),
+                ..Default::default()
+            },
+        );
+        // Always approves whatever it is asked to grade
+        config.backends.insert(
+            "approver".into(),
+            BackendConfig {
+                command: "sh".into(),
+                args: vec!["-c".into(), "echo PASS; echo looks fine".into()],
+                ..Default::default()
+            },
+        );
+        config.roles.insert(
+            "judge".into(),
+            RoleConfig {
+                backends: vec!["approver".into()],
+                ..Default::default()
+            },
+        );
+        config
+    }
+
+    #[tokio::test]
+    async fn test_run_suite() {
+        let suite: EvalSuite = toml::from_str(
+            r#"
+name = "demo"
+
+[[targets]]
+backend = "echo"
+
+[[targets]]
+name = "shouty"
+backend = "echo"
+prompt = "{{ input | upper }} {{ suffix }}"
+
+[[assert]]
+type = "judge"
+role = "judge"
+criteria = "echoes the input"
+
+[[cases]]
+name = "hello"
+input = "hello"
+vars = { suffix = "!" }
+[[cases.assert]]
+type = "contains"
+value = "hello"
+"#,
+        )
+        .unwrap();
+
+        let config = Arc::new(echo_config());
+        let dir = tempfile::TempDir::new().unwrap();
+        let runner = EvalRunner::new(
+            config.clone(),
+            RoleExecutor::new(config),
+            dir.path().to_path_buf(),
+        );
+
+        let report = runner.run(&suite, |_| {}).await.unwrap();
+        assert_eq!(report.results.len(), 2);
+
+        let plain = report.result("hello", "echo").unwrap();
+        assert!(plain.passed, "{:?}", plain);
+        assert_eq!(plain.checks.len(), 2);
+        assert_eq!(plain.checks[0].reason.as_deref(), Some("looks fine"));
+
+        let shouty = report.result("hello", "shouty").unwrap();
+        assert!(!shouty.passed);
+        assert_eq!(shouty.output.as_deref().map(str::trim), Some("HELLO !"));
+        assert!((report.targets[1].pass_rate).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,268 @@
+//! Eval suite files: targets, cases and assertions
+
+use super::assertion::Assertion;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Prompt used by targets that don't set one
+pub const DEFAULT_PROMPT: &str = "{{ input }}";
+
+/// An eval suite loaded from TOML
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalSuite {
+    /// Suite name (defaults to the file stem)
+    #[serde(default)]
+    pub name: String,
+
+    /// Roles, backends or prompt variants to evaluate
+    #[serde(default)]
+    pub targets: Vec<EvalTarget>,
+
+    /// Assertions applied to every case
+    #[serde(default, rename = "assert")]
+    pub asserts: Vec<Assertion>,
+
+    /// JSONL file of additional cases, relative to the suite file
+    pub dataset: Option<String>,
+
+    /// Inline cases
+    #[serde(default)]
+    pub cases: Vec<EvalCase>,
+}
+
+/// Something to evaluate: a role or backend, with an optional prompt variant
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalTarget {
+    /// Label in the report (defaults to the role or backend)
+    pub name: Option<String>,
+
+    /// Role to ask
+    pub role: Option<String>,
+
+    /// Single backend to ask
+    pub backend: Option<String>,
+
+    /// Prompt template; `{{ input }}` and the case's vars are available
+    pub prompt: Option<String>,
+
+    /// System prompt template
+    pub system_prompt: Option<String>,
+}
+
+impl EvalTarget {
+    /// Label used for this target in reports
+    pub fn label(&self) -> String {
+        self.name
+            .clone()
+            .or_else(|| self.role.clone())
+            .or_else(|| self.backend.clone())
+            .unwrap_or_default()
+    }
+
+    /// Prompt template for this target
+    pub fn prompt(&self) -> &str {
+        self.prompt.as_deref().unwrap_or(DEFAULT_PROMPT)
+    }
+}
+
+/// One input with its expectations
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalCase {
+    /// Case name (defaults to its position)
+    #[serde(default)]
+    pub name: String,
+
+    /// Input text, available to prompts as `{{ input }}`
+    pub input: String,
+
+    /// Extra template variables
+    #[serde(default)]
+    pub vars: HashMap<String, serde_json::Value>,
+
+    /// Assertions for this case, on top of the suite's
+    #[serde(default, rename = "assert")]
+    pub asserts: Vec<Assertion>,
+}
+
+impl EvalSuite {
+    /// Load a suite file, along with its JSONL dataset if it names one
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut suite: EvalSuite =
+            toml::from_str(&contents).with_context(|| format!("parsing {}", path.display()))?;
+
+        if suite.name.is_empty() {
+            suite.name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "eval".into());
+        }
+
+        if let Some(ref dataset) = suite.dataset {
+            let dataset_path = path.parent().unwrap_or(Path::new(".")).join(dataset);
+            suite.cases.extend(load_dataset(&dataset_path)?);
+        }
+
+        for (index, case) in suite.cases.iter_mut().enumerate() {
+            if case.name.is_empty() {
+                case.name = format!("case-{}", index + 1);
+            }
+        }
+
+        suite.validate().map_err(|errors| {
+            anyhow::anyhow!("eval suite validation failed:\n  {}", errors.join("\n  "))
+        })?;
+
+        Ok(suite)
+    }
+
+    /// Check targets and assertions
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        if self.cases.is_empty() {
+            errors.push("suite has no cases".to_string());
+        }
+        for (index, target) in self.targets.iter().enumerate() {
+            if target.role.is_some() == target.backend.is_some() {
+                errors.push(format!(
+                    "target {} needs exactly one of 'role' or 'backend'",
+                    index + 1
+                ));
+            }
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        for target in &self.targets {
+            if !seen.insert(target.label()) {
+                errors.push(format!(
+                    "duplicate target '{}' (give variants a 'name')",
+                    target.label()
+                ));
+            }
+        }
+
+        let asserts = self
+            .asserts
+            .iter()
+            .map(|assertion| ("suite".to_string(), assertion))
+            .chain(self.cases.iter().flat_map(|case| {
+                case.asserts
+                    .iter()
+                    .map(|assertion| (format!("case '{}'", case.name), assertion))
+            }));
+        for (owner, assertion) in asserts {
+            if let Err(e) = assertion.validate() {
+                errors.push(format!("{}: {}", owner, e));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Read cases from a JSONL file, one JSON object per line
+pub fn load_dataset(path: &Path) -> Result<Vec<EvalCase>> {
+    let contents =
+        std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("parsing {} line {}", path.display(), number + 1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_suite_with_dataset() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("summaries.toml"),
+            r#"
+dataset = "cases.jsonl"
+
+[[targets]]
+role = "analyzer"
+
+[[targets]]
+name = "terse"
+role = "analyzer"
+prompt = "Summarize in one line: {{ input }}"
+
+[[assert]]
+type = "regex"
+pattern = "\\w+"
+
+[[cases]]
+input = "The build fails on Windows."
+[[cases.assert]]
+type = "contains"
+value = "Windows"
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("cases.jsonl"),
+            "{\"name\": \"json\", \"input\": \"{}\", \"assert\": [{\"type\": \"equals\", \"field\": \"ok\", \"value\": true}]}\n\n",
+        )
+        .unwrap();
+
+        let suite = EvalSuite::load(&dir.path().join("summaries.toml")).unwrap();
+        assert_eq!(suite.name, "summaries");
+        assert_eq!(suite.cases.len(), 2);
+        assert_eq!(suite.cases[0].name, "case-1");
+        assert_eq!(suite.cases[1].name, "json");
+        assert_eq!(suite.targets[0].prompt(), DEFAULT_PROMPT);
+        assert_eq!(suite.targets[1].label(), "terse");
+    }
+
+    #[test]
+    fn test_validate_targets() {
+        let suite = EvalSuite {
+            targets: vec![
+                EvalTarget {
+                    role: Some("analyzer".into()),
+                    backend: Some("claude".into()),
+                    ..Default::default()
+                },
+                EvalTarget {
+                    role: Some("analyzer".into()),
+                    ..Default::default()
+                },
+                EvalTarget {
+                    role: Some("analyzer".into()),
+                    ..Default::default()
+                },
+            ],
+            cases: vec![EvalCase {
+                name: "one".into(),
+                input: "x".into(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let errors = suite.validate().unwrap_err();
+        assert!(errors.iter().any(|e| e.contains("exactly one")));
+        assert!(errors.iter().any(|e| e.contains("duplicate target")));
+    }
+}
@@ -3,6 +3,7 @@ mod backend_executor;
 mod cli;
 mod config;
 mod discovery;
+mod eval;
 mod logging;
 mod memory;
 mod process;
@@ -11,7 +12,7 @@ mod template;
 mod workflow;
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -57,13 +58,134 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Run a workflow
+    #[command(disable_help_flag = true)]
     Run {
         /// Workflow name
-        workflow: String,
+        #[arg(required_unless_present = "help")]
+        workflow: Option<String>,
 
-        /// Workflow arguments (key=value or positional)
+        /// Workflow arguments (key=value or positional; @file or - reads a value from a file or stdin)
         #[arg(trailing_var_arg = true)]
         args: Vec<String>,
+
+        /// Bypass the response cache
+        #[arg(long)]
+        no_cache: bool,
+
+        /// Print help, including the workflow's arguments
+        #[arg(long, short)]
+        help: bool,
+    },
+
+    /// Ask a one-off question (extra input can be piped on stdin)
+    Ask {
+        /// The question
+        prompt: String,
+
+        /// `-` to wait for stdin however long it takes
+        #[arg(value_name = "-", value_parser = ["-"], hide_possible_values = true)]
+        input: Option<String>,
+
+        /// Role to ask
+        #[arg(long, short, conflicts_with = "backend")]
+        role: Option<String>,
+
+        /// Ask a single backend instead of a role
+        #[arg(long, short)]
+        backend: Option<String>,
+
+        /// Ask every backend of the role and print each answer
+        #[arg(long)]
+        parallel: bool,
+
+        /// System prompt
+        #[arg(long)]
+        system: Option<String>,
+
+        /// JSON output schema file to validate the answer against
+        #[arg(long)]
+        schema: Option<PathBuf>,
+    },
+
+    /// Run a prompt on several backends and compare the answers
+    Compare {
+        /// The prompt
+        prompt: String,
+
+        /// `-` to wait for stdin however long it takes
+        #[arg(value_name = "-", value_parser = ["-"], hide_possible_values = true)]
+        input: Option<String>,
+
+        /// Backends to compare (comma-separated)
+        #[arg(long, short, value_delimiter = ',')]
+        backends: Vec<String>,
+
+        /// Compare the backends of a role
+        #[arg(long, short)]
+        role: Option<String>,
+
+        /// Diff the answers of two backends line by line
+        #[arg(long, value_delimiter = ',', num_args = 2, value_names = ["A", "B"])]
+        diff: Vec<String>,
+
+        /// Console width for the side-by-side view
+        #[arg(long)]
+        width: Option<usize>,
+    },
+
+    /// Run an eval suite against roles, backends or prompt variants
+    Eval {
+        /// Suite file, or name under .llm-mux/evals/
+        suite: String,
+
+        /// Evaluate these roles instead of the suite's targets
+        #[arg(long, short)]
+        role: Vec<String>,
+
+        /// Evaluate these backends instead of the suite's targets
+        #[arg(long, short)]
+        backend: Vec<String>,
+
+        /// Compare against this report instead of the last saved run
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+
+        /// Don't save the report
+        #[arg(long)]
+        no_save: bool,
+
+        /// Exit with an error if any target's pass rate (percent) is below this
+        #[arg(long)]
+        fail_under: Option<f64>,
+    },
+
+    /// Run workflow tests (*.test.toml next to workflows)
+    Test {
+        /// Test files or workflow names (defaults to all tests in .llm-mux/workflows/)
+        targets: Vec<String>,
+
+        /// Only run tests whose name contains this
+        #[arg(long)]
+        filter: Option<String>,
+    },
+
+    /// Chat interactively with a role
+    Chat {
+        /// Role to chat with
+        #[arg(long, short)]
+        role: Option<String>,
+
+        /// Start on this backend instead of the role's first available one
+        #[arg(long)]
+        backend: Option<String>,
+
+        /// Resume a saved session (the most recent if no id is given)
+        #[arg(long, num_args = 0..=1, default_missing_value = "latest")]
+        resume: Option<String>,
+
+        /// List saved sessions
+        #[arg(long)]
+        list: bool,
     },
 
     /// Validate a workflow without running
@@ -76,7 +198,11 @@ enum Commands {
     Doctor,
 
     /// List configured backends
-    Backends,
+    Backends {
+        /// Show recorded success rate, latency and cost per backend
+        #[arg(long)]
+        stats: bool,
+    },
 
     /// List configured teams
     Teams,
@@ -93,6 +219,12 @@ enum Commands {
     /// Gather and seed project context
     Context,
 
+    /// Inspect or clear the response cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
     /// Interactive configuration setup
     Init {
         /// Initialize global config (~/.config/llm-mux/config.toml)
@@ -113,6 +245,19 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum CacheAction {
+    /// Show cache statistics
+    Stats,
+
+    /// Remove cached responses
+    Clear {
+        /// Only remove expired entries
+        #[arg(long)]
+        expired: bool,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -168,15 +313,144 @@ async fn main() -> Result<()> {
 
     // Execute command
     let exit_code = match cli.command {
-        Commands::Run { workflow, args } => {
-            match commands::run_workflow(
-                &workflow,
-                args,
+        Commands::Run {
+            workflow,
+            args,
+            no_cache,
+            help,
+        } => {
+            let result = match workflow {
+                // `llmux run --help` without a workflow
+                None => {
+                    let mut command = Cli::command();
+                    if let Some(run) = command.find_subcommand_mut("run") {
+                        let _ = run.print_help();
+                    }
+                    Ok(0)
+                }
+                // Only a flag before the first workflow argument asks for help;
+                // later `-h`/`--help` values are passed to the workflow
+                Some(workflow) if help => commands::workflow_help(&workflow, &working_dir),
+                Some(workflow) => {
+                    let options = commands::RunOptions {
+                        team_override: cli.team.as_deref(),
+                        output_file: cli.output_file.as_deref(),
+                        no_cache,
+                    };
+                    commands::run_workflow(
+                        &workflow,
+                        args,
+                        &working_dir,
+                        config,
+                        &*handler,
+                        options,
+                    )
+                    .await
+                }
+            };
+            match result {
+                Ok(code) => code,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    1
+                }
+            }
+        }
+
+        Commands::Ask {
+            prompt,
+            input,
+            role,
+            backend,
+            parallel,
+            system,
+            schema,
+        } => {
+            let options = commands::AskOptions {
+                prompt: &prompt,
+                role: role.as_deref(),
+                backend: backend.as_deref(),
+                parallel,
+                system: system.as_deref(),
+                schema: schema.as_deref(),
+                context_files: cli.context.as_deref().unwrap_or_default(),
+                stdin: commands::read_stdin(input.is_some()),
+                team_override: cli.team.as_deref(),
+                json: output_mode == OutputMode::Json,
+            };
+            match commands::ask(config, &working_dir, &*handler, options).await {
+                Ok(code) => code,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    1
+                }
+            }
+        }
+
+        Commands::Compare {
+            prompt,
+            input,
+            backends,
+            role,
+            diff,
+            width,
+        } => {
+            let options = cli::compare::CompareOptions {
+                prompt: &prompt,
+                backends: &backends,
+                role: role.as_deref(),
+                diff: match diff.as_slice() {
+                    [a, b] => Some((a.as_str(), b.as_str())),
+                    _ => None,
+                },
+                width,
+                context_files: cli.context.as_deref().unwrap_or_default(),
+                stdin: commands::read_stdin(input.is_some()),
+                team_override: cli.team.as_deref(),
+                json: output_mode == OutputMode::Json,
+            };
+            match cli::compare::compare(config, &working_dir, options).await {
+                Ok(code) => code,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    1
+                }
+            }
+        }
+
+        Commands::Eval {
+            suite,
+            role,
+            backend,
+            baseline,
+            no_save,
+            fail_under,
+        } => {
+            let options = commands::EvalOptions {
+                roles: &role,
+                backends: &backend,
+                baseline: baseline.as_deref(),
+                no_save,
+                fail_under,
+                team_override: cli.team.as_deref(),
+                json: output_mode == OutputMode::Json,
+            };
+            match commands::eval(&suite, config, &working_dir, options).await {
+                Ok(code) => code,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    1
+                }
+            }
+        }
+
+        Commands::Test { targets, filter } => {
+            match commands::test_workflows(
+                &targets,
+                filter.as_deref(),
                 &working_dir,
-                cli.team.as_deref(),
                 config,
-                &*handler,
-                cli.output_file.as_deref(),
+                output_mode == OutputMode::Json,
             )
             .await
             {
@@ -188,8 +462,34 @@ async fn main() -> Result<()> {
             }
         }
 
+        Commands::Chat {
+            role,
+            backend,
+            resume,
+            list,
+        } => {
+            let result = if list {
+                cli::chat::list_sessions(&mut std::io::stdout())
+            } else {
+                let options = cli::chat::ChatOptions {
+                    role: role.as_deref(),
+                    team_override: cli.team.as_deref(),
+                    backend: backend.as_deref(),
+                    resume: resume.as_deref(),
+                };
+                cli::chat::chat(config, &working_dir, options).await
+            };
+            match result {
+                Ok(code) => code,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    1
+                }
+            }
+        }
+
         Commands::Validate { workflow } => {
-            match commands::validate_workflow(&workflow, Some(&working_dir), &*handler) {
+            match commands::validate_workflow(&workflow, Some(&working_dir), &config, &*handler) {
                 Ok(code) => code,
                 Err(e) => {
                     eprintln!("Error: {}", e);
@@ -200,9 +500,13 @@ async fn main() -> Result<()> {
 
         Commands::Doctor => commands::doctor(&config, &working_dir, &*handler).await,
 
-        Commands::Backends => {
-            commands::list_backends(&config, &*handler);
-            0
+        Commands::Backends { stats } => {
+            if stats {
+                commands::backend_stats(&config, &*handler)
+            } else {
+                commands::list_backends(&config, &*handler);
+                0
+            }
         }
 
         Commands::Teams => {
@@ -234,6 +538,20 @@ async fn main() -> Result<()> {
             0
         }
 
+        Commands::Cache { action } => {
+            let result = match action {
+                CacheAction::Stats => commands::cache_stats(&*handler),
+                CacheAction::Clear { expired } => commands::cache_clear(expired, &*handler),
+            };
+            match result {
+                Ok(code) => code,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    1
+                }
+            }
+        }
+
         Commands::Init {
             global,
             project,
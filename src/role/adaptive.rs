@@ -0,0 +1,111 @@
+//! Adaptive backend ordering from recorded call statistics
+
+use crate::backend_executor::{BackendStats, BreakerState, CircuitBreaker};
+use crate::config::{LlmuxConfig, Optimize};
+use std::collections::HashMap;
+
+use super::role_resolver::ResolvedRole;
+
+/// Calls needed before a backend's success rate is trusted
+const MIN_CALLS: u64 = 5;
+
+/// Success rate below which a backend counts as unhealthy
+const MIN_SUCCESS_RATE: f64 = 0.5;
+
+/// Order a role's backends for adaptive execution
+///
+/// Healthy backends with statistics come first, cheapest or fastest first.
+/// Backends without enough data follow in config order, and unhealthy ones
+/// (open circuit or mostly failing) go last.
+pub fn adaptive_order(
+    role: &ResolvedRole,
+    config: &LlmuxConfig,
+    stats: &HashMap<String, BackendStats>,
+) -> Vec<String> {
+    let rank = |name: &String| -> (u8, f64) {
+        let backend = config.backends.get(name);
+
+        let circuit_open = backend
+            .and_then(|b| CircuitBreaker::shared(name, b))
+            .is_some_and(|breaker| matches!(breaker.state(), BreakerState::Open { .. }));
+        let stats = stats.get(name);
+        let failing = stats.is_some_and(|s| {
+            s.calls >= MIN_CALLS && s.success_rate().is_some_and(|r| r < MIN_SUCCESS_RATE)
+        });
+        if circuit_open || failing {
+            return (2, 0.0);
+        }
+
+        let metric = match role.optimize {
+            Optimize::Latency => stats.and_then(|s| s.p50_ms).map(|ms| ms as f64),
+            Optimize::Cost => stats
+                .and_then(|s| s.cost_per_mtok())
+                .or_else(|| backend.and_then(|b| b.cost_per_mtok)),
+        };
+        match metric {
+            Some(metric) => (0, metric),
+            None => (1, 0.0),
+        }
+    };
+
+    let mut ranked: Vec<_> = role.backends.iter().map(|b| (rank(b), b)).collect();
+    // Stable sort keeps config order among equals
+    ranked.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    ranked.into_iter().map(|(_, b)| b.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BackendConfig, BackendOrder};
+
+    fn stats(backend: &str, calls: u64, successes: u64, p50_ms: u64) -> BackendStats {
+        BackendStats {
+            backend: backend.into(),
+            calls,
+            successes,
+            p50_ms: Some(p50_ms),
+            ..Default::default()
+        }
+    }
+
+    fn role(optimize: Optimize) -> ResolvedRole {
+        ResolvedRole {
+            name: "quick".into(),
+            backends: vec!["slow".into(), "new".into(), "flaky".into(), "fast".into()],
+            order: BackendOrder::Adaptive,
+            optimize,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_orders_by_latency() {
+        let config = LlmuxConfig::default();
+        let stats = HashMap::from([
+            ("slow".to_string(), stats("slow", 20, 20, 9000)),
+            ("fast".to_string(), stats("fast", 20, 19, 800)),
+            ("flaky".to_string(), stats("flaky", 10, 2, 100)),
+        ]);
+
+        let order = adaptive_order(&role(Optimize::Latency), &config, &stats);
+        assert_eq!(order, vec!["fast", "slow", "new", "flaky"]);
+    }
+
+    #[test]
+    fn test_orders_by_configured_cost() {
+        let mut config = LlmuxConfig::default();
+        for (name, price) in [("slow", 0.5), ("fast", 15.0)] {
+            config.backends.insert(
+                name.into(),
+                BackendConfig {
+                    cost_per_mtok: Some(price),
+                    ..Default::default()
+                },
+            );
+        }
+
+        let order = adaptive_order(&role(Optimize::Cost), &config, &HashMap::new());
+        assert_eq!(order, vec!["slow", "fast", "new", "flaky"]);
+    }
+}
@@ -23,13 +23,20 @@
 //! let result = executor.execute(&resolved, &request).await?;
 //! ```
 
+mod adaptive;
 mod role_executor;
 mod role_resolver;
 mod team_detector;
-
-pub use role_executor::{ExecutionError, RoleExecutor};
-pub use role_resolver::{RoleError, resolve_role};
+mod vote;
+
+#[allow(unused_imports)]
+pub use role_executor::{
+    BackendFactory, ExecutionError, ParallelProgress, ProgressCallback, RoleExecutor, RoleResult,
+};
+#[allow(unused_imports)]
+pub use role_resolver::{ResolvedRole, RoleError, resolve_role, resolve_role_for};
 pub use team_detector::detect_team;
+pub use vote::VoteKey;
 
 #[cfg(test)]
 mod tests {
@@ -65,6 +72,7 @@ mod tests {
                 backends: vec!["claude".into(), "codex".into()],
                 execution: RoleExecution::First,
                 min_success: 1,
+                ..Default::default()
             },
         );
 
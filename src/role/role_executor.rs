@@ -2,14 +2,22 @@
 
 //! Execute roles across backends with different execution modes
 
-use crate::backend_executor::{BackendExecutor, BackendRequest, create_executor_with_retry};
-use crate::config::{LlmuxConfig, RoleExecution, StepResult};
+use crate::backend_executor::{
+    BackendError, BackendExecutor, BackendRequest, BackendResponse, CircuitBreaker,
+    CircuitBreakerExecutor, ResponseCache, StatsExecutor, StatsStore, TokenUsage,
+    create_executor_with_retry, with_cache,
+};
+use crate::config::{
+    BackendConfig, BackendOrder, ErrorClass, LlmuxConfig, QuorumMode, RoleExecution, StepResult,
+};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use thiserror::Error;
 
+use super::adaptive::adaptive_order;
 use super::role_resolver::{ResolvedRole, RoleError};
+use super::vote::{VoteKey, VoteOutcome, normalize_answer, tally};
 
 /// Errors during role execution
 #[derive(Debug, Error)]
@@ -30,6 +38,13 @@ pub enum ExecutionError {
 
     #[error("backend '{backend}' error: {message}")]
     BackendError { backend: String, message: String },
+
+    #[error("backend '{backend}' failed with {class} error, not falling back")]
+    FallbackStopped {
+        backend: String,
+        class: ErrorClass,
+        errors: HashMap<String, String>,
+    },
 }
 
 /// Result of executing a role
@@ -52,9 +67,44 @@ pub struct RoleResult {
 
     /// Execution mode used
     pub execution_mode: RoleExecution,
+
+    /// Backends whose response came from the response cache
+    pub cache_hits: Vec<String>,
+
+    /// Vote tally (for Vote mode)
+    pub vote: Option<VoteOutcome>,
+
+    /// Token usage reported by the answering backend (single-backend modes)
+    pub usage: Option<TokenUsage>,
 }
 
 impl RoleResult {
+    /// Result of a single backend answering
+    fn single(
+        backend: String,
+        response: BackendResponse,
+        failed: HashMap<String, String>,
+        duration: Duration,
+        execution_mode: RoleExecution,
+    ) -> Self {
+        let cache_hits = if response.cached {
+            vec![backend.clone()]
+        } else {
+            Vec::new()
+        };
+        Self {
+            output: Some(response.text),
+            outputs: HashMap::new(),
+            succeeded: vec![backend],
+            failed,
+            duration,
+            execution_mode,
+            cache_hits,
+            vote: None,
+            usage: response.usage,
+        }
+    }
+
     /// Convert to a StepResult for workflow engine
     pub fn to_step_result(&self) -> StepResult {
         StepResult {
@@ -70,21 +120,121 @@ impl RoleResult {
                 None
             },
             duration_ms: self.duration.as_millis() as u64,
-            backend: self.succeeded.first().cloned(),
+            backend: self
+                .vote
+                .as_ref()
+                .and_then(|v| v.winners.first())
+                .or(self.succeeded.first())
+                .cloned(),
             backends: self.succeeded.clone(),
+            cache_hits: self.cache_hits.clone(),
+            vote: self.vote.as_ref().map(|v| v.answer.clone()),
+            agreement: self.vote.as_ref().map(|v| v.agreement),
+            dissenters: self
+                .vote
+                .as_ref()
+                .map(|v| v.dissenters.clone())
+                .unwrap_or_default(),
+            ..Default::default()
         }
     }
 }
 
+/// Progress of a parallel role, reported as each backend finishes
+#[derive(Debug, Clone)]
+pub struct ParallelProgress {
+    /// Role being executed
+    pub role: String,
+
+    /// Backend that just finished
+    pub backend: String,
+
+    /// Whether that backend succeeded
+    pub success: bool,
+
+    /// Number of backends finished so far
+    pub completed: usize,
+
+    /// All backends started for the role
+    pub backends: Vec<String>,
+}
+
+/// Callback receiving parallel progress
+pub type ProgressCallback = Arc<dyn Fn(ParallelProgress) + Send + Sync>;
+
+/// Builds the executor for a backend name in place of its configured one
+pub type BackendFactory = Arc<dyn Fn(&str) -> Box<dyn BackendExecutor> + Send + Sync>;
+
 /// Execute roles across backends
+#[derive(Clone)]
 pub struct RoleExecutor {
     config: Arc<LlmuxConfig>,
+    cache: Option<Arc<ResponseCache>>,
+    stats: Option<Arc<StatsStore>>,
+    progress: Option<ProgressCallback>,
+    backend_factory: Option<BackendFactory>,
 }
 
 impl RoleExecutor {
     /// Create a new role executor
     pub fn new(config: Arc<LlmuxConfig>) -> Self {
-        Self { config }
+        Self {
+            config,
+            cache: None,
+            stats: None,
+            progress: None,
+            backend_factory: None,
+        }
+    }
+
+    /// Record call statistics, and use them for adaptive ordering
+    pub fn with_stats(mut self, stats: Arc<StatsStore>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Report progress as each backend of a parallel role finishes
+    pub fn with_progress(mut self, progress: ProgressCallback) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Serve cacheable requests from a response cache
+    pub fn with_cache(mut self, cache: Arc<ResponseCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Build backends with `factory` instead of from their config
+    ///
+    /// Workflow tests use this to answer with mocks. Factory-built backends
+    /// skip retry, statistics, the circuit breaker and the cache.
+    pub fn with_backend_factory(mut self, factory: BackendFactory) -> Self {
+        self.backend_factory = Some(factory);
+        self
+    }
+
+    /// Create the executor for a backend, wrapped with retry, statistics,
+    /// circuit breaker and caching
+    ///
+    /// Statistics sit inside the breaker, so calls it rejects aren't recorded
+    /// as failures of a backend they never reached.
+    fn create_executor(&self, name: &str, config: &BackendConfig) -> Box<dyn BackendExecutor> {
+        if let Some(factory) = &self.backend_factory {
+            return factory(name);
+        }
+        let mut executor: Box<dyn BackendExecutor> =
+            Box::new(create_executor_with_retry(name, config));
+        if let Some(stats) = &self.stats {
+            executor = Box::new(StatsExecutor::new(executor, stats.clone(), config));
+        }
+        if let Some(breaker) = CircuitBreaker::shared(name, config) {
+            executor = Box::new(CircuitBreakerExecutor::new(executor, breaker));
+        }
+        match &self.cache {
+            Some(cache) => Box::new(with_cache(executor, cache.clone(), config)),
+            None => Box::new(executor),
+        }
     }
 
     /// Execute a resolved role with a prompt
@@ -93,14 +243,56 @@ impl RoleExecutor {
         role: &ResolvedRole,
         request: &BackendRequest,
     ) -> Result<RoleResult, ExecutionError> {
+        let reordered;
+        let role = match self.adaptive_backends(role) {
+            Some(backends) => {
+                reordered = ResolvedRole {
+                    backends,
+                    ..role.clone()
+                };
+                &reordered
+            }
+            None => role,
+        };
+
         match role.execution {
             RoleExecution::First => self.execute_first(role, request).await,
             RoleExecution::Parallel => self.execute_parallel(role, request).await,
             RoleExecution::Fallback => self.execute_fallback(role, request).await,
+            RoleExecution::Race => self.execute_race(role, request).await,
+            RoleExecution::Vote => self.execute_vote(role, request, &VoteKey::default()).await,
         }
     }
 
-    /// Execute with First mode: use first available backend
+    /// Backend order for a role with `order = "adaptive"`, if statistics are available
+    fn adaptive_backends(&self, role: &ResolvedRole) -> Option<Vec<String>> {
+        if role.order != BackendOrder::Adaptive {
+            return None;
+        }
+        let store = self.stats.as_ref()?;
+
+        let mut stats = HashMap::new();
+        for backend in &role.backends {
+            match store.stats(backend) {
+                Ok(s) => {
+                    stats.insert(backend.clone(), s);
+                }
+                Err(e) => {
+                    tracing::warn!(backend = %backend, error = %e, "Failed to read backend stats")
+                }
+            }
+        }
+
+        let order = adaptive_order(role, &self.config, &stats);
+        tracing::debug!(role = %role.name, order = ?order, "Adaptive backend order");
+        Some(order)
+    }
+
+    /// Execute with First mode: commit to the first available backend
+    ///
+    /// Backends that are disabled or report unavailable (CLI binary missing,
+    /// API key unset, server unreachable) are skipped; the first available
+    /// one is used and its result returned, success or not.
     async fn execute_first(
         &self,
         role: &ResolvedRole,
@@ -110,47 +302,191 @@ impl RoleExecutor {
         let mut failed = HashMap::new();
 
         for backend_name in &role.backends {
-            if let Some(backend_config) = self.config.backends.get(backend_name) {
-                if !backend_config.enabled {
-                    continue;
+            let Some(backend_config) = self.config.backends.get(backend_name) else {
+                failed.insert(backend_name.clone(), "skipped: not configured".into());
+                continue;
+            };
+            if !backend_config.enabled {
+                failed.insert(backend_name.clone(), "skipped: disabled".into());
+                continue;
+            }
+
+            let executor = self.create_executor(backend_name, backend_config);
+            if !executor.is_available().await {
+                failed.insert(backend_name.clone(), "skipped: unavailable".into());
+                continue;
+            }
+
+            match executor.execute(request).await {
+                Ok(response) => {
+                    return Ok(RoleResult::single(
+                        backend_name.clone(),
+                        response,
+                        failed,
+                        start.elapsed(),
+                        RoleExecution::First,
+                    ));
+                }
+                Err(e) => {
+                    failed.insert(backend_name.clone(), format!("{}: {}", e.class(), e));
+                    break;
+                }
+            }
+        }
+
+        Err(ExecutionError::AllFailed { errors: failed })
+    }
+
+    /// Execute with Fallback mode: try each backend until success
+    ///
+    /// Only errors whose class is listed in the role's `fallback_on` move on
+    /// to the next backend; any other error stops the role.
+    async fn execute_fallback(
+        &self,
+        role: &ResolvedRole,
+        request: &BackendRequest,
+    ) -> Result<RoleResult, ExecutionError> {
+        let start = Instant::now();
+        let mut failed = HashMap::new();
+
+        for backend_name in &role.backends {
+            let Some(backend_config) = self.config.backends.get(backend_name) else {
+                failed.insert(backend_name.clone(), "skipped: not configured".into());
+                continue;
+            };
+            if !backend_config.enabled {
+                failed.insert(backend_name.clone(), "skipped: disabled".into());
+                continue;
+            }
+
+            let executor = self.create_executor(backend_name, backend_config);
+
+            match executor.execute(request).await {
+                Ok(response) => {
+                    return Ok(RoleResult::single(
+                        backend_name.clone(),
+                        response,
+                        failed,
+                        start.elapsed(),
+                        RoleExecution::Fallback,
+                    ));
                 }
+                Err(e) => {
+                    let class = e.class();
+                    failed.insert(backend_name.clone(), format!("{}: {}", class, e));
 
-                let executor = create_executor_with_retry(backend_name, backend_config);
-
-                match executor.execute(request).await {
-                    Ok(response) => {
-                        return Ok(RoleResult {
-                            output: Some(response.text),
-                            outputs: HashMap::new(),
-                            succeeded: vec![backend_name.clone()],
-                            failed,
-                            duration: start.elapsed(),
-                            execution_mode: RoleExecution::First,
+                    if !role.fallback_on.contains(&class) {
+                        return Err(ExecutionError::FallbackStopped {
+                            backend: backend_name.clone(),
+                            class,
+                            errors: failed,
                         });
                     }
-                    Err(e) => {
-                        failed.insert(backend_name.clone(), e.to_string());
-                    }
+
+                    tracing::info!(
+                        role = %role.name,
+                        backend = %backend_name,
+                        class = %class,
+                        "Falling back to next backend"
+                    );
                 }
             }
         }
 
-        eprintln!("[DEBUG role_executor] AllFailed errors: {:?}", failed);
         Err(ExecutionError::AllFailed { errors: failed })
     }
 
-    /// Execute with Fallback mode: try each backend until success
-    async fn execute_fallback(
+    /// Execute with Race mode: first successful backend wins, the rest are cancelled
+    ///
+    /// Without `hedge_after_ms` all racers start at once. With it, the next
+    /// racer is only started when no backend has answered in time (or the
+    /// running ones have failed).
+    async fn execute_race(
         &self,
         role: &ResolvedRole,
         request: &BackendRequest,
     ) -> Result<RoleResult, ExecutionError> {
-        // Fallback is the same as First, but semantically different
-        // (First = take first available, Fallback = try until success)
-        self.execute_first(role, request).await.map(|mut r| {
-            r.execution_mode = RoleExecution::Fallback;
-            r
-        })
+        let start = Instant::now();
+        let width = role.race_width.map_or(usize::MAX, |n| n.max(1) as usize);
+        let hedge = role.hedge_after_ms.map(Duration::from_millis);
+
+        let mut pending = role
+            .backends
+            .iter()
+            .filter_map(|name| {
+                let config = self.config.backends.get(name)?;
+                config
+                    .enabled
+                    .then(|| (name.clone(), self.create_executor(name, config)))
+            })
+            .take(width);
+
+        let mut racers = tokio::task::JoinSet::new();
+        let mut launch = |racers: &mut tokio::task::JoinSet<_>| {
+            let Some((name, executor)) = pending.next() else {
+                return false;
+            };
+            let request = request.clone();
+            racers.spawn(async move {
+                let result = executor.execute(&request).await;
+                (name, result)
+            });
+            true
+        };
+
+        if hedge.is_some() {
+            launch(&mut racers);
+        } else {
+            while launch(&mut racers) {}
+        }
+
+        let mut failed = HashMap::new();
+
+        loop {
+            let next = match hedge {
+                Some(delay) => tokio::time::timeout(delay, racers.join_next()).await,
+                None => Ok(racers.join_next().await),
+            };
+
+            match next {
+                // Nobody answered in time: hedge with the next backend
+                Err(_) => {
+                    launch(&mut racers);
+                }
+                Ok(Some(Ok((name, Ok(response))))) => {
+                    // Dropping the losers' tasks kills their child processes
+                    racers.abort_all();
+                    return Ok(RoleResult::single(
+                        name,
+                        response,
+                        failed,
+                        start.elapsed(),
+                        RoleExecution::Race,
+                    ));
+                }
+                Ok(Some(Ok((name, Err(e))))) => {
+                    failed.insert(name, format!("{}: {}", e.class(), e));
+                    // A racer failed: start the next one right away
+                    if hedge.is_some() {
+                        launch(&mut racers);
+                    }
+                }
+                Ok(Some(Err(e))) => {
+                    // Task panicked
+                    failed.insert("unknown".into(), e.to_string());
+                    if hedge.is_some() {
+                        launch(&mut racers);
+                    }
+                }
+                Ok(None) => {
+                    if !launch(&mut racers) {
+                        break;
+                    }
+                }
+            }
+        }
+
+        Err(ExecutionError::AllFailed { errors: failed })
     }
 
     /// Execute with Parallel mode: run all backends concurrently
@@ -158,6 +494,71 @@ impl RoleExecutor {
         &self,
         role: &ResolvedRole,
         request: &BackendRequest,
+    ) -> Result<RoleResult, ExecutionError> {
+        let mut result = self.run_parallel(role, request).await?;
+
+        // Combine outputs for the main output field
+        if !result.outputs.is_empty() {
+            result.output = Some(
+                result
+                    .outputs
+                    .iter()
+                    .map(|(k, v)| format!("=== {} ===\n{}", k, v))
+                    .collect::<Vec<_>>()
+                    .join("\n\n"),
+            );
+        }
+
+        Ok(result)
+    }
+
+    /// Execute with Vote mode: run all backends concurrently and pick the majority answer
+    ///
+    /// Each output is normalized with `key` before counting. The main output
+    /// is the raw answer of the first winning backend; all raw answers stay
+    /// in `outputs`.
+    pub async fn execute_vote(
+        &self,
+        role: &ResolvedRole,
+        request: &BackendRequest,
+        key: &VoteKey,
+    ) -> Result<RoleResult, ExecutionError> {
+        let mut result = self.run_parallel(role, request).await?;
+        result.execution_mode = RoleExecution::Vote;
+
+        let answers: Vec<(String, String)> = result
+            .succeeded
+            .iter()
+            .map(|name| (name.clone(), normalize_answer(&result.outputs[name], key)))
+            .collect();
+
+        if let Some(outcome) = tally(&answers, &role.weights, role.tie_break.as_deref()) {
+            tracing::debug!(
+                role = %role.name,
+                answer = %outcome.answer,
+                agreement = outcome.agreement,
+                "Vote decided"
+            );
+            result.output = outcome
+                .winners
+                .first()
+                .map(|name| result.outputs[name].clone());
+            result.vote = Some(outcome);
+        }
+
+        Ok(result)
+    }
+
+    /// Run every enabled backend concurrently and collect their outputs
+    ///
+    /// Fails when fewer than `min_success` backends succeed. With a quorum
+    /// mode other than `wait`, returns as soon as `min_success` backends have
+    /// succeeded, and fails as soon as that has become impossible. The
+    /// returned result has no combined `output`; callers decide how to merge.
+    async fn run_parallel(
+        &self,
+        role: &ResolvedRole,
+        request: &BackendRequest,
     ) -> Result<RoleResult, ExecutionError> {
         let start = Instant::now();
 
@@ -165,8 +566,8 @@ impl RoleExecutor {
         let max_concurrent = self.config.defaults.max_concurrent.unwrap_or(u32::MAX);
         let semaphore = Arc::new(tokio::sync::Semaphore::new(max_concurrent as usize));
 
-        // Create futures for all backends
-        let mut handles = Vec::new();
+        let mut tasks = tokio::task::JoinSet::new();
+        let mut started = Vec::new();
 
         for backend_name in &role.backends {
             if let Some(backend_config) = self.config.backends.get(backend_name) {
@@ -174,40 +575,87 @@ impl RoleExecutor {
                     continue;
                 }
 
-                let executor = create_executor_with_retry(backend_name, backend_config);
+                let executor = self.create_executor(backend_name, backend_config);
                 let request = request.clone();
                 let name = backend_name.clone();
                 let sem = semaphore.clone();
 
-                handles.push(tokio::spawn(async move {
+                tasks.spawn(async move {
                     let _permit = sem.acquire().await.expect("semaphore closed");
                     let result = executor.execute(&request).await;
                     (name, result)
-                }));
+                });
+                started.push(backend_name.clone());
             }
         }
 
-        // Wait for all to complete
         let mut outputs = HashMap::new();
         let mut succeeded = Vec::new();
         let mut failed = HashMap::new();
+        let mut cache_hits = Vec::new();
+        let mut completed = 0;
+
+        while let Some(joined) = tasks.join_next().await {
+            completed += 1;
 
-        for handle in handles {
-            match handle.await {
+            let (name, success) = match joined {
                 Ok((name, Ok(response))) => {
+                    if response.cached {
+                        cache_hits.push(name.clone());
+                    }
                     outputs.insert(name.clone(), response.text);
-                    succeeded.push(name);
+                    succeeded.push(name.clone());
+                    (name, true)
                 }
                 Ok((name, Err(e))) => {
-                    failed.insert(name, e.to_string());
+                    failed.insert(name.clone(), e.to_string());
+                    (name, false)
                 }
                 Err(e) => {
                     // Task panicked or was cancelled
                     failed.insert("unknown".into(), e.to_string());
+                    ("unknown".to_string(), false)
                 }
+            };
+
+            if let Some(progress) = &self.progress {
+                progress(ParallelProgress {
+                    role: role.name.clone(),
+                    backend: name,
+                    success,
+                    completed,
+                    backends: started.clone(),
+                });
+            }
+
+            if role.quorum == QuorumMode::Wait || tasks.is_empty() {
+                continue;
             }
+
+            let remaining = started.len() - completed;
+            let reached = succeeded.len() as u32 >= role.min_success;
+            let impossible = (succeeded.len() + remaining) < role.min_success as usize;
+            if !reached && !impossible {
+                continue;
+            }
+
+            tracing::debug!(
+                role = %role.name,
+                succeeded = succeeded.len(),
+                remaining,
+                "Quorum decided, not waiting for remaining backends"
+            );
+            if role.quorum == QuorumMode::Background && reached {
+                tokio::spawn(finish_in_background(role.name.clone(), tasks));
+            } else {
+                tasks.abort_all();
+            }
+            break;
         }
 
+        // Keep role order regardless of completion order
+        succeeded.sort_by_key(|name| started.iter().position(|s| s == name));
+
         let success_count = succeeded.len() as u32;
 
         if success_count < role.min_success {
@@ -219,30 +667,44 @@ impl RoleExecutor {
             });
         }
 
-        // Combine outputs for the main output field
-        let combined_output = if !outputs.is_empty() {
-            Some(
-                outputs
-                    .iter()
-                    .map(|(k, v)| format!("=== {} ===\n{}", k, v))
-                    .collect::<Vec<_>>()
-                    .join("\n\n"),
-            )
-        } else {
-            None
-        };
-
         Ok(RoleResult {
-            output: combined_output,
+            output: None,
             outputs,
             succeeded,
             failed,
             duration: start.elapsed(),
             execution_mode: RoleExecution::Parallel,
+            cache_hits,
+            vote: None,
+            usage: None,
         })
     }
 }
 
+/// Let backends left over after a quorum finish, logging how they did
+async fn finish_in_background(
+    role: String,
+    mut tasks: tokio::task::JoinSet<(String, Result<BackendResponse, BackendError>)>,
+) {
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((backend, Ok(response))) => tracing::info!(
+                role = %role,
+                backend = %backend,
+                duration_ms = response.duration.as_millis() as u64,
+                "Backend finished after quorum"
+            ),
+            Ok((backend, Err(e))) => tracing::info!(
+                role = %role,
+                backend = %backend,
+                error = %e,
+                "Backend failed after quorum"
+            ),
+            Err(e) => tracing::warn!(role = %role, error = %e, "Backend task failed after quorum"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -301,6 +763,7 @@ mod tests {
             backends: vec!["echo".into()],
             execution: RoleExecution::First,
             min_success: 1,
+            ..Default::default()
         };
 
         let request = BackendRequest::new("hello");
@@ -322,6 +785,7 @@ mod tests {
             backends: vec!["echo".into(), "echo2".into()],
             execution: RoleExecution::Parallel,
             min_success: 1,
+            ..Default::default()
         };
 
         let request = BackendRequest::new("parallel test");
@@ -344,6 +808,7 @@ mod tests {
             backends: vec!["disabled".into(), "echo".into()],
             execution: RoleExecution::First,
             min_success: 1,
+            ..Default::default()
         };
 
         let request = BackendRequest::new("test");
@@ -353,6 +818,274 @@ mod tests {
         assert_eq!(result.succeeded, vec!["echo"]);
     }
 
+    #[tokio::test]
+    async fn test_race_mode_takes_fastest() {
+        let mut config = create_test_config();
+        config.backends.insert(
+            "slow".into(),
+            BackendConfig {
+                command: "sh".into(),
+                args: vec!["-c".into(), "sleep 5; echo slow".into(), "sh".into()],
+                ..Default::default()
+            },
+        );
+        let executor = RoleExecutor::new(Arc::new(config));
+
+        let role = ResolvedRole {
+            name: "test".into(),
+            backends: vec!["slow".into(), "echo".into()],
+            execution: RoleExecution::Race,
+            min_success: 1,
+            ..Default::default()
+        };
+
+        let result = executor
+            .execute(&role, &BackendRequest::new("race"))
+            .await
+            .unwrap();
+
+        assert_eq!(result.succeeded, vec!["echo"]);
+        assert_eq!(result.execution_mode, RoleExecution::Race);
+        assert!(result.duration < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_race_mode_hedges_after_delay() {
+        let mut config = create_test_config();
+        config.backends.insert(
+            "slow".into(),
+            BackendConfig {
+                command: "sh".into(),
+                args: vec!["-c".into(), "sleep 5; echo slow".into(), "sh".into()],
+                ..Default::default()
+            },
+        );
+        let executor = RoleExecutor::new(Arc::new(config));
+
+        // First racer answers before the hedge delay, so the slow one never starts
+        let role = ResolvedRole {
+            name: "test".into(),
+            backends: vec!["echo".into(), "slow".into()],
+            execution: RoleExecution::Race,
+            min_success: 1,
+            hedge_after_ms: Some(2000),
+            ..Default::default()
+        };
+        let result = executor
+            .execute(&role, &BackendRequest::new("hedge"))
+            .await
+            .unwrap();
+        assert_eq!(result.succeeded, vec!["echo"]);
+
+        // Slow first racer: the hedge starts echo after 50ms
+        let role = ResolvedRole {
+            backends: vec!["slow".into(), "echo".into()],
+            hedge_after_ms: Some(50),
+            ..role
+        };
+        let result = executor
+            .execute(&role, &BackendRequest::new("hedge"))
+            .await
+            .unwrap();
+        assert_eq!(result.succeeded, vec!["echo"]);
+        assert!(result.duration < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_first_mode_skips_unavailable() {
+        let mut config = create_test_config();
+        config.backends.insert(
+            "missing".into(),
+            BackendConfig {
+                command: "llmux-test-no-such-binary".into(),
+                ..Default::default()
+            },
+        );
+        let executor = RoleExecutor::new(Arc::new(config));
+
+        let role = ResolvedRole {
+            name: "test".into(),
+            backends: vec!["missing".into(), "echo".into()],
+            execution: RoleExecution::First,
+            min_success: 1,
+            ..Default::default()
+        };
+
+        let result = executor
+            .execute(&role, &BackendRequest::new("test"))
+            .await
+            .unwrap();
+
+        assert_eq!(result.succeeded, vec!["echo"]);
+        assert_eq!(result.failed["missing"], "skipped: unavailable");
+    }
+
+    #[tokio::test]
+    async fn test_fallback_mode_respects_error_classes() {
+        let mut config = create_test_config();
+        config.backends.insert(
+            "broken".into(),
+            BackendConfig {
+                command: "sh".into(),
+                args: vec!["-c".into(), "exit 1".into(), "sh".into()],
+                max_retries: 0,
+                ..Default::default()
+            },
+        );
+        let executor = RoleExecutor::new(Arc::new(config));
+
+        // Execution failures are not in the default fallback classes
+        let role = ResolvedRole {
+            name: "test".into(),
+            backends: vec!["broken".into(), "echo".into()],
+            execution: RoleExecution::Fallback,
+            min_success: 1,
+            fallback_on: crate::config::default_fallback_on(),
+            ..Default::default()
+        };
+        let result = executor.execute(&role, &BackendRequest::new("test")).await;
+        assert!(matches!(
+            result,
+            Err(ExecutionError::FallbackStopped {
+                class: ErrorClass::ExecutionFailed,
+                ..
+            })
+        ));
+
+        let role = ResolvedRole {
+            fallback_on: vec![ErrorClass::ExecutionFailed],
+            ..role
+        };
+        let result = executor
+            .execute(&role, &BackendRequest::new("test"))
+            .await
+            .unwrap();
+        assert_eq!(result.succeeded, vec!["echo"]);
+        assert_eq!(result.execution_mode, RoleExecution::Fallback);
+        assert!(result.failed["broken"].starts_with("execution_failed:"));
+    }
+
+    #[tokio::test]
+    async fn test_vote_mode_picks_majority() {
+        let mut config = create_test_config();
+        config.backends.insert(
+            "contrarian".into(),
+            BackendConfig {
+                command: "sh".into(),
+                args: vec!["-c".into(), "echo No".into(), "sh".into()],
+                ..Default::default()
+            },
+        );
+        let executor = RoleExecutor::new(Arc::new(config));
+
+        let role = ResolvedRole {
+            name: "test".into(),
+            backends: vec!["echo".into(), "contrarian".into(), "echo2".into()],
+            execution: RoleExecution::Vote,
+            min_success: 1,
+            ..Default::default()
+        };
+
+        let result = executor
+            .execute(&role, &BackendRequest::new("Yes."))
+            .await
+            .unwrap();
+
+        let vote = result.vote.as_ref().unwrap();
+        assert_eq!(vote.answer, "yes");
+        assert_eq!(vote.dissenters, vec!["contrarian"]);
+        assert_eq!(result.outputs.len(), 3);
+
+        let step = result.to_step_result();
+        assert_eq!(step.vote.as_deref(), Some("yes"));
+        assert_eq!(step.dissenters, vec!["contrarian"]);
+        assert_eq!(step.backend.as_deref(), Some("echo"));
+        assert!((step.agreement.unwrap() - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_quorum_returns_early_and_reports_progress() {
+        let mut config = create_test_config();
+        config.backends.insert(
+            "slow".into(),
+            BackendConfig {
+                command: "sh".into(),
+                args: vec!["-c".into(), "sleep 5; echo slow".into(), "sh".into()],
+                ..Default::default()
+            },
+        );
+
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let executor = RoleExecutor::new(Arc::new(config)).with_progress(Arc::new(move |p| {
+            sink.lock().unwrap().push(p);
+        }));
+
+        let role = ResolvedRole {
+            name: "test".into(),
+            backends: vec!["slow".into(), "echo".into(), "echo2".into()],
+            execution: RoleExecution::Parallel,
+            min_success: 2,
+            quorum: QuorumMode::Cancel,
+            ..Default::default()
+        };
+
+        let result = executor
+            .execute(&role, &BackendRequest::new("quorum"))
+            .await
+            .unwrap();
+
+        assert_eq!(result.succeeded, vec!["echo", "echo2"]);
+        assert!(result.duration < Duration::from_secs(2));
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].completed, 2);
+        assert_eq!(events[1].backends.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_quorum_fails_fast_when_impossible() {
+        let mut config = create_test_config();
+        config.backends.insert(
+            "broken".into(),
+            BackendConfig {
+                command: "sh".into(),
+                args: vec!["-c".into(), "exit 1".into(), "sh".into()],
+                max_retries: 0,
+                ..Default::default()
+            },
+        );
+        config.backends.insert(
+            "slow".into(),
+            BackendConfig {
+                command: "sh".into(),
+                args: vec!["-c".into(), "sleep 5; echo slow".into(), "sh".into()],
+                ..Default::default()
+            },
+        );
+        let executor = RoleExecutor::new(Arc::new(config));
+
+        // With one of two backends failed, min_success = 2 can no longer be met
+        let role = ResolvedRole {
+            name: "test".into(),
+            backends: vec!["broken".into(), "slow".into()],
+            execution: RoleExecution::Parallel,
+            min_success: 2,
+            quorum: QuorumMode::Cancel,
+            ..Default::default()
+        };
+
+        let start = Instant::now();
+        let result = executor.execute(&role, &BackendRequest::new("x")).await;
+
+        assert!(matches!(
+            result,
+            Err(ExecutionError::InsufficientSuccesses { got: 0, .. })
+        ));
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
     #[tokio::test]
     async fn test_all_failed() {
         let config = Arc::new(create_test_config());
@@ -363,6 +1096,7 @@ mod tests {
             backends: vec!["nonexistent".into()],
             execution: RoleExecution::First,
             min_success: 1,
+            ..Default::default()
         };
 
         let request = BackendRequest::new("test");
@@ -371,6 +1105,40 @@ mod tests {
         assert!(matches!(result, Err(ExecutionError::AllFailed { .. })));
     }
 
+    #[tokio::test]
+    async fn test_breaker_rejections_not_recorded_in_stats() {
+        let mut config = create_test_config();
+        config.backends.insert(
+            "stats-breaker-test".into(),
+            BackendConfig {
+                // A missing command is unavailable, which trips the breaker
+                command: "llmux-test-missing-command".into(),
+                max_retries: 0,
+                breaker_threshold: Some(1),
+                breaker_cooldown: 3600,
+                ..Default::default()
+            },
+        );
+        let dir = tempfile::TempDir::new().unwrap();
+        let stats = Arc::new(StatsStore::open(&dir.path().join("stats.db")).unwrap());
+        let executor = RoleExecutor::new(Arc::new(config)).with_stats(stats.clone());
+
+        let role = ResolvedRole {
+            name: "test".into(),
+            backends: vec!["stats-breaker-test".into()],
+            execution: RoleExecution::Parallel,
+            min_success: 1,
+            ..Default::default()
+        };
+        let request = BackendRequest::new("test");
+        for _ in 0..3 {
+            assert!(executor.execute(&role, &request).await.is_err());
+        }
+
+        // Only the call that reached the backend counts against it
+        assert_eq!(stats.stats("stats-breaker-test").unwrap().calls, 1);
+    }
+
     #[test]
     fn test_role_result_to_step_result() {
         let role_result = RoleResult {
@@ -380,6 +1148,9 @@ mod tests {
             failed: HashMap::new(),
             duration: Duration::from_secs(1),
             execution_mode: RoleExecution::First,
+            cache_hits: Vec::new(),
+            vote: None,
+            usage: None,
         };
 
         let step_result = role_result.to_step_result();
@@ -2,7 +2,11 @@
 
 //! Resolve role names to backend lists using team context
 
-use crate::config::{LlmuxConfig, RoleExecution};
+use crate::config::{
+    BackendOrder, BackendRequirements, ErrorClass, LlmuxConfig, Optimize, QuorumMode, RoleConfig,
+    RoleExecution, default_fallback_on,
+};
+use std::collections::HashMap;
 use thiserror::Error;
 
 /// Errors that can occur during role resolution
@@ -19,10 +23,13 @@ pub enum RoleError {
 
     #[error("role '{role}' has no backends configured")]
     NoBackends { role: String },
+
+    #[error("role '{role}' has no backend with the required capabilities ({requires})")]
+    NoCapableBackend { role: String, requires: String },
 }
 
 /// Resolved role with backends and execution mode
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ResolvedRole {
     /// Role name
     pub name: String,
@@ -33,8 +40,56 @@ pub struct ResolvedRole {
     /// How to execute across backends
     pub execution: RoleExecution,
 
+    /// Order in which backends are tried
+    pub order: BackendOrder,
+
+    /// What adaptive ordering optimizes for
+    pub optimize: Optimize,
+
     /// Minimum successful backends (for parallel mode)
     pub min_success: u32,
+
+    /// Whether to stop waiting once `min_success` is reached
+    pub quorum: QuorumMode,
+
+    /// Number of backends to race (for race mode)
+    pub race_width: Option<u32>,
+
+    /// Delay before starting the next racer (for race mode)
+    pub hedge_after_ms: Option<u64>,
+
+    /// Error classes that move fallback mode on to the next backend
+    pub fallback_on: Vec<ErrorClass>,
+
+    /// Vote weight per backend (for vote mode)
+    pub weights: HashMap<String, f64>,
+
+    /// Backend whose answer wins a tied vote (for vote mode)
+    pub tie_break: Option<String>,
+
+    /// Role that merges parallel outputs (for parallel mode)
+    pub judge: Option<String>,
+}
+
+impl ResolvedRole {
+    /// Build from a role definition
+    fn from_config(name: &str, config: &RoleConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            backends: config.backends.clone(),
+            execution: config.execution,
+            order: config.order,
+            optimize: config.optimize,
+            min_success: config.min_success,
+            quorum: config.quorum,
+            race_width: config.race_width,
+            hedge_after_ms: config.hedge_after_ms,
+            fallback_on: config.fallback_on.clone(),
+            weights: config.weights.clone(),
+            tie_break: config.tie_break.clone(),
+            judge: config.judge.clone(),
+        }
+    }
 }
 
 /// Role resolver that maps role names to backends
@@ -62,21 +117,25 @@ impl<'a> RoleResolver<'a> {
                     // Validate backends exist
                     self.validate_backends(&override_.backends)?;
 
-                    // Get execution mode from override or fall back to global role
-                    let (execution, min_success) = if let Some(exec) = override_.execution {
-                        (exec, 1) // Override specifies execution mode
-                    } else if let Some(global_role) = self.config.roles.get(role) {
-                        (global_role.execution, global_role.min_success)
-                    } else {
-                        (RoleExecution::First, 1)
+                    // Start from the global role's settings, if any
+                    let mut resolved = match self.config.roles.get(role) {
+                        Some(global_role) => ResolvedRole::from_config(role, global_role),
+                        None => ResolvedRole {
+                            name: role.to_string(),
+                            min_success: 1,
+                            fallback_on: default_fallback_on(),
+                            ..Default::default()
+                        },
                     };
+                    resolved.backends = override_.backends.clone();
+
+                    // Override specifies execution mode
+                    if let Some(exec) = override_.execution {
+                        resolved.execution = exec;
+                        resolved.min_success = 1;
+                    }
 
-                    return Ok(ResolvedRole {
-                        name: role.to_string(),
-                        backends: override_.backends.clone(),
-                        execution,
-                        min_success,
-                    });
+                    return Ok(resolved);
                 }
             }
         }
@@ -92,12 +151,7 @@ impl<'a> RoleResolver<'a> {
             // Validate backends exist
             self.validate_backends(&role_config.backends)?;
 
-            return Ok(ResolvedRole {
-                name: role.to_string(),
-                backends: role_config.backends.clone(),
-                execution: role_config.execution,
-                min_success: role_config.min_success,
-            });
+            return Ok(ResolvedRole::from_config(role, role_config));
         }
 
         Err(RoleError::RoleNotFound {
@@ -105,6 +159,41 @@ impl<'a> RoleResolver<'a> {
         })
     }
 
+    /// Resolve a role, keeping only backends with the required capabilities
+    ///
+    /// Backends with the preferred capabilities are moved to the front,
+    /// otherwise keeping the role's order.
+    pub fn resolve_for(
+        &self,
+        role: &str,
+        team: Option<&str>,
+        requires: &BackendRequirements,
+        prefers: &BackendRequirements,
+    ) -> Result<ResolvedRole, RoleError> {
+        let mut resolved = self.resolve(role, team)?;
+        if requires.is_empty() && prefers.is_empty() {
+            return Ok(resolved);
+        }
+
+        let backends = &self.config.backends;
+        resolved
+            .backends
+            .retain(|name| backends.get(name).is_some_and(|b| b.satisfies(requires)));
+        if resolved.backends.is_empty() {
+            return Err(RoleError::NoCapableBackend {
+                role: role.to_string(),
+                requires: requires.to_string(),
+            });
+        }
+
+        // Stable sort: preferred backends first
+        resolved
+            .backends
+            .sort_by_key(|name| !backends.get(name).is_some_and(|b| b.satisfies(prefers)));
+
+        Ok(resolved)
+    }
+
     /// Validate that all backends exist in config
     fn validate_backends(&self, backends: &[String]) -> Result<(), RoleError> {
         for backend in backends {
@@ -133,6 +222,17 @@ pub fn resolve_role(
     resolver.resolve(role, team)
 }
 
+/// Convenience function to resolve a role for a step's capability requirements
+pub fn resolve_role_for(
+    role: &str,
+    team: Option<&str>,
+    config: &LlmuxConfig,
+    requires: &BackendRequirements,
+    prefers: &BackendRequirements,
+) -> Result<ResolvedRole, RoleError> {
+    RoleResolver::new(config).resolve_for(role, team, requires, prefers)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -173,6 +273,7 @@ mod tests {
                 backends: vec!["claude".into(), "codex".into()],
                 execution: RoleExecution::First,
                 min_success: 1,
+                ..Default::default()
             },
         );
         config.roles.insert(
@@ -182,6 +283,7 @@ mod tests {
                 backends: vec!["claude".into()],
                 execution: RoleExecution::Parallel,
                 min_success: 1,
+                ..Default::default()
             },
         );
 
@@ -208,6 +310,37 @@ mod tests {
         config
     }
 
+    #[test]
+    fn test_resolve_for_capabilities() {
+        let mut config = create_test_config();
+        config.backends.get_mut("codex").unwrap().supports_json = true;
+        config.backends.get_mut("codex").unwrap().local = true;
+        let resolver = RoleResolver::new(&config);
+
+        let json = BackendRequirements {
+            json: true,
+            ..Default::default()
+        };
+        let local = BackendRequirements {
+            local: true,
+            ..Default::default()
+        };
+        let none = BackendRequirements::default();
+
+        let resolved = resolver
+            .resolve_for("analyzer", None, &json, &none)
+            .unwrap();
+        assert_eq!(resolved.backends, vec!["codex"]);
+
+        let resolved = resolver
+            .resolve_for("analyzer", None, &none, &local)
+            .unwrap();
+        assert_eq!(resolved.backends, vec!["codex", "claude"]);
+
+        let result = resolver.resolve_for("reviewer", None, &json, &none);
+        assert!(matches!(result, Err(RoleError::NoCapableBackend { .. })));
+    }
+
     #[test]
     fn test_resolve_global_role() {
         let config = create_test_config();
@@ -0,0 +1,209 @@
+#![allow(dead_code)]
+
+//! Majority voting across parallel backend answers
+
+use crate::backend_executor::output_parser::{extract_json, lookup};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// How to reduce a backend's output to a comparable answer
+#[derive(Debug, Clone, Default)]
+pub struct VoteKey {
+    /// Dot-separated path into the JSON answer to vote on (e.g. `verdict` or `result.severity`)
+    pub extract: Option<String>,
+
+    /// Compare the whole JSON answer rather than its text
+    pub structured: bool,
+}
+
+/// Outcome of a vote
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoteOutcome {
+    /// Normalized winning answer
+    pub answer: String,
+
+    /// Backends that voted for the winning answer
+    pub winners: Vec<String>,
+
+    /// Share of the total vote weight behind the winning answer (0.0–1.0)
+    pub agreement: f64,
+
+    /// Backends that voted for a different answer
+    pub dissenters: Vec<String>,
+
+    /// Whether the winner was decided by the tie-break
+    pub tie_broken: bool,
+}
+
+/// Normalize an output into the answer it votes for
+///
+/// With an `extract` path the value at that path is used; with `structured`
+/// the whole JSON answer is compared with its keys sorted. Otherwise (or when
+/// no JSON can be found) the text is compared case- and whitespace-insensitively.
+pub fn normalize_answer(output: &str, key: &VoteKey) -> String {
+    if key.extract.is_some() || key.structured {
+        let value = extract_json(output).and_then(|json| match &key.extract {
+            Some(path) => lookup(&json, path).cloned(),
+            None => Some(json),
+        });
+        match value {
+            Some(Value::String(s)) => return normalize_text(&s),
+            Some(value) => return value.to_string(),
+            None => {}
+        }
+    }
+
+    normalize_text(output)
+}
+
+/// Lowercase, collapse whitespace and drop surrounding quotes and trailing punctuation
+fn normalize_text(text: &str) -> String {
+    let collapsed = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+
+    collapsed
+        .trim_matches(|c: char| matches!(c, '"' | '\'' | '`' | '*'))
+        .trim_end_matches(['.', '!', '?'])
+        .trim()
+        .to_string()
+}
+
+/// Tally normalized answers and pick the winner
+///
+/// `answers` holds `(backend, answer)` pairs in role order. Ties go to the
+/// `tie_break` backend's answer when it is among the leaders, otherwise to
+/// the leader whose first voter comes earliest.
+pub fn tally(
+    answers: &[(String, String)],
+    weights: &HashMap<String, f64>,
+    tie_break: Option<&str>,
+) -> Option<VoteOutcome> {
+    let weight = |backend: &str| weights.get(backend).copied().unwrap_or(1.0).max(0.0);
+
+    // Answers in order of first appearance, with their total weight
+    let mut totals: Vec<(&str, f64)> = Vec::new();
+    for (backend, answer) in answers {
+        match totals.iter_mut().find(|(a, _)| a == answer) {
+            Some((_, total)) => *total += weight(backend),
+            None => totals.push((answer, weight(backend))),
+        }
+    }
+
+    let best = totals
+        .iter()
+        .map(|(_, w)| *w)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let leaders: Vec<&str> = totals
+        .iter()
+        .filter(|(_, w)| *w == best)
+        .map(|(a, _)| *a)
+        .collect();
+
+    let tie_broken = leaders.len() > 1;
+    let preferred = tie_break.and_then(|tb| {
+        answers
+            .iter()
+            .find(|(backend, _)| backend == tb)
+            .map(|(_, answer)| answer.as_str())
+            .filter(|answer| leaders.contains(answer))
+    });
+    let winner = preferred.or_else(|| leaders.first().copied())?;
+
+    let total: f64 = totals.iter().map(|(_, w)| w).sum();
+    let (winners, dissenters) = answers
+        .iter()
+        .partition::<Vec<_>, _>(|(_, answer)| answer == winner);
+
+    Some(VoteOutcome {
+        answer: winner.to_string(),
+        winners: winners.into_iter().map(|(b, _)| b.clone()).collect(),
+        agreement: if total > 0.0 { best / total } else { 0.0 },
+        dissenters: dissenters.into_iter().map(|(b, _)| b.clone()).collect(),
+        tie_broken,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn votes(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(b, a)| (b.to_string(), a.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_normalize_text() {
+        let key = VoteKey::default();
+        assert_eq!(normalize_answer("  Yes.\n", &key), "yes");
+        assert_eq!(normalize_answer("**YES**", &key), "yes");
+        assert_eq!(normalize_answer("High  severity", &key), "high severity");
+    }
+
+    #[test]
+    fn test_normalize_extract() {
+        let key = VoteKey {
+            extract: Some("result.severity".into()),
+            structured: false,
+        };
+        let output = "Here you go:\n```json\n{\"result\": {\"severity\": \"High\"}}\n```";
+        assert_eq!(normalize_answer(output, &key), "high");
+
+        // Falls back to the text when the path is missing
+        assert_eq!(normalize_answer("Low", &key), "low");
+    }
+
+    #[test]
+    fn test_normalize_structured() {
+        let key = VoteKey {
+            extract: None,
+            structured: true,
+        };
+        assert_eq!(
+            normalize_answer(r#"{"b": 1, "a": true}"#, &key),
+            normalize_answer("```json\n{\"a\": true, \"b\": 1}\n```", &key)
+        );
+    }
+
+    #[test]
+    fn test_majority_wins() {
+        let answers = votes(&[("claude", "yes"), ("codex", "no"), ("gemini", "yes")]);
+        let outcome = tally(&answers, &HashMap::new(), None).unwrap();
+
+        assert_eq!(outcome.answer, "yes");
+        assert_eq!(outcome.winners, vec!["claude", "gemini"]);
+        assert_eq!(outcome.dissenters, vec!["codex"]);
+        assert!((outcome.agreement - 2.0 / 3.0).abs() < 1e-9);
+        assert!(!outcome.tie_broken);
+    }
+
+    #[test]
+    fn test_weights_override_majority() {
+        let answers = votes(&[("claude", "yes"), ("codex", "no"), ("gemini", "no")]);
+        let weights = HashMap::from([("claude".to_string(), 3.0)]);
+        let outcome = tally(&answers, &weights, None).unwrap();
+
+        assert_eq!(outcome.answer, "yes");
+        assert!((outcome.agreement - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_tie_break() {
+        let answers = votes(&[("claude", "yes"), ("codex", "no")]);
+
+        let outcome = tally(&answers, &HashMap::new(), None).unwrap();
+        assert_eq!(outcome.answer, "yes");
+        assert!(outcome.tie_broken);
+
+        let outcome = tally(&answers, &HashMap::new(), Some("codex")).unwrap();
+        assert_eq!(outcome.answer, "no");
+        assert_eq!(outcome.dissenters, vec!["claude"]);
+
+        assert!(tally(&[], &HashMap::new(), None).is_none());
+    }
+}
@@ -17,6 +17,9 @@ pub struct TemplateContext {
     /// CLI arguments passed to the workflow
     pub args: HashMap<String, String>,
 
+    /// Typed values of declared arguments, shown instead of their text in `args`
+    pub typed_args: HashMap<String, Value>,
+
     /// Current team configuration (if detected)
     pub team: Option<TeamConfig>,
 
@@ -31,6 +34,9 @@ pub struct TemplateContext {
 
     /// Workflow name
     pub workflow: Option<String>,
+
+    /// Extra top-level variables for a single render (e.g. `outputs` in merge templates)
+    pub locals: HashMap<String, Value>,
 }
 
 /// Ecosystem context for templates
@@ -80,6 +86,22 @@ impl TemplateContext {
         self.team = Some(team);
     }
 
+    /// Set an extra top-level variable
+    pub fn set_local(&mut self, name: impl Into<String>, value: Value) {
+        self.locals.insert(name.into(), value);
+    }
+
+    /// Set `loop` for an iteration of a loop step: its 1-based `index`, `first`,
+    /// and the `previous` iteration's step results
+    pub fn set_loop(&mut self, index: u32, previous: HashMap<String, StepResult>) {
+        let value = Value::from_iter([
+            ("index", Value::from(index)),
+            ("first", Value::from(index == 1)),
+            ("previous", Value::from_object(StepsObject(previous))),
+        ]);
+        self.locals.insert("loop".into(), value);
+    }
+
     /// Set the workflow name
     pub fn set_workflow(&mut self, name: impl Into<String>) {
         self.workflow = Some(name.into());
@@ -107,6 +129,7 @@ impl TemplateContext {
     /// Get list of known top-level variable names for error suggestions
     pub fn known_variables(&self) -> Vec<&str> {
         let mut vars = vec!["steps", "args", "env", "item", "workflow"];
+        vars.extend(self.locals.keys().map(|k| k.as_str()));
         if self.team.is_some() {
             vars.push("team");
         }
@@ -137,7 +160,16 @@ impl Object for ContextObject {
         let key_str = key.as_str()?;
         match key_str {
             "steps" => Some(Value::from_object(StepsObject(self.0.steps.clone()))),
-            "args" => Some(Value::from_object(ArgsObject(self.0.args.clone()))),
+            "args" => {
+                let mut args: HashMap<String, Value> = self
+                    .0
+                    .args
+                    .iter()
+                    .map(|(name, value)| (name.clone(), Value::from(value.clone())))
+                    .collect();
+                args.extend(self.0.typed_args.clone());
+                Some(Value::from_object(ArgsObject(args)))
+            }
             "team" => self
                 .0
                 .team
@@ -151,12 +183,12 @@ impl Object for ContextObject {
             "item" => self.0.item.clone(),
             "workflow" => self.0.workflow.as_ref().map(|w| Value::from(w.clone())),
             "env" => Some(Value::from_object(EnvObject)),
-            _ => None,
+            _ => self.0.locals.get(key_str).cloned(),
         }
     }
 
     fn enumerate(self: &Arc<Self>) -> minijinja::value::Enumerator {
-        minijinja::value::Enumerator::Str(&[
+        let mut keys: Vec<Value> = [
             "steps",
             "args",
             "team",
@@ -164,7 +196,12 @@ impl Object for ContextObject {
             "item",
             "workflow",
             "env",
-        ])
+        ]
+        .into_iter()
+        .map(Value::from)
+        .collect();
+        keys.extend(self.0.locals.keys().map(|k| Value::from(k.clone())));
+        minijinja::value::Enumerator::Values(keys)
     }
 }
 
@@ -227,6 +264,24 @@ impl Object for StepResultObject {
             "backends" => Some(Value::from_iter(
                 self.0.backends.iter().cloned().map(Value::from),
             )),
+            "cached" => Some(Value::from(
+                !self.0.cache_hits.is_empty() && self.0.cache_hits.len() == self.0.backends.len(),
+            )),
+            "cache_hits" => Some(Value::from_iter(
+                self.0.cache_hits.iter().cloned().map(Value::from),
+            )),
+            "vote" => self.0.vote.clone().map(Value::from),
+            "agreement" => self.0.agreement.map(Value::from),
+            "dissenters" => Some(Value::from_iter(
+                self.0.dissenters.iter().cloned().map(Value::from),
+            )),
+            "steps" => Some(Value::from_object(StepsObject(self.0.steps.clone()))),
+            "items" => {
+                Some(Value::from_iter(self.0.items.iter().map(|item| {
+                    Value::from_object(StepResultObject(item.clone()))
+                })))
+            }
+            "matrix" => Some(Value::from_object(StepsObject(self.0.matrix.clone()))),
             _ => None,
         }
     }
@@ -240,13 +295,21 @@ impl Object for StepResultObject {
             "duration_ms",
             "backend",
             "backends",
+            "cached",
+            "cache_hits",
+            "vote",
+            "agreement",
+            "dissenters",
+            "steps",
+            "items",
+            "matrix",
         ])
     }
 }
 
 /// Object for accessing CLI arguments
 #[derive(Debug, Clone)]
-struct ArgsObject(HashMap<String, String>);
+struct ArgsObject(HashMap<String, Value>);
 
 impl fmt::Display for ArgsObject {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -257,7 +320,7 @@ impl fmt::Display for ArgsObject {
 impl Object for ArgsObject {
     fn get_value(self: &Arc<Self>, key: &Value) -> Option<Value> {
         let arg_name = key.as_str()?;
-        self.0.get(arg_name).map(|v| Value::from(v.clone()))
+        self.0.get(arg_name).cloned()
     }
 
     fn enumerate(self: &Arc<Self>) -> minijinja::value::Enumerator {
@@ -35,7 +35,7 @@ mod filters;
 pub use conditionals::{evaluate_condition, evaluate_expression, should_execute_step};
 pub use context::TemplateContext;
 pub use engine::TemplateEngine;
-pub use errors::TemplateError;
+pub use errors::{TemplateError, suggest_correction};
 
 #[cfg(test)]
 mod tests {
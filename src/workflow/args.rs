@@ -0,0 +1,227 @@
+//! Checking workflow arguments against their definitions
+
+use crate::config::{ArgType, WorkflowConfig};
+use crate::template::suggest_correction;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Check `args` against the workflow's definitions, filling in defaults
+///
+/// Returns typed values of the declared arguments, or every problem found.
+pub fn check_args(
+    workflow: &WorkflowConfig,
+    args: &mut HashMap<String, String>,
+    working_dir: &Path,
+) -> Result<HashMap<String, serde_json::Value>, Vec<String>> {
+    let mut errors = Vec::new();
+
+    // Undeclared arguments are most likely typos, unless nothing is declared
+    if !workflow.args.is_empty() {
+        let known: Vec<&str> = workflow.args.keys().map(String::as_str).collect();
+        let mut names: Vec<_> = args.keys().collect();
+        names.sort();
+        for name in names {
+            if workflow.args.contains_key(name) || is_positional(name) {
+                continue;
+            }
+            let mut error = format!("unknown argument '{}'", name);
+            if let Some(suggestion) = suggest_correction(name, &known) {
+                let _ = write!(error, ", did you mean '{}'?", suggestion);
+            }
+            errors.push(error);
+        }
+    }
+
+    let mut names: Vec<_> = workflow.args.keys().collect();
+    names.sort();
+    let mut typed = HashMap::new();
+    for name in names {
+        let def = &workflow.args[name];
+        if !args.contains_key(name)
+            && let Some(default) = def.default_value()
+        {
+            args.insert(name.clone(), default);
+        }
+        let Some(value) = args.get(name) else {
+            if def.required {
+                errors.push(format!("missing required argument '{}'", name));
+            }
+            continue;
+        };
+
+        let checked = def.parse(value).and_then(|typed| {
+            let path = working_dir.join(value);
+            match def.arg_type {
+                ArgType::Path if !path.exists() => Err(format!("'{}' does not exist", value)),
+                ArgType::File if !path.is_file() => Err(format!("'{}' is not a file", value)),
+                _ => Ok(typed),
+            }
+        });
+        match checked {
+            Ok(value) => {
+                typed.insert(name.clone(), value);
+            }
+            Err(e) => errors.push(format!("argument '{}': {}", name, e)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(typed)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Positional arguments are passed as `arg0`, `arg1`, ...
+fn is_positional(name: &str) -> bool {
+    name.strip_prefix("arg")
+        .is_some_and(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Usage text for `llmux run <workflow> --help`
+pub fn usage(name: &str, workflow: &WorkflowConfig) -> String {
+    let mut out = format!("Usage: llmux run {}", name);
+
+    let mut args: Vec<_> = workflow.args.iter().collect();
+    args.sort_by_key(|(name, def)| (!def.required, name.as_str()));
+    for (name, def) in &args {
+        if def.required {
+            let _ = write!(out, " {}=<{}>", name, def.arg_type.name());
+        }
+    }
+    if args.iter().any(|(_, def)| !def.required) {
+        out.push_str(" [<arg>=<value>]...");
+    }
+    out.push('\n');
+
+    if !workflow.description.is_empty() {
+        let _ = write!(out, "\n{}\n", workflow.description);
+    }
+    if args.is_empty() {
+        return out;
+    }
+
+    let labels: Vec<String> = args
+        .iter()
+        .map(|(name, def)| {
+            let value = if def.choices.is_empty() {
+                def.arg_type.name().to_string()
+            } else {
+                def.choices.join("|")
+            };
+            format!("{}=<{}>", name, value)
+        })
+        .collect();
+    let width = labels.iter().map(String::len).max().unwrap_or(0);
+
+    out.push_str("\nArguments:\n");
+    for ((_, def), label) in args.iter().zip(&labels) {
+        let mut notes = Vec::new();
+        if !def.description.is_empty() {
+            notes.push(def.description.clone());
+        }
+        if def.required {
+            notes.push("(required)".to_string());
+        }
+        if let Some(default) = def.default_value() {
+            notes.push(format!("[default: {}]", default));
+        }
+        if let Some(ref pattern) = def.pattern {
+            notes.push(format!("[pattern: {}]", pattern));
+        }
+        let line = format!("  {:<width$}  {}", label, notes.join(" "));
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out.push_str(
+        "\nValues starting with @ are read from that file (@@ for a literal @); - reads stdin.\n",
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn workflow() -> WorkflowConfig {
+        toml::from_str(
+            r#"
+name = "review"
+description = "Review a change"
+
+[args.issue]
+type = "int"
+required = true
+description = "Issue number"
+
+[args.depth]
+type = "enum"
+choices = ["quick", "thorough"]
+default = "quick"
+
+[args.dry_run]
+type = "bool"
+default = false
+
+[args.spec]
+type = "file"
+
+[args.ticket]
+pattern = "^[A-Z]+-\\d+$"
+"#,
+        )
+        .unwrap()
+    }
+
+    fn args(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_args() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("spec.md"), "spec").unwrap();
+        let workflow = workflow();
+
+        let mut given = args(&[("issue", "42"), ("spec", "spec.md"), ("arg0", "extra")]);
+        let typed = check_args(&workflow, &mut given, dir.path()).unwrap();
+        assert_eq!(typed["issue"], serde_json::json!(42));
+        assert_eq!(typed["dry_run"], serde_json::json!(false));
+        assert_eq!(given["depth"], "quick");
+        assert!(!typed.contains_key("ticket"));
+
+        let mut given = args(&[
+            ("isue", "42"),
+            ("depth", "deep"),
+            ("spec", "missing.md"),
+            ("ticket", "abc"),
+        ]);
+        let errors = check_args(&workflow, &mut given, dir.path()).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                "unknown argument 'isue', did you mean 'issue'?",
+                "argument 'depth': 'deep' is not one of: quick, thorough",
+                "missing required argument 'issue'",
+                "argument 'spec': 'missing.md' is not a file",
+                "argument 'ticket': 'abc' does not match /^[A-Z]+-\\d+$/",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_usage() {
+        let usage = usage("review", &workflow());
+        assert!(usage.starts_with("Usage: llmux run review issue=<int> [<arg>=<value>]...\n"));
+        assert!(usage.contains("Review a change"));
+        assert!(usage.contains("depth=<quick|thorough>"));
+        assert!(usage.contains("Issue number (required)"));
+        assert!(usage.contains("[default: false]"));
+        assert!(usage.contains("(@@ for a literal @)"));
+    }
+}
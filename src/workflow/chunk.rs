@@ -0,0 +1,173 @@
+//! Splitting large inputs into chunks for map_reduce steps
+
+use crate::config::ChunkBy;
+use serde::Serialize;
+
+/// Default lines per chunk when chunking by lines
+pub const DEFAULT_CHUNK_LINES: usize = 500;
+
+/// Default approximate tokens per chunk when chunking by tokens
+pub const DEFAULT_CHUNK_TOKENS: usize = 8000;
+
+/// Rough characters per token, for sizing chunks without a tokenizer
+const CHARS_PER_TOKEN: usize = 4;
+
+/// A piece of a map_reduce step's input, exposed to map prompts as `chunk`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Chunk {
+    /// Position among the chunks, from 1
+    pub index: usize,
+
+    /// Number of chunks
+    pub total: usize,
+
+    /// The chunk's content
+    pub text: String,
+
+    /// Files whose diffs appear in the chunk
+    pub files: Vec<String>,
+}
+
+/// Split `input` into chunks; `size` is lines, files or approximate tokens per chunk
+pub fn split(input: &str, by: ChunkBy, size: Option<usize>) -> Vec<Chunk> {
+    let texts = match by {
+        ChunkBy::Lines => by_lines(input, size.unwrap_or(DEFAULT_CHUNK_LINES)),
+        ChunkBy::Files => by_files(input, size.unwrap_or(1)),
+        ChunkBy::Tokens => by_tokens(input, size.unwrap_or(DEFAULT_CHUNK_TOKENS)),
+    };
+
+    let total = texts.len();
+    texts
+        .into_iter()
+        .enumerate()
+        .map(|(index, text)| Chunk {
+            index: index + 1,
+            total,
+            files: diff_files(&text),
+            text,
+        })
+        .collect()
+}
+
+fn by_lines(input: &str, lines_per_chunk: usize) -> Vec<String> {
+    let lines: Vec<&str> = input.lines().collect();
+    lines
+        .chunks(lines_per_chunk.max(1))
+        .map(|lines| lines.join("\n"))
+        .collect()
+}
+
+/// Split a unified diff at its `diff --git` headers, `files_per_chunk` files at a time
+fn by_files(input: &str, files_per_chunk: usize) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+    for line in input.lines() {
+        if line.starts_with("diff --git ") || files.is_empty() {
+            files.push(String::new());
+        }
+        let file = files.last_mut().expect("a file was just pushed");
+        if !file.is_empty() {
+            file.push('\n');
+        }
+        file.push_str(line);
+    }
+
+    files
+        .chunks(files_per_chunk.max(1))
+        .map(|files| files.join("\n"))
+        .collect()
+}
+
+/// Split at line boundaries into chunks of about `tokens` tokens; longer lines are cut
+fn by_tokens(input: &str, tokens: usize) -> Vec<String> {
+    let max_chars = tokens.saturating_mul(CHARS_PER_TOKEN).max(1);
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    // Character counts, kept as we go so long inputs aren't rescanned
+    let mut current_chars = 0;
+
+    for line in input.lines() {
+        let mut line = line;
+        let mut line_chars = line.chars().count();
+        loop {
+            let separator = usize::from(!current.is_empty());
+            let room = max_chars.saturating_sub(current_chars + separator);
+            if line_chars <= room {
+                if !current.is_empty() {
+                    current.push('\n');
+                }
+                current.push_str(line);
+                current_chars += separator + line_chars;
+                break;
+            }
+            if !current.is_empty() {
+                chunks.push(std::mem::take(&mut current));
+                current_chars = 0;
+                continue;
+            }
+            // A single line longer than a chunk
+            let cut = line
+                .char_indices()
+                .nth(max_chars)
+                .map_or(line.len(), |(i, _)| i);
+            chunks.push(line[..cut].to_string());
+            line = &line[cut..];
+            line_chars = line_chars.saturating_sub(max_chars);
+            if line.is_empty() {
+                break;
+            }
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Paths named by `diff --git a/<path> b/<path>` headers
+fn diff_files(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| line.strip_prefix("diff --git "))
+        .filter_map(|paths| paths.split_once(" b/").map(|(_, path)| path.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "diff --git a/src/a.rs b/src/a.rs\n+one\ndiff --git a/src/b.rs b/src/b.rs\n+two\ndiff --git a/c.md b/c.md\n+three";
+
+    #[test]
+    fn test_split_by_files() {
+        let chunks = split(DIFF, ChunkBy::Files, Some(2));
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].files, vec!["src/a.rs", "src/b.rs"]);
+        assert_eq!(chunks[1].files, vec!["c.md"]);
+        assert_eq!((chunks[1].index, chunks[1].total), (2, 2));
+        assert!(chunks[1].text.starts_with("diff --git a/c.md"));
+    }
+
+    #[test]
+    fn test_split_by_lines() {
+        let chunks = split("1\n2\n3\n4\n5", ChunkBy::Lines, Some(2));
+        let texts: Vec<_> = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["1\n2", "3\n4", "5"]);
+        assert!(split("", ChunkBy::Lines, None).is_empty());
+    }
+
+    #[test]
+    fn test_split_by_tokens() {
+        // 2 tokens is about 8 characters
+        let chunks = split("abc\ndef\nghijklmnopqrst", ChunkBy::Tokens, Some(2));
+        let texts: Vec<_> = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["abc\ndef", "ghijklmn", "opqrst"]);
+
+        // Sizes count characters, not bytes
+        let chunks = split("äöü\néèê\nß", ChunkBy::Tokens, Some(2));
+        let texts: Vec<_> = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["äöü\néèê", "ß"]);
+
+        // A huge size doesn't overflow
+        assert_eq!(split("abc", ChunkBy::Tokens, Some(usize::MAX)).len(), 1);
+    }
+}
@@ -2,18 +2,31 @@
 
 //! Step execution logic
 
+use super::chunk;
+use super::runner::{WorkflowRunner, join_all};
+use super::testing::StepMock;
 use crate::apply_and_verify::RollbackStrategy;
 use crate::apply_and_verify::{ApplyVerifyConfig, ApplyVerifyError, apply_and_verify, apply_only};
-use crate::backend_executor::BackendRequest;
-use crate::config::{LlmuxConfig, StepConfig, StepResult, StepType};
+use crate::backend_executor::{
+    BackendRequest, Message, MessageRole, ResponseCache, StatsStore, Toolbox,
+};
+use crate::config::{
+    LlmuxConfig, RoleExecution, StepConfig, StepResult, StepType, load_sub_workflow,
+};
 use crate::process::{OutputStream, OutputWaitError, exit_status_code, wait_for_child_output};
-use crate::role::{RoleExecutor, resolve_role};
+use crate::role::{
+    ParallelProgress, ResolvedRole, RoleExecutor, RoleResult, VoteKey, resolve_role_for,
+};
 use crate::template::{TemplateContext, TemplateEngine, evaluate_condition};
+use minijinja::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 use std::process::Stdio;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use thiserror::Error;
 use tokio::process::Command;
+use tokio::sync::Semaphore;
 use tokio::time::timeout;
 
 /// Errors during step execution
@@ -43,18 +56,38 @@ pub enum StepExecutionError {
     #[error("source step '{source_step}' not found for apply step '{step}'")]
     SourceNotFound { step: String, source_step: String },
 
+    #[error("step '{step}' cannot continue from '{source_step}': it has no conversation")]
+    NoConversation { step: String, source_step: String },
+
     #[error("apply-verify failed: {0}")]
     ApplyVerify(#[from] ApplyVerifyError),
 
     #[error("shell command timed out after {0:?}")]
     ShellTimeout(Duration),
+
+    #[error("no mock response for query step '{step}'")]
+    NoMock { step: String },
+
+    #[error("sub-workflow '{workflow}' failed: {message}")]
+    SubWorkflow { workflow: String, message: String },
+
+    #[error("step '{step}' is misconfigured: {message}")]
+    Misconfigured { step: String, message: String },
 }
 
+/// Callback receiving parallel progress along with the step name
+pub type StepProgressCallback = Arc<dyn Fn(&str, ParallelProgress) + Send + Sync>;
+
 /// Context for step execution
 pub struct ExecutionContext {
     pub config: Arc<LlmuxConfig>,
     pub template_engine: TemplateEngine,
     pub role_executor: RoleExecutor,
+    pub progress: Option<StepProgressCallback>,
+    /// Canned step results used by workflow tests instead of backends and commands
+    pub mocks: Option<Arc<HashMap<String, StepMock>>>,
+    /// Directory searched first for sub-workflows
+    pub workflows_dir: Option<PathBuf>,
 }
 
 impl ExecutionContext {
@@ -63,8 +96,77 @@ impl ExecutionContext {
             role_executor: RoleExecutor::new(config.clone()),
             config,
             template_engine: TemplateEngine::new(),
+            progress: None,
+            mocks: None,
+            workflows_dir: None,
         }
     }
+
+    /// Look for sub-workflows in `dir` before the usual locations
+    pub fn with_workflows_dir(mut self, dir: PathBuf) -> Self {
+        self.workflows_dir = Some(dir);
+        self
+    }
+
+    /// Mock steps for a workflow test; unmocked query steps fail instead of calling backends
+    ///
+    /// Query mocks stand in for backends, so the rest of the step runs as usual.
+    pub fn with_mocks(mut self, mocks: Arc<HashMap<String, StepMock>>) -> Self {
+        self.mocks = Some(mocks);
+        self
+    }
+
+    /// Mock for a step, when running a workflow test
+    fn mock(&self, step: &str) -> Option<&StepMock> {
+        self.mocks.as_ref()?.get(step)
+    }
+
+    /// Record backend call statistics for adaptive ordering
+    pub fn with_stats(mut self, stats: Arc<StatsStore>) -> Self {
+        self.role_executor = self.role_executor.with_stats(stats);
+        self
+    }
+
+    /// Report parallel progress for query steps
+    pub fn with_progress(mut self, progress: StepProgressCallback) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Role executor for a step's backends, answering with the step's mock in a workflow test
+    ///
+    /// A synthesizer answers with the mock's `output`, never a per-backend one.
+    fn mocked_role_executor(&self, step: &str, synthesizer: bool) -> RoleExecutor {
+        let Some(mock) = self.mock(step) else {
+            return self.role_executor.clone();
+        };
+        let backends = if synthesizer {
+            StepMock {
+                outputs: HashMap::new(),
+                ..mock.clone()
+            }
+            .backends()
+        } else {
+            mock.backends()
+        };
+        self.role_executor.clone().with_backend_factory(backends)
+    }
+
+    /// Role executor that reports progress under a step's name
+    fn role_executor_for(&self, step: &str) -> RoleExecutor {
+        let executor = self.mocked_role_executor(step, false);
+        let Some(progress) = self.progress.clone() else {
+            return executor;
+        };
+        let step = step.to_string();
+        executor.with_progress(Arc::new(move |p| progress(&step, p)))
+    }
+
+    /// Serve cacheable query steps from a response cache
+    pub fn with_cache(mut self, cache: Arc<ResponseCache>) -> Self {
+        self.role_executor = self.role_executor.with_cache(cache);
+        self
+    }
 }
 
 /// Execute a single step
@@ -96,26 +198,39 @@ pub async fn execute_step(
                 duration_ms: start.elapsed().as_millis() as u64,
                 backend: None,
                 backends: Vec::new(),
+                ..Default::default()
             });
         }
     }
 
-    let result = match step.step_type {
-        StepType::Shell => execute_shell_step(step, ctx, template_ctx, working_dir).await,
-        StepType::Query => execute_query_step(step, ctx, template_ctx, team).await,
-        StepType::Apply => execute_apply_step(step, ctx, template_ctx, working_dir).await,
-        StepType::Store => execute_store_step(step, ctx, template_ctx).await,
-        StepType::Input => {
-            // Input steps require user interaction
-            Ok(StepResult {
-                output: Some("input step not yet implemented".into()),
-                outputs: std::collections::HashMap::new(),
-                failed: false,
-                error: None,
-                duration_ms: start.elapsed().as_millis() as u64,
-                backend: None,
-                backends: Vec::new(),
-            })
+    let result = if !step.matrix.is_empty() {
+        execute_matrix_step(step, ctx, template_ctx, team, working_dir).await
+    } else {
+        match step.step_type {
+            StepType::Shell => execute_shell_step(step, ctx, template_ctx, working_dir).await,
+            StepType::Query => execute_query_step(step, ctx, template_ctx, team, working_dir).await,
+            StepType::Apply => execute_apply_step(step, ctx, template_ctx, working_dir).await,
+            StepType::Store => execute_store_step(step, ctx, template_ctx).await,
+            StepType::Workflow => {
+                execute_workflow_step(step, ctx, template_ctx, team, working_dir).await
+            }
+            StepType::Loop => execute_loop_step(step, ctx, template_ctx, team, working_dir).await,
+            StepType::MapReduce => {
+                execute_map_reduce_step(step, ctx, template_ctx, team, working_dir).await
+            }
+            StepType::Input => {
+                // Input steps require user interaction
+                Ok(StepResult {
+                    output: Some("input step not yet implemented".into()),
+                    outputs: std::collections::HashMap::new(),
+                    failed: false,
+                    error: None,
+                    duration_ms: start.elapsed().as_millis() as u64,
+                    backend: None,
+                    backends: Vec::new(),
+                    ..Default::default()
+                })
+            }
         }
     };
 
@@ -170,6 +285,18 @@ async fn execute_shell_step(
     // All interpolated values are automatically quoted to prevent injection.
     let rendered_command = ctx.template_engine.render_shell(command, template_ctx)?;
 
+    // Workflow tests replace the command's output
+    if let Some(mock) = ctx.mock(&step.name) {
+        return shell_result(
+            step,
+            mock.output.as_deref().unwrap_or_default(),
+            mock.error.as_deref().unwrap_or_default(),
+            Some(mock.exit_code),
+            mock.exit_code == 0,
+            0,
+        );
+    }
+
     // Execute command
     let mut child = Command::new("sh")
         .arg("-c")
@@ -222,6 +349,7 @@ async fn execute_shell_step(
                         duration_ms,
                         backend: Some("shell".into()),
                         backends: vec!["shell".into()],
+                        ..Default::default()
                     });
                 }
                 return Err(StepExecutionError::ShellTimeout(dur));
@@ -235,9 +363,26 @@ async fn execute_shell_step(
 
     let (stdout, stderr, status) = output_result?;
 
-    let duration_ms = start.elapsed().as_millis() as u64;
+    shell_result(
+        step,
+        &stdout,
+        &stderr,
+        exit_status_code(&status),
+        status.success(),
+        start.elapsed().as_millis() as u64,
+    )
+}
 
-    if status.success() {
+/// Step result for a finished shell command
+fn shell_result(
+    step: &StepConfig,
+    stdout: &str,
+    stderr: &str,
+    exit_code: Option<i32>,
+    success: bool,
+    duration_ms: u64,
+) -> Result<StepResult, StepExecutionError> {
+    if success {
         Ok(StepResult {
             output: Some(stdout.trim().to_string()),
             outputs: std::collections::HashMap::new(),
@@ -246,10 +391,11 @@ async fn execute_shell_step(
             duration_ms,
             backend: Some("shell".into()),
             backends: vec!["shell".into()],
+            ..Default::default()
         })
     } else {
         let error_msg = if stderr.is_empty() {
-            format!("command exited with code {:?}", exit_status_code(&status))
+            format!("command exited with code {:?}", exit_code)
         } else {
             stderr.trim().to_string()
         };
@@ -263,11 +409,12 @@ async fn execute_shell_step(
                 duration_ms,
                 backend: Some("shell".into()),
                 backends: vec!["shell".into()],
+                ..Default::default()
             })
         } else {
             Err(StepExecutionError::ShellFailed {
                 message: error_msg,
-                exit_code: exit_status_code(&status),
+                exit_code,
             })
         }
     }
@@ -279,14 +426,9 @@ async fn execute_query_step(
     ctx: &ExecutionContext,
     template_ctx: &TemplateContext,
     team: Option<&str>,
+    working_dir: &std::path::Path,
 ) -> Result<StepResult, StepExecutionError> {
-    let role_name = step
-        .role
-        .as_ref()
-        .ok_or_else(|| StepExecutionError::MissingField {
-            step: step.name.clone(),
-            field: "role".into(),
-        })?;
+    let role_name = step.role.as_ref();
 
     let prompt = step
         .prompt
@@ -301,24 +443,193 @@ async fn execute_query_step(
 
     // If output_schema is present, append JSON formatting instructions
     if let Some(ref schema) = step.output_schema {
-        let schema_json = serde_json::to_string_pretty(schema).unwrap_or_else(|_| "{}".to_string());
-
-        rendered_prompt.push_str(&format!(
-            "\n\nIMPORTANT: You MUST respond with valid JSON matching this schema:\n```json\n{}\n```\n\nDo not include any text before or after the JSON object.",
-            schema_json
-        ));
+        rendered_prompt.push_str(&schema_instructions(schema));
     }
 
-    // Resolve role to backends
-    let resolved_role = resolve_role(role_name, team, &ctx.config)?;
+    // Resolve the step's backend or role to the backends capable of running it
+    let mut resolved_role = match (&step.backend, role_name) {
+        (Some(backend), _) => {
+            let name = format!("backend:{}", backend);
+            match ctx.config.backends.get(backend) {
+                None => {
+                    return Err(crate::role::RoleError::BackendNotFound {
+                        backend: backend.clone(),
+                    }
+                    .into());
+                }
+                // Held to the step's requirements, like a role's backends
+                Some(config) if !config.satisfies(&step.requires) => {
+                    return Err(crate::role::RoleError::NoCapableBackend {
+                        role: name,
+                        requires: step.requires.to_string(),
+                    }
+                    .into());
+                }
+                Some(_) => {}
+            }
+            ResolvedRole {
+                name,
+                backends: vec![backend.clone()],
+                execution: RoleExecution::First,
+                ..Default::default()
+            }
+        }
+        (None, Some(role)) => {
+            resolve_role_for(role, team, &ctx.config, &step.requires, &step.prefers)?
+        }
+        (None, None) => {
+            return Err(StepExecutionError::MissingField {
+                step: step.name.clone(),
+                field: "role".into(),
+            });
+        }
+    };
+    // Only API backends can call tools; CLI backends would just fail the step
+    if !step.tools.is_empty() {
+        resolved_role.backends.retain(|name| {
+            ctx.config
+                .backends
+                .get(name)
+                .is_some_and(|backend| !backend.is_cli())
+        });
+        if resolved_role.backends.is_empty() {
+            return Err(StepExecutionError::Misconfigured {
+                step: step.name.clone(),
+                message: format!(
+                    "tools need an HTTP or Claude API backend, but '{}' has none",
+                    resolved_role.name
+                ),
+            });
+        }
+    }
+    if step.parallel || step.min_success.is_some() {
+        if !matches!(
+            resolved_role.execution,
+            RoleExecution::First | RoleExecution::Fallback | RoleExecution::Parallel
+        ) {
+            return Err(StepExecutionError::Misconfigured {
+                step: step.name.clone(),
+                message: format!(
+                    "'parallel' and 'min_success' can't override '{}', which runs in {} mode",
+                    resolved_role.name, resolved_role.execution
+                ),
+            });
+        }
+        if step.parallel {
+            resolved_role.execution = RoleExecution::Parallel;
+        }
+    }
+    if let Some(min_success) = step.min_success {
+        if min_success as usize > resolved_role.backends.len() {
+            return Err(StepExecutionError::Misconfigured {
+                step: step.name.clone(),
+                message: format!(
+                    "'min_success' is {}, but only {} backend(s) of '{}' can run it",
+                    min_success,
+                    resolved_role.backends.len(),
+                    resolved_role.name
+                ),
+            });
+        }
+        resolved_role.min_success = min_success;
+    }
+    if step.synthesize.is_some() && resolved_role.execution != RoleExecution::Parallel {
+        return Err(StepExecutionError::Misconfigured {
+            step: step.name.clone(),
+            message: format!(
+                "'synthesize' needs a parallel role, but '{}' runs in {} mode",
+                resolved_role.name, resolved_role.execution
+            ),
+        });
+    }
 
     // Create backend request
-    let request = BackendRequest::new(rendered_prompt);
+    let mut request = BackendRequest::new(rendered_prompt.clone());
+    if let Some(ref system_prompt) = step.system_prompt {
+        request =
+            request.with_system_prompt(ctx.template_engine.render(system_prompt, template_ctx)?);
+    }
+    if let Some(ttl) = step.cache_ttl() {
+        request = request.with_cache(ttl);
+    }
+
+    // Let the model call the step's tools, scoped to the working directory
+    if !step.tools.is_empty() {
+        let toolbox = Toolbox::from_config(&step.tools, working_dir, step.max_tool_calls);
+        request = request.with_tools(Arc::new(toolbox));
+    }
+
+    // Replay an earlier step's conversation, this prompt being the next user turn
+    if let Some(ref from) = step.continue_from {
+        let messages = template_ctx
+            .steps
+            .get(from)
+            .map(|r| r.messages.clone())
+            .filter(|messages| !messages.is_empty())
+            .ok_or_else(|| StepExecutionError::NoConversation {
+                step: step.name.clone(),
+                source_step: from.clone(),
+            })?;
+        request = request.with_messages(messages);
+    }
+
+    // Workflow tests never reach real backends
+    if let Some(ref mocks) = ctx.mocks
+        && !mocks.contains_key(&step.name)
+    {
+        return Err(StepExecutionError::NoMock {
+            step: step.name.clone(),
+        });
+    }
 
     // Execute
-    let result = ctx.role_executor.execute(&resolved_role, &request).await?;
+    let role_executor = ctx.role_executor_for(&step.name);
+    let result = if resolved_role.execution == RoleExecution::Vote {
+        let key = VoteKey {
+            extract: step.extract.clone(),
+            structured: step.output_schema.is_some(),
+        };
+        role_executor
+            .execute_vote(&resolved_role, &request, &key)
+            .await?
+    } else {
+        role_executor.execute(&resolved_role, &request).await?
+    };
     let mut step_result = result.to_step_result();
 
+    // Merge parallel outputs through the synthesizer role, if any
+    let synthesizer = step.synthesize.as_ref().or(resolved_role.judge.as_ref());
+    if let Some(synthesizer) = synthesizer
+        && result.execution_mode == RoleExecution::Parallel
+    {
+        let merged = synthesize(
+            step,
+            ctx,
+            template_ctx,
+            team,
+            synthesizer,
+            &rendered_prompt,
+            &result,
+        )
+        .await?;
+        step_result.output = merged.output;
+        step_result.duration_ms += merged.duration.as_millis() as u64;
+        step_result.cache_hits.extend(merged.cache_hits);
+    }
+
+    // Keep the conversation so later steps can continue it. The system turn is
+    // left out: a continuing step applies its own system prompt.
+    if let Some(ref output) = step_result.output {
+        step_result.messages = request
+            .conversation()
+            .into_iter()
+            .filter(|m| m.role != MessageRole::System)
+            .collect();
+        step_result
+            .messages
+            .push(Message::assistant(output.clone()));
+    }
+
     // Validate against schema if present
     if let Some(ref schema) = step.output_schema {
         if let Some(ref output) = step_result.output {
@@ -332,6 +643,84 @@ async fn execute_query_step(
     Ok(step_result)
 }
 
+/// Default merge prompt for synthesizing parallel outputs
+const DEFAULT_SYNTHESIZE_PROMPT: &str = "\
+Several assistants answered the same request independently.
+
+Request:
+{{ prompt }}
+{% for backend, output in outputs | items %}
+=== {{ backend }} ===
+{{ output }}
+{% endfor %}
+Merge these into a single best answer. Keep what they agree on, resolve \
+disagreements in favour of the best-supported claim, and drop repetition. \
+Respond with the merged answer only.";
+
+/// Send all per-backend outputs to a synthesizer role and return its merged answer
+async fn synthesize(
+    step: &StepConfig,
+    ctx: &ExecutionContext,
+    template_ctx: &TemplateContext,
+    team: Option<&str>,
+    synthesizer: &str,
+    prompt: &str,
+    result: &RoleResult,
+) -> Result<RoleResult, StepExecutionError> {
+    let outputs: BTreeMap<&str, &str> = result
+        .outputs
+        .iter()
+        .map(|(backend, output)| (backend.as_str(), output.as_str()))
+        .collect();
+
+    let mut merge_ctx = template_ctx.clone();
+    merge_ctx.set_local("prompt", Value::from(prompt));
+    merge_ctx.set_local("outputs", Value::from_serialize(&outputs));
+
+    let template = step
+        .synthesize_prompt
+        .as_deref()
+        .unwrap_or(DEFAULT_SYNTHESIZE_PROMPT);
+    let mut merge_prompt = ctx.template_engine.render(template, &merge_ctx)?;
+    if let Some(ref schema) = step.output_schema {
+        merge_prompt.push_str(&schema_instructions(schema));
+    }
+
+    let resolved = resolve_role_for(
+        synthesizer,
+        team,
+        &ctx.config,
+        &step.requires,
+        &step.prefers,
+    )?;
+    let mut request = BackendRequest::new(merge_prompt);
+    if let Some(ttl) = step.cache_ttl() {
+        request = request.with_cache(ttl);
+    }
+
+    tracing::debug!(
+        step = %step.name,
+        synthesizer = %synthesizer,
+        inputs = outputs.len(),
+        "Synthesizing parallel outputs"
+    );
+
+    Ok(ctx
+        .mocked_role_executor(&step.name, true)
+        .execute(&resolved, &request)
+        .await?)
+}
+
+/// Prompt suffix asking for JSON matching an output schema
+fn schema_instructions(schema: &crate::config::OutputSchema) -> String {
+    let schema_json = serde_json::to_string_pretty(schema).unwrap_or_else(|_| "{}".to_string());
+
+    format!(
+        "\n\nIMPORTANT: You MUST respond with valid JSON matching this schema:\n```json\n{}\n```\n\nDo not include any text before or after the JSON object.",
+        schema_json
+    )
+}
+
 /// Strip markdown code fences from output if present
 fn strip_markdown_fences(output: &str) -> &str {
     let trimmed = output.trim();
@@ -368,7 +757,10 @@ fn strip_markdown_fences(output: &str) -> &str {
 }
 
 /// Validate JSON output against a schema
-fn validate_json_schema(output: &str, schema: &crate::config::OutputSchema) -> Result<(), String> {
+pub fn validate_json_schema(
+    output: &str,
+    schema: &crate::config::OutputSchema,
+) -> Result<(), String> {
     // Strip markdown code fences if present
     let clean_output = strip_markdown_fences(output);
 
@@ -500,6 +892,7 @@ async fn execute_apply_step(
             duration_ms: start.elapsed().as_millis() as u64,
             backend: Some("apply".into()),
             backends: vec!["apply".into()],
+            ..Default::default()
         })
     } else {
         let result = apply_only(source_output, working_dir).await?;
@@ -515,6 +908,7 @@ async fn execute_apply_step(
             duration_ms: start.elapsed().as_millis() as u64,
             backend: Some("apply".into()),
             backends: vec!["apply".into()],
+            ..Default::default()
         })
     }
 }
@@ -572,6 +966,350 @@ async fn execute_store_step(
         duration_ms: start.elapsed().as_millis() as u64,
         backend: Some("store".into()),
         backends: vec!["store".into()],
+        ..Default::default()
+    })
+}
+
+/// Execute a workflow step: run another workflow with templated args
+async fn execute_workflow_step(
+    step: &StepConfig,
+    ctx: &ExecutionContext,
+    template_ctx: &TemplateContext,
+    team: Option<&str>,
+    working_dir: &std::path::Path,
+) -> Result<StepResult, StepExecutionError> {
+    let start = Instant::now();
+
+    let name = step
+        .workflow
+        .as_ref()
+        .ok_or_else(|| StepExecutionError::MissingField {
+            step: step.name.clone(),
+            field: "workflow".into(),
+        })?;
+    let sub_workflow_error = |message: String| StepExecutionError::SubWorkflow {
+        workflow: name.clone(),
+        message,
+    };
+
+    let workflow = load_sub_workflow(name, ctx.workflows_dir.as_deref(), Some(working_dir))
+        .map_err(|e| sub_workflow_error(format!("{:#}", e)))?;
+
+    let mut args = HashMap::new();
+    for (arg, value) in &step.with {
+        args.insert(
+            arg.clone(),
+            ctx.template_engine.render(value, template_ctx)?,
+        );
+    }
+
+    // Boxed because sub-workflows can contain workflow steps themselves
+    let result = Box::pin(WorkflowRunner::new(ctx.config.clone()).run_in_context(
+        ctx,
+        workflow,
+        args,
+        working_dir,
+        team.map(String::from),
+    ))
+    .await
+    .map_err(|e| sub_workflow_error(e.to_string()))?;
+
+    Ok(StepResult {
+        output: result.output,
+        failed: !result.success,
+        error: result
+            .error
+            .map(|e| format!("sub-workflow '{}' failed: {}", name, e)),
+        duration_ms: start.elapsed().as_millis() as u64,
+        steps: result.steps,
+        ..Default::default()
+    })
+}
+
+/// Chunks or matrix combinations run at once unless a step sets `concurrency`
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Execute a matrix step: run the step once per combination of its matrix values
+async fn execute_matrix_step(
+    step: &StepConfig,
+    ctx: &ExecutionContext,
+    template_ctx: &TemplateContext,
+    team: Option<&str>,
+    working_dir: &std::path::Path,
+) -> Result<StepResult, StepExecutionError> {
+    let start = Instant::now();
+    let combinations = step.matrix_combinations();
+    let semaphore = Semaphore::new(step.concurrency.unwrap_or(DEFAULT_CONCURRENCY).max(1));
+
+    let results = join_all(
+        combinations
+            .iter()
+            .map(|(_, values)| {
+                let semaphore = &semaphore;
+                async move {
+                    let _permit = semaphore
+                        .acquire()
+                        .await
+                        .expect("semaphore is never closed");
+                    let combination_step = StepConfig {
+                        role: values.get("role").cloned().or_else(|| step.role.clone()),
+                        backend: values
+                            .get("backend")
+                            .cloned()
+                            .or_else(|| step.backend.clone()),
+                        matrix: BTreeMap::new(),
+                        condition: None,
+                        ..step.clone()
+                    };
+                    let mut combination_ctx = template_ctx.clone();
+                    combination_ctx.set_local("matrix", Value::from_serialize(values));
+
+                    // Boxed because execute_step is what called us
+                    match Box::pin(execute_step(
+                        &combination_step,
+                        ctx,
+                        &combination_ctx,
+                        team,
+                        working_dir,
+                    ))
+                    .await
+                    {
+                        Err(e) if step.continue_on_error => {
+                            Ok(StepResult::failure(e.to_string(), 0))
+                        }
+                        result => result,
+                    }
+                }
+            })
+            .collect(),
+    )
+    .await;
+
+    let mut outputs = Vec::new();
+    let mut failed = Vec::new();
+    let mut matrix = HashMap::new();
+    for ((label, _), result) in combinations.into_iter().zip(results) {
+        let result = result?;
+        if let Some(ref output) = result.output {
+            outputs.push(format!("=== {} ===\n{}", label, output));
+        }
+        if result.failed {
+            failed.push(label.clone());
+        }
+        matrix.insert(label, result);
+    }
+
+    Ok(StepResult {
+        output: Some(outputs.join("\n\n")),
+        failed: !failed.is_empty(),
+        error: (!failed.is_empty()).then(|| format!("failed combinations: {}", failed.join(", "))),
+        duration_ms: start.elapsed().as_millis() as u64,
+        matrix,
+        ..Default::default()
+    })
+}
+
+/// Default prompt combining a map_reduce step's mapped outputs
+const DEFAULT_REDUCE_PROMPT: &str = "\
+The answers below each cover one part of a larger input, in order.
+{% for output in outputs %}
+=== Part {{ loop.index }} of {{ outputs | length }} ===
+{{ output }}
+{% endfor %}
+Combine them into a single answer covering the whole input. Merge overlapping \
+points and drop repetition. Respond with the combined answer only.";
+
+/// Execute a map_reduce step: query the role on each chunk of the input, then
+/// once more to combine the answers
+async fn execute_map_reduce_step(
+    step: &StepConfig,
+    ctx: &ExecutionContext,
+    template_ctx: &TemplateContext,
+    team: Option<&str>,
+    working_dir: &std::path::Path,
+) -> Result<StepResult, StepExecutionError> {
+    let start = Instant::now();
+
+    let input = step
+        .input
+        .as_ref()
+        .ok_or_else(|| StepExecutionError::MissingField {
+            step: step.name.clone(),
+            field: "input".into(),
+        })?;
+    let input = ctx.template_engine.render(input, template_ctx)?;
+
+    let chunks = chunk::split(&input, step.chunk_by, step.chunk_size);
+    if chunks.is_empty() {
+        return Ok(StepResult {
+            error: Some("skipped: input is empty".into()),
+            duration_ms: start.elapsed().as_millis() as u64,
+            ..Default::default()
+        });
+    }
+    tracing::info!(step = %step.name, chunks = chunks.len(), "Mapping chunks");
+
+    // Map and reduce both run as query steps through the step's role
+    let map_step = StepConfig {
+        step_type: StepType::Query,
+        output_schema: None,
+        continue_from: None,
+        ..step.clone()
+    };
+    let semaphore = Semaphore::new(step.concurrency.unwrap_or(DEFAULT_CONCURRENCY).max(1));
+    let mapped = join_all(
+        chunks
+            .iter()
+            .map(|chunk| {
+                let (map_step, semaphore) = (&map_step, &semaphore);
+                async move {
+                    let _permit = semaphore
+                        .acquire()
+                        .await
+                        .expect("semaphore is never closed");
+                    let mut chunk_ctx = template_ctx.clone();
+                    chunk_ctx.set_local("chunk", Value::from_serialize(chunk));
+                    execute_query_step(map_step, ctx, &chunk_ctx, team, working_dir).await
+                }
+            })
+            .collect(),
+    )
+    .await;
+
+    let mut items = Vec::new();
+    for (chunk, result) in chunks.iter().zip(mapped) {
+        let result = result?;
+        if result.failed {
+            return Ok(StepResult {
+                failed: true,
+                error: Some(format!(
+                    "chunk {}/{} failed: {}",
+                    chunk.index,
+                    chunk.total,
+                    result.error.as_deref().unwrap_or("no answer")
+                )),
+                duration_ms: start.elapsed().as_millis() as u64,
+                items,
+                ..Default::default()
+            });
+        }
+        items.push(result);
+    }
+
+    let outputs: Vec<&str> = items
+        .iter()
+        .map(|item| item.output.as_deref().unwrap_or_default())
+        .collect();
+    let mut reduce_ctx = template_ctx.clone();
+    reduce_ctx.set_local("outputs", Value::from_serialize(&outputs));
+    let reduce_step = StepConfig {
+        step_type: StepType::Query,
+        prompt: Some(
+            step.reduce_prompt
+                .clone()
+                .unwrap_or_else(|| DEFAULT_REDUCE_PROMPT.to_string()),
+        ),
+        continue_from: None,
+        ..step.clone()
+    };
+
+    let mut result = execute_query_step(&reduce_step, ctx, &reduce_ctx, team, working_dir).await?;
+    result.items = items;
+    result.duration_ms = start.elapsed().as_millis() as u64;
+    Ok(result)
+}
+
+/// Execute a loop step: run its steps in order until `until` holds or the limit is hit
+async fn execute_loop_step(
+    step: &StepConfig,
+    ctx: &ExecutionContext,
+    template_ctx: &TemplateContext,
+    team: Option<&str>,
+    working_dir: &std::path::Path,
+) -> Result<StepResult, StepExecutionError> {
+    let start = Instant::now();
+
+    let until = step
+        .until
+        .as_ref()
+        .ok_or_else(|| StepExecutionError::MissingField {
+            step: step.name.clone(),
+            field: "until".into(),
+        })?;
+
+    let mut previous = HashMap::new();
+    let mut output = None;
+    let mut converged = false;
+    for index in 1..=step.max_iterations {
+        let mut iteration_ctx = template_ctx.clone();
+        iteration_ctx.set_loop(index, previous.clone());
+
+        let mut current = HashMap::new();
+        for inner in &step.steps {
+            // Boxed because loops can contain loops
+            let result =
+                match Box::pin(execute_step(inner, ctx, &iteration_ctx, team, working_dir)).await {
+                    Ok(result) => result,
+                    Err(e) if inner.continue_on_error => StepResult::failure(e.to_string(), 0),
+                    Err(e) => return Err(e),
+                };
+
+            if result.output.is_some() {
+                output = result.output.clone();
+            }
+            let failed = result.failed && !inner.continue_on_error;
+            let error = result.error.clone();
+            iteration_ctx.add_step(inner.name.clone(), result.clone());
+            current.insert(inner.name.clone(), result);
+
+            if failed {
+                return Ok(StepResult {
+                    output,
+                    failed: true,
+                    error: Some(format!(
+                        "iteration {}: step '{}' failed: {}",
+                        index,
+                        inner.name,
+                        error.unwrap_or_default()
+                    )),
+                    duration_ms: start.elapsed().as_millis() as u64,
+                    steps: current,
+                    ..Default::default()
+                });
+            }
+        }
+
+        converged = evaluate_condition(until, &iteration_ctx)?;
+        previous = current;
+        if converged {
+            tracing::info!(step = %step.name, iterations = index, "Loop condition met");
+            break;
+        }
+    }
+
+    // Running out of iterations means `until` never held; that only fails
+    // loops that opted in with `fail_on_max`
+    if !converged {
+        tracing::warn!(
+            step = %step.name,
+            max_iterations = step.max_iterations,
+            "Loop stopped at its iteration limit"
+        );
+    }
+    let error = (!converged && step.fail_on_max).then(|| {
+        format!(
+            "'until' still false after {} iteration(s)",
+            step.max_iterations
+        )
+    });
+
+    Ok(StepResult {
+        output,
+        failed: error.is_some(),
+        error,
+        duration_ms: start.elapsed().as_millis() as u64,
+        steps: previous,
+        ..Default::default()
     })
 }
 
@@ -755,6 +1493,7 @@ mod tests {
                 backends: vec!["echo".into()],
                 execution: RoleExecution::First,
                 min_success: 1,
+                ..Default::default()
             },
         );
 
@@ -970,4 +1709,254 @@ mod tests {
         assert!(!result.failed);
         assert!(result.output.is_some());
     }
+
+    #[tokio::test]
+    async fn test_query_step_continues_conversation() {
+        let config = Arc::new(create_test_config());
+        let ctx = ExecutionContext::new(config);
+        let mut template_ctx = TemplateContext::new();
+        let dir = TempDir::new().unwrap();
+
+        let first = StepConfig {
+            name: "analyze".into(),
+            step_type: StepType::Query,
+            role: Some("test".into()),
+            prompt: Some("What broke?".into()),
+            system_prompt: Some("Be brief".into()),
+            ..Default::default()
+        };
+        let result = execute_step(&first, &ctx, &template_ctx, None, dir.path())
+            .await
+            .unwrap();
+        // The system turn isn't carried into the saved conversation
+        assert_eq!(result.messages.len(), 2);
+        assert!(
+            result
+                .messages
+                .iter()
+                .all(|m| m.role != MessageRole::System)
+        );
+        template_ctx.add_step("analyze", result);
+
+        let followup = StepConfig {
+            name: "fix".into(),
+            prompt: Some("Fix it".into()),
+            system_prompt: Some("Write code".into()),
+            continue_from: Some("analyze".into()),
+            ..first.clone()
+        };
+        let result = execute_step(&followup, &ctx, &template_ctx, None, dir.path())
+            .await
+            .unwrap();
+
+        // The echo backend repeats the flattened transcript, with only the
+        // continuing step's system prompt
+        assert_eq!(
+            result.output.as_deref(),
+            Some(
+                "System: Write code\n\nUser: What broke?\n\nAssistant: System: Be brief\n\nUser: What broke?\n\nUser: Fix it"
+            )
+        );
+        assert_eq!(result.messages.len(), 4);
+
+        let missing = StepConfig {
+            continue_from: Some("nothing".into()),
+            ..followup
+        };
+        let err = execute_step(&missing, &ctx, &template_ctx, None, dir.path())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, StepExecutionError::NoConversation { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_query_step_synthesizes_parallel_outputs() {
+        let mut config = create_test_config();
+        config.backends.insert(
+            "upper".into(),
+            BackendConfig {
+                command: "sh".into(),
+                args: vec!["-c".into(), "echo \"$1\" | tr a-z A-Z".into(), "sh".into()],
+                ..Default::default()
+            },
+        );
+        config.roles.insert(
+            "panel".into(),
+            RoleConfig {
+                backends: vec!["echo".into(), "upper".into()],
+                execution: RoleExecution::Parallel,
+                judge: Some("test".into()),
+                ..Default::default()
+            },
+        );
+        let ctx = ExecutionContext::new(Arc::new(config));
+        let dir = TempDir::new().unwrap();
+
+        let step = StepConfig {
+            name: "merge".into(),
+            step_type: StepType::Query,
+            role: Some("panel".into()),
+            prompt: Some("hi".into()),
+            synthesize_prompt: Some(
+                "merged{% for b, o in outputs | items %} {{ b }}={{ o | trim }}{% endfor %}".into(),
+            ),
+            ..Default::default()
+        };
+
+        let result = execute_step(&step, &ctx, &TemplateContext::new(), None, dir.path())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            result.output.as_deref().map(str::trim),
+            Some("merged echo=hi upper=HI")
+        );
+        assert_eq!(result.outputs.len(), 2);
+        assert_eq!(result.outputs["upper"].trim(), "HI");
+    }
+
+    #[tokio::test]
+    async fn test_query_step_overrides_need_compatible_role() {
+        let mut config = create_test_config();
+        config.roles.insert(
+            "majority".into(),
+            RoleConfig {
+                backends: vec!["echo".into()],
+                execution: RoleExecution::Vote,
+                ..Default::default()
+            },
+        );
+        let ctx = ExecutionContext::new(Arc::new(config));
+        let dir = TempDir::new().unwrap();
+
+        let step = StepConfig {
+            name: "ask".into(),
+            step_type: StepType::Query,
+            role: Some("majority".into()),
+            prompt: Some("hi".into()),
+            parallel: true,
+            ..Default::default()
+        };
+        let err = execute_step(&step, &ctx, &TemplateContext::new(), None, dir.path())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("runs in vote mode"), "{}", err);
+
+        // The test role has a single backend, so two can never succeed
+        let step = StepConfig {
+            role: Some("test".into()),
+            min_success: Some(2),
+            ..step
+        };
+        let err = execute_step(&step, &ctx, &TemplateContext::new(), None, dir.path())
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("only 1 backend(s) of 'test'"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backend_step_checks_requirements() {
+        let ctx = ExecutionContext::new(Arc::new(create_test_config()));
+        let dir = TempDir::new().unwrap();
+
+        let step = StepConfig {
+            name: "ask".into(),
+            step_type: StepType::Query,
+            backend: Some("echo".into()),
+            prompt: Some("hi".into()),
+            requires: crate::config::BackendRequirements {
+                vision: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let err = execute_step(&step, &ctx, &TemplateContext::new(), None, dir.path())
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("required capabilities (vision)"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_map_reduce_step() {
+        let ctx = ExecutionContext::new(Arc::new(create_test_config()));
+        let mut template_ctx = TemplateContext::new();
+        template_ctx.args.insert(
+            "diff".into(),
+            "diff --git a/a.rs b/a.rs\n+1\ndiff --git a/b.rs b/b.rs\n+2".into(),
+        );
+        let dir = TempDir::new().unwrap();
+
+        let step = StepConfig {
+            name: "review".into(),
+            step_type: StepType::MapReduce,
+            role: Some("test".into()),
+            input: Some("{{ args.diff }}".into()),
+            chunk_by: crate::config::ChunkBy::Files,
+            prompt: Some(
+                "{{ chunk.index }}/{{ chunk.total }} {{ chunk.files | join(',') }}".into(),
+            ),
+            reduce_prompt: Some(
+                "{% for output in outputs %}[{{ output | trim }}]{% endfor %}".into(),
+            ),
+            ..Default::default()
+        };
+
+        let result = execute_step(&step, &ctx, &template_ctx, None, dir.path())
+            .await
+            .unwrap();
+
+        assert!(!result.failed, "{:?}", result.error);
+        assert_eq!(
+            result.output.as_deref().map(str::trim),
+            Some("[1/2 a.rs][2/2 b.rs]")
+        );
+        assert_eq!(result.items.len(), 2);
+
+        template_ctx.args.insert("diff".into(), String::new());
+        let skipped = execute_step(&step, &ctx, &template_ctx, None, dir.path())
+            .await
+            .unwrap();
+        assert_eq!(skipped.error.as_deref(), Some("skipped: input is empty"));
+    }
+
+    #[tokio::test]
+    async fn test_matrix_step() {
+        let ctx = ExecutionContext::new(Arc::new(create_test_config()));
+        let dir = TempDir::new().unwrap();
+
+        let step: StepConfig = toml::from_str(
+            r#"
+name = "review"
+type = "query"
+prompt = "{{ matrix.focus }} via {{ matrix.backend }}"
+matrix = { backend = ["echo"], focus = ["security", "perf"] }
+"#,
+        )
+        .unwrap();
+
+        let result = execute_step(&step, &ctx, &TemplateContext::new(), None, dir.path())
+            .await
+            .unwrap();
+
+        assert!(!result.failed, "{:?}", result.error);
+        assert_eq!(result.matrix.len(), 2);
+        let perf = &result.matrix["echo/perf"];
+        assert_eq!(perf.output.as_deref().map(str::trim), Some("perf via echo"));
+        assert_eq!(perf.backend.as_deref(), Some("echo"));
+
+        let missing = StepConfig {
+            matrix: [("backend".to_string(), vec!["nope".to_string()])].into(),
+            ..step
+        };
+        let result = execute_step(&missing, &ctx, &TemplateContext::new(), None, dir.path()).await;
+        assert!(result.is_err());
+    }
 }
@@ -25,10 +25,20 @@
 //! }
 //! ```
 
+mod args;
+mod chunk;
 mod ecosystem_detector;
 mod executor;
 mod runner;
 mod state;
+mod testing;
 
+pub use args::usage;
 pub use ecosystem_detector::detect_ecosystem;
+pub use executor::{ExecutionContext, StepProgressCallback, execute_step, validate_json_schema};
 pub use runner::WorkflowRunner;
+#[allow(unused_imports)]
+pub use testing::{
+    StepMock, TEST_FILE_SUFFIX, TestOutcome, WorkflowTest, WorkflowTestFile, discover_tests,
+    run_test_file,
+};
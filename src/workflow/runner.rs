@@ -1,17 +1,27 @@
 //! Workflow runner - orchestrates step execution
 
+use super::args::check_args;
 use super::detect_ecosystem;
-use super::executor::{ExecutionContext, StepExecutionError, execute_step};
+use super::executor::{ExecutionContext, StepExecutionError, StepProgressCallback, execute_step};
 use super::state::{WorkflowResult, WorkflowState};
+use super::testing::StepMock;
 use crate::backend_executor::output_parser::extract_json;
-use crate::config::{LlmuxConfig, StepResult, WorkflowConfig};
+use crate::backend_executor::{ResponseCache, StatsStore};
+use crate::config::{
+    LlmuxConfig, StepConfig, StepResult, WorkflowConfig, check_sub_workflows, load_sub_workflow,
+};
 use crate::role::detect_team;
-use crate::template::evaluate_expression;
+use crate::template::{TemplateContext, evaluate_expression};
 use minijinja::value::Value;
+use serde::Serialize;
 use std::collections::HashMap;
+use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::task::Poll;
 use thiserror::Error;
+use tokio::sync::Semaphore;
 
 /// Errors during workflow execution
 #[derive(Debug, Error)]
@@ -30,17 +40,129 @@ pub enum WorkflowError {
 
     #[error("template error: {0}")]
     Template(#[from] crate::template::TemplateError),
+
+    #[error("invalid sub-workflows:\n  {}", .0.join("\n  "))]
+    SubWorkflows(Vec<String>),
+
+    #[error("invalid arguments:\n  {}", .0.join("\n  "))]
+    InvalidArgs(Vec<String>),
+
+    #[error("invalid workflow:\n  {}", .0.join("\n  "))]
+    Invalid(Vec<String>),
+}
+
+/// The failure on_failure and `finally` steps see as `failure`
+#[derive(Debug, Clone, Serialize)]
+struct Failure {
+    step: String,
+    error: String,
+}
+
+/// Error message of a failed step, without the step name
+fn failure_message(error: &WorkflowError) -> String {
+    match error {
+        WorkflowError::StepFailed { message, .. } => message.clone(),
+        other => other.to_string(),
+    }
 }
 
 /// Workflow runner
 pub struct WorkflowRunner {
     config: Arc<LlmuxConfig>,
+    use_cache: bool,
+    stats: Option<Arc<StatsStore>>,
+    progress: Option<StepProgressCallback>,
+    mocks: Option<Arc<HashMap<String, StepMock>>>,
+    workflows_dir: Option<PathBuf>,
 }
 
 impl WorkflowRunner {
     /// Create a new workflow runner
     pub fn new(config: Arc<LlmuxConfig>) -> Self {
-        Self { config }
+        Self {
+            config,
+            use_cache: true,
+            stats: None,
+            progress: None,
+            mocks: None,
+            workflows_dir: None,
+        }
+    }
+
+    /// Enable or disable the response cache (enabled by default)
+    pub fn with_cache(mut self, enabled: bool) -> Self {
+        self.use_cache = enabled;
+        self
+    }
+
+    /// Record backend call statistics (and order adaptive roles by them)
+    pub fn with_stats(mut self, stats: Arc<StatsStore>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Report progress as backends of parallel query steps finish
+    pub fn with_progress(mut self, progress: StepProgressCallback) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Replace backends and shell commands with canned results (for workflow tests)
+    pub fn with_mocks(mut self, mocks: Arc<HashMap<String, StepMock>>) -> Self {
+        self.mocks = Some(mocks);
+        self
+    }
+
+    /// Look for sub-workflows in `dir` before the usual locations
+    pub fn with_workflows_dir(mut self, dir: PathBuf) -> Self {
+        self.workflows_dir = Some(dir);
+        self
+    }
+
+    /// Whether any step of the workflow or the sub-workflows it calls caches responses
+    fn uses_cache(&self, workflow: &WorkflowConfig, working_dir: &Path) -> bool {
+        let mut seen = vec![workflow.name.clone()];
+        let mut pending = vec![workflow.clone()];
+
+        while let Some(workflow) = pending.pop() {
+            for step in workflow.all_steps() {
+                if step.cache_ttl().is_some() {
+                    return true;
+                }
+                let Some(child) = &step.workflow else {
+                    continue;
+                };
+                if seen.contains(child) {
+                    continue;
+                }
+                seen.push(child.clone());
+                if let Ok(sub) =
+                    load_sub_workflow(child, self.workflows_dir.as_deref(), Some(working_dir))
+                {
+                    pending.push(sub);
+                }
+            }
+        }
+        false
+    }
+
+    /// Open the response cache if any step the workflow runs uses it
+    fn open_cache(
+        &self,
+        workflow: &WorkflowConfig,
+        working_dir: &Path,
+    ) -> Option<Arc<ResponseCache>> {
+        if !self.use_cache || !self.uses_cache(workflow, working_dir) {
+            return None;
+        }
+
+        match ResponseCache::open_default() {
+            Ok(cache) => Some(Arc::new(cache)),
+            Err(e) => {
+                tracing::warn!(error = %e, "Response cache unavailable, continuing without it");
+                None
+            }
+        }
     }
 
     /// Create output directory for workflow run
@@ -94,21 +216,59 @@ impl WorkflowRunner {
         args: HashMap<String, String>,
         working_dir: &Path,
         team_override: Option<&str>,
+    ) -> Result<WorkflowResult, WorkflowError> {
+        // Sub-workflows must exist and must not call each other in a cycle
+        check_sub_workflows(&workflow, self.workflows_dir.as_deref(), Some(working_dir))
+            .map_err(WorkflowError::SubWorkflows)?;
+
+        // Detect team
+        let team = detect_team(working_dir, &self.config.teams, team_override);
+
+        // Create execution context
+        let mut ctx = ExecutionContext::new(self.config.clone());
+        if let Some(cache) = self.open_cache(&workflow, working_dir) {
+            ctx = ctx.with_cache(cache);
+        }
+        if let Some(stats) = &self.stats {
+            ctx = ctx.with_stats(stats.clone());
+        }
+        if let Some(progress) = &self.progress {
+            ctx = ctx.with_progress(progress.clone());
+        }
+        if let Some(mocks) = &self.mocks {
+            ctx = ctx.with_mocks(mocks.clone());
+        }
+        if let Some(dir) = &self.workflows_dir {
+            ctx = ctx.with_workflows_dir(dir.clone());
+        }
+
+        self.run_in_context(&ctx, workflow, args, working_dir, team)
+            .await
+    }
+
+    /// Run a workflow with an existing execution context (shared with sub-workflows)
+    pub(crate) async fn run_in_context(
+        &self,
+        ctx: &ExecutionContext,
+        workflow: WorkflowConfig,
+        mut args: HashMap<String, String>,
+        working_dir: &Path,
+        team: Option<String>,
     ) -> Result<WorkflowResult, WorkflowError> {
         // Validate workflow first
         self.validate_workflow(&workflow)?;
+        let typed_args =
+            check_args(&workflow, &mut args, working_dir).map_err(WorkflowError::InvalidArgs)?;
 
         // Create output directory for this workflow run
         let output_dir = Self::create_output_dir(&workflow.name)?;
 
-        // Detect team
-        let team = detect_team(working_dir, &self.config.teams, team_override);
-
         // Detect ecosystem
         let ecosystem = detect_ecosystem(working_dir, &self.config.ecosystems);
 
         // Create state
         let mut state = WorkflowState::new(workflow.clone(), args, working_dir.to_path_buf());
+        state.typed_args = typed_args;
 
         if let Some(ref team_name) = team {
             if let Some(team_config) = self.config.teams.get(team_name) {
@@ -126,157 +286,289 @@ impl WorkflowRunner {
             }
         }
 
-        // Create execution context
-        let ctx = ExecutionContext::new(self.config.clone());
-
         // Get execution order
         let order = self.topological_sort(&workflow)?;
 
+        // Steps named by an on_failure hook only run when it fires
+        let handlers = workflow.handlers();
+
+        // Step whose error stopped the workflow
+        let mut stopped: Option<(String, WorkflowError)> = None;
+
+        // Handlers already run for each failed step
+        let mut handled: HashMap<String, &[String]> = HashMap::new();
+
         // Execute steps in order
         for step_name in order {
             if state.failed && !workflow.continue_on_error {
                 break;
             }
+            if handlers.contains(step_name.as_str()) {
+                continue;
+            }
+            let Some(step) = workflow.steps.iter().find(|s| s.name == step_name) else {
+                continue;
+            };
+
+            let outcome = self
+                .run_step(
+                    step,
+                    ctx,
+                    &mut state,
+                    team.as_deref(),
+                    working_dir,
+                    &output_dir,
+                    None,
+                )
+                .await;
+
+            // Run the step's own failure hooks
+            let error = match &outcome {
+                Err(e) => Some(failure_message(e)),
+                Ok(()) => state
+                    .get_result(&step_name)
+                    .filter(|result| result.failed)
+                    .map(|result| result.error.clone().unwrap_or_default()),
+            };
+            if let Some(error) = error
+                && !step.on_failure.is_empty()
+            {
+                let failure = Failure {
+                    step: step_name.clone(),
+                    error,
+                };
+                handled.insert(step_name.clone(), &step.on_failure);
+                let steps = Self::handler_steps(&workflow, &step.on_failure);
+                self.run_handlers(
+                    &steps,
+                    Some(&failure),
+                    ctx,
+                    &mut state,
+                    team.as_deref(),
+                    working_dir,
+                    &output_dir,
+                )
+                .await;
+            }
 
-            if let Some(step) = workflow.steps.iter().find(|s| s.name == step_name) {
-                let mut template_ctx = state.to_template_context();
-
-                // Handle for_each
-                if let Some(ref for_each_expr) = step.for_each {
-                    let items = self.evaluate_for_each(for_each_expr, &template_ctx)?;
-                    let mut results = Vec::new();
-
-                    for (idx, item) in items.into_iter().enumerate() {
-                        // Reuse context, just update item (avoids expensive clone)
-                        template_ctx.set_item(item);
-
-                        match execute_step(step, &ctx, &template_ctx, team.as_deref(), working_dir)
-                            .await
-                        {
-                            Ok(result) => {
-                                // Save output for each iteration
-                                if let Some(ref output) = result.output {
-                                    let iter_step_name = format!("{}.{}", step_name, idx);
-                                    if let Err(e) = Self::save_step_output(
-                                        &output_dir,
-                                        &iter_step_name,
-                                        output,
-                                        result.failed,
-                                    ) {
-                                        tracing::warn!(
-                                            step = &iter_step_name,
-                                            error = %e,
-                                            "Failed to save iteration output"
-                                        );
-                                    }
-                                }
-                                results.push(result);
-                            }
-                            Err(e) if step.continue_on_error => {
-                                let error_msg = e.to_string();
-                                let iter_step_name = format!("{}.{}", step_name, idx);
-
-                                // Save error for this iteration
-                                if let Err(err) = Self::save_step_output(
-                                    &output_dir,
-                                    &iter_step_name,
-                                    &error_msg,
-                                    true,
-                                ) {
-                                    tracing::warn!(
-                                        step = &iter_step_name,
-                                        error = %err,
-                                        "Failed to save iteration error"
-                                    );
-                                }
-
-                                results.push(StepResult::failure(error_msg, 0));
-                            }
-                            Err(e) => return Err(e.into()),
-                        }
-                    }
-                    // Clear item after loop
-                    template_ctx.clear_item();
-
-                    // Aggregate results
-                    let aggregated = self.aggregate_for_each_results(results);
-                    state.add_result(&step_name, aggregated, step.continue_on_error);
-                } else {
-                    // Regular step execution
-                    match execute_step(step, &ctx, &template_ctx, team.as_deref(), working_dir)
-                        .await
-                    {
-                        Ok(result) => {
-                            // Save step output to file
-                            if let Some(ref output) = result.output {
-                                if let Err(e) = Self::save_step_output(
-                                    &output_dir,
-                                    &step_name,
-                                    output,
-                                    result.failed,
-                                ) {
-                                    tracing::warn!(
-                                        step = &step_name,
-                                        error = %e,
-                                        "Failed to save step output"
-                                    );
-                                }
-                            }
-
-                            state.add_result(&step_name, result, step.continue_on_error);
-                        }
-                        Err(e) if step.continue_on_error => {
-                            let error_msg = e.to_string();
-
-                            // Save error output
-                            if let Err(err) =
-                                Self::save_step_output(&output_dir, &step_name, &error_msg, true)
-                            {
-                                tracing::warn!(
-                                    step = &step_name,
-                                    error = %err,
-                                    "Failed to save error output"
-                                );
-                            }
-
-                            state.add_result(&step_name, StepResult::failure(error_msg, 0), true);
-                        }
-                        Err(e) => {
-                            let error_msg = e.to_string();
-
-                            // Save error output before returning
-                            if let Err(err) =
-                                Self::save_step_output(&output_dir, &step_name, &error_msg, true)
-                            {
-                                tracing::warn!(
-                                    step = &step_name,
-                                    error = %err,
-                                    "Failed to save error output"
-                                );
-                            }
-
-                            return Err(WorkflowError::StepFailed {
-                                step: step_name.clone(),
-                                message: error_msg,
-                            });
-                        }
-                    }
-                }
+            if let Err(e) = outcome {
+                stopped = Some((step_name, e));
+                break;
             }
         }
 
+        let failure = match &stopped {
+            Some((step, e)) => Some(Failure {
+                step: step.clone(),
+                error: failure_message(e),
+            }),
+            None if state.failed => Some(Failure {
+                step: state.failed_step.clone().unwrap_or_default(),
+                error: state.error.clone().unwrap_or_default(),
+            }),
+            None => None,
+        };
+
+        // Workflow failure hooks, then steps that always run. Hooks the failed
+        // step already ran for this failure don't run a second time.
+        if let Some(ref failure) = failure {
+            let already_run = handled.get(&failure.step).copied().unwrap_or_default();
+            let remaining: Vec<String> = workflow
+                .on_failure
+                .iter()
+                .filter(|name| !already_run.contains(name))
+                .cloned()
+                .collect();
+            let steps = Self::handler_steps(&workflow, &remaining);
+            self.run_handlers(
+                &steps,
+                Some(failure),
+                ctx,
+                &mut state,
+                team.as_deref(),
+                working_dir,
+                &output_dir,
+            )
+            .await;
+        }
+        let finally: Vec<&StepConfig> = workflow.finally.iter().collect();
+        self.run_handlers(
+            &finally,
+            failure.as_ref(),
+            ctx,
+            &mut state,
+            team.as_deref(),
+            working_dir,
+            &output_dir,
+        )
+        .await;
+
         tracing::info!(
             output_dir = %output_dir.display(),
             "Workflow outputs saved"
         );
 
+        if let Some((_, e)) = stopped {
+            return Err(e);
+        }
+
         let mut result = WorkflowResult::from_state(&state);
         result.output_dir = Some(output_dir.to_string_lossy().to_string());
         Ok(result)
     }
 
+    /// Run one step (or all its for_each iterations) and record its result
+    ///
+    /// While handling a failure, `failure` is set and failed steps don't fail the workflow.
+    #[allow(clippy::too_many_arguments)]
+    async fn run_step(
+        &self,
+        step: &StepConfig,
+        ctx: &ExecutionContext,
+        state: &mut WorkflowState,
+        team: Option<&str>,
+        working_dir: &Path,
+        output_dir: &Path,
+        failure: Option<&Failure>,
+    ) -> Result<(), WorkflowError> {
+        let step_name = &step.name;
+        let continue_on_error = step.continue_on_error || failure.is_some();
+
+        let mut template_ctx = state.to_template_context();
+        template_ctx.set_local("failure", Value::from_serialize(failure));
+
+        // Handle for_each
+        if let Some(ref for_each_expr) = step.for_each {
+            let items = self.evaluate_for_each(for_each_expr, &template_ctx)?;
+            let results = self
+                .run_for_each(step, items, ctx, &template_ctx, team, working_dir)
+                .await?;
+
+            // Save output (or error) for each iteration
+            for (idx, result) in results.iter().enumerate() {
+                let saved = match (&result.output, &result.error) {
+                    (Some(output), _) => Some((output, result.failed)),
+                    (None, Some(error)) if result.failed => Some((error, true)),
+                    _ => None,
+                };
+                if let Some((text, failed)) = saved {
+                    let iter_step_name = format!("{}.{}", step_name, idx);
+                    if let Err(e) =
+                        Self::save_step_output(output_dir, &iter_step_name, text, failed)
+                    {
+                        tracing::warn!(
+                            step = &iter_step_name,
+                            error = %e,
+                            "Failed to save iteration output"
+                        );
+                    }
+                }
+            }
+
+            // Aggregate results
+            let aggregated = self.aggregate_for_each_results(step, results);
+            state.add_result(step_name, aggregated, continue_on_error);
+            return Ok(());
+        }
+
+        // Regular step execution
+        match execute_step(step, ctx, &template_ctx, team, working_dir).await {
+            Ok(result) => {
+                // Save step output to file
+                if let Some(ref output) = result.output {
+                    if let Err(e) =
+                        Self::save_step_output(output_dir, step_name, output, result.failed)
+                    {
+                        tracing::warn!(
+                            step = step_name,
+                            error = %e,
+                            "Failed to save step output"
+                        );
+                    }
+                }
+
+                state.add_result(step_name, result, continue_on_error);
+                Ok(())
+            }
+            Err(e) if continue_on_error => {
+                let error_msg = e.to_string();
+
+                // Save error output
+                if let Err(err) = Self::save_step_output(output_dir, step_name, &error_msg, true) {
+                    tracing::warn!(
+                        step = step_name,
+                        error = %err,
+                        "Failed to save error output"
+                    );
+                }
+
+                state.add_result(step_name, StepResult::failure(error_msg, 0), true);
+                Ok(())
+            }
+            Err(e) => {
+                let error_msg = e.to_string();
+
+                // Save error output before returning
+                if let Err(err) = Self::save_step_output(output_dir, step_name, &error_msg, true) {
+                    tracing::warn!(
+                        step = step_name,
+                        error = %err,
+                        "Failed to save error output"
+                    );
+                }
+
+                Err(WorkflowError::StepFailed {
+                    step: step_name.clone(),
+                    message: error_msg,
+                })
+            }
+        }
+    }
+
+    /// Run handler or `finally` steps in order; a failing one doesn't stop the rest
+    #[allow(clippy::too_many_arguments)]
+    async fn run_handlers(
+        &self,
+        steps: &[&StepConfig],
+        failure: Option<&Failure>,
+        ctx: &ExecutionContext,
+        state: &mut WorkflowState,
+        team: Option<&str>,
+        working_dir: &Path,
+        output_dir: &Path,
+    ) {
+        // Handlers don't replace the workflow's output
+        let output = state.output.clone();
+        for step in steps {
+            if let Err(e) = self
+                .run_step(step, ctx, state, team, working_dir, output_dir, failure)
+                .await
+            {
+                tracing::warn!(step = %step.name, error = %e, "Step failed");
+                state.add_result(
+                    &step.name,
+                    StepResult::failure(failure_message(&e), 0),
+                    failure.is_some(),
+                );
+            }
+        }
+        state.output = output;
+    }
+
+    /// Steps named by an on_failure hook
+    fn handler_steps<'a>(workflow: &'a WorkflowConfig, names: &[String]) -> Vec<&'a StepConfig> {
+        names
+            .iter()
+            .filter_map(|name| workflow.steps.iter().find(|step| &step.name == name))
+            .collect()
+    }
+
     /// Validate workflow before execution
     fn validate_workflow(&self, workflow: &WorkflowConfig) -> Result<(), WorkflowError> {
+        workflow
+            .validate_roles(&self.config)
+            .map_err(WorkflowError::Invalid)?;
+
         // Check for unknown dependencies
         let step_names: std::collections::HashSet<_> =
             workflow.steps.iter().map(|s| s.name.as_str()).collect();
@@ -393,44 +685,181 @@ impl WorkflowRunner {
         }
     }
 
-    /// Aggregate for_each results
-    fn aggregate_for_each_results(&self, results: Vec<StepResult>) -> StepResult {
+    /// Run a for_each step's iterations, up to `for_each_concurrency` at a time
+    ///
+    /// Results are in item order. Without `continue_on_error` or a threshold, an
+    /// iteration error aborts the step; iterations not yet started are skipped.
+    async fn run_for_each(
+        &self,
+        step: &StepConfig,
+        items: Vec<Value>,
+        ctx: &ExecutionContext,
+        template_ctx: &TemplateContext,
+        team: Option<&str>,
+        working_dir: &Path,
+    ) -> Result<Vec<StepResult>, WorkflowError> {
+        let tolerate_errors = step.continue_on_error
+            || step.for_each_max_failures.is_some()
+            || step.for_each_min_success.is_some();
+        let semaphore = Semaphore::new(step.for_each_concurrency.unwrap_or(1).max(1));
+        let failures = AtomicUsize::new(0);
+        let stopped = AtomicBool::new(false);
+
+        let iterations: Vec<_> = items
+            .into_iter()
+            .map(|item| {
+                let (semaphore, failures, stopped) = (&semaphore, &failures, &stopped);
+                async move {
+                    let _permit = semaphore
+                        .acquire()
+                        .await
+                        .expect("semaphore is never closed");
+                    if stopped.load(Ordering::SeqCst) {
+                        return Ok(StepResult {
+                            error: Some("skipped: for_each stopped after failures".into()),
+                            ..Default::default()
+                        });
+                    }
+
+                    let mut item_ctx = template_ctx.clone();
+                    item_ctx.set_item(item);
+                    let result = match execute_step(step, ctx, &item_ctx, team, working_dir).await {
+                        Ok(result) => result,
+                        Err(e) if tolerate_errors => StepResult::failure(e.to_string(), 0),
+                        Err(e) => {
+                            stopped.store(true, Ordering::SeqCst);
+                            return Err(e);
+                        }
+                    };
+
+                    if result.failed {
+                        let failed = failures.fetch_add(1, Ordering::SeqCst) + 1;
+                        if step.for_each_max_failures.is_some_and(|max| failed > max) {
+                            stopped.store(true, Ordering::SeqCst);
+                        }
+                    }
+                    Ok(result)
+                }
+            })
+            .collect();
+
+        join_all(iterations)
+            .await
+            .into_iter()
+            .map(|result| result.map_err(WorkflowError::from))
+            .collect()
+    }
+
+    /// Aggregate for_each results, applying the step's failure thresholds
+    fn aggregate_for_each_results(
+        &self,
+        step: &StepConfig,
+        results: Vec<StepResult>,
+    ) -> StepResult {
         let mut outputs = Vec::new();
         let mut all_failed = true;
-        let mut any_failed = false;
+        let mut failed = 0;
         let mut total_duration = 0u64;
         let mut backends = Vec::new();
+        let mut cache_hits = Vec::new();
 
-        for result in results {
-            if let Some(output) = result.output {
-                outputs.push(output);
-            }
-            if !result.failed {
-                all_failed = false;
+        for result in &results {
+            if let Some(ref output) = result.output {
+                outputs.push(output.clone());
             }
             if result.failed {
-                any_failed = true;
+                failed += 1;
+            } else {
+                all_failed = false;
             }
             total_duration += result.duration_ms;
-            backends.extend(result.backends);
+            backends.extend(result.backends.iter().cloned());
+            cache_hits.extend(result.cache_hits.iter().cloned());
         }
+        let skipped = results
+            .iter()
+            .filter(|r| {
+                r.error
+                    .as_deref()
+                    .is_some_and(|e| e.starts_with("skipped:"))
+            })
+            .count();
+        let succeeded = results.len() - failed - skipped;
+
+        let (step_failed, error) = if let Some(max) = step.for_each_max_failures
+            && failed > max
+        {
+            (
+                true,
+                Some(format!(
+                    "{} of {} iterations failed (max {})",
+                    failed,
+                    results.len(),
+                    max
+                )),
+            )
+        } else if let Some(min) = step.for_each_min_success
+            && succeeded < min
+        {
+            (
+                true,
+                Some(format!(
+                    "{} of {} iterations succeeded (min {})",
+                    succeeded,
+                    results.len(),
+                    min
+                )),
+            )
+        } else if step.for_each_max_failures.is_some() || step.for_each_min_success.is_some() {
+            (false, (failed > 0).then(|| "some iterations failed".into()))
+        } else {
+            (
+                all_failed,
+                (failed > 0).then(|| "some iterations failed".into()),
+            )
+        };
 
         StepResult {
             output: Some(outputs.join("\n")),
             outputs: HashMap::new(),
-            failed: all_failed,
-            error: if any_failed {
-                Some("some iterations failed".into())
-            } else {
-                None
-            },
+            failed: step_failed,
+            error,
             duration_ms: total_duration,
             backend: backends.first().cloned(),
             backends,
+            cache_hits,
+            items: results,
+            ..Default::default()
         }
     }
 }
 
+/// Poll futures concurrently on the current task, returning their outputs in order
+pub(super) async fn join_all<F: Future>(futures: Vec<F>) -> Vec<F::Output> {
+    let mut futures: Vec<_> = futures.into_iter().map(Box::pin).collect();
+    let mut outputs: Vec<Option<F::Output>> = futures.iter().map(|_| None).collect();
+
+    std::future::poll_fn(|cx| {
+        let mut pending = false;
+        for (future, output) in futures.iter_mut().zip(outputs.iter_mut()) {
+            if output.is_none() {
+                match future.as_mut().poll(cx) {
+                    Poll::Ready(value) => *output = Some(value),
+                    Poll::Pending => pending = true,
+                }
+            }
+        }
+        if pending {
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
+    })
+    .await;
+
+    outputs.into_iter().flatten().collect()
+}
+
 /// Convert serde_json::Value to minijinja::value::Value
 fn json_to_minijinja_value(json: &serde_json::Value) -> Value {
     match json {
@@ -485,6 +914,8 @@ mod tests {
             args: HashMap::new(),
             timeout: None,
             continue_on_error: false,
+            on_failure: Vec::new(),
+            finally: Vec::new(),
             steps: vec![
                 StepConfig {
                     name: "step1".into(),
@@ -662,4 +1093,469 @@ mod tests {
                 .contains("first_output")
         );
     }
+
+    #[test]
+    fn test_uses_cache_checks_sub_workflows() {
+        let dir = TempDir::new().unwrap();
+        let workflows = dir.path().join(".llm-mux/workflows");
+        std::fs::create_dir_all(&workflows).unwrap();
+        std::fs::write(
+            workflows.join("cached-summary.toml"),
+            r#"
+name = "cached-summary"
+
+[[steps]]
+name = "summarize"
+type = "query"
+role = "analyzer"
+prompt = "Summarize"
+cache = "1h"
+"#,
+        )
+        .unwrap();
+
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "parent"
+
+[[steps]]
+name = "summary"
+type = "workflow"
+workflow = "cached-summary"
+"#,
+        )
+        .unwrap();
+        let uncached: WorkflowConfig = toml::from_str(
+            r#"
+name = "plain"
+
+[[steps]]
+name = "hello"
+type = "shell"
+run = "echo hello"
+"#,
+        )
+        .unwrap();
+
+        let runner = WorkflowRunner::new(Arc::new(create_test_config()));
+        assert!(runner.uses_cache(&workflow, dir.path()));
+        assert!(!runner.uses_cache(&uncached, dir.path()));
+    }
+
+    #[tokio::test]
+    async fn test_sub_workflow_step() {
+        let dir = TempDir::new().unwrap();
+        let workflows = dir.path().join(".llm-mux/workflows");
+        std::fs::create_dir_all(&workflows).unwrap();
+        std::fs::write(
+            workflows.join("collect-context.toml"),
+            r#"
+name = "collect-context"
+
+[[steps]]
+name = "gather"
+type = "shell"
+run = "echo 'notes on {{ args.topic }}'"
+
+[[steps]]
+name = "summarize"
+type = "shell"
+run = "echo 'summary'"
+depends_on = ["gather"]
+"#,
+        )
+        .unwrap();
+
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "parent"
+
+[[steps]]
+name = "context"
+type = "workflow"
+workflow = "collect-context"
+with = { topic = "{{ args.topic }}" }
+
+[[steps]]
+name = "report"
+type = "shell"
+run = "echo '{{ steps.context.steps.gather.output | trim }} / {{ steps.context.output | trim }}'"
+depends_on = ["context"]
+"#,
+        )
+        .unwrap();
+
+        let mut args = HashMap::new();
+        args.insert("topic".into(), "caching".into());
+
+        let runner = WorkflowRunner::new(Arc::new(create_test_config()));
+        let result = runner.run(workflow, args, dir.path(), None).await.unwrap();
+
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(
+            result.step_output("report").map(str::trim),
+            Some("notes on caching / summary")
+        );
+        assert_eq!(
+            result.output.as_deref().map(str::trim),
+            result.step_output("report").map(str::trim)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_loop_step() {
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "refine"
+
+[[steps]]
+name = "refine"
+type = "loop"
+until = "steps.attempt.output | trim == '3'"
+
+[[steps.steps]]
+name = "attempt"
+type = "shell"
+run = "echo {{ loop.index }}"
+
+[[steps.steps]]
+name = "review"
+type = "shell"
+run = "echo '{% if loop.first %}none{% else %}{{ loop.previous.attempt.output | trim }}{% endif %} -> {{ steps.attempt.output | trim }}'"
+depends_on = ["attempt"]
+
+[[steps]]
+name = "capped"
+type = "loop"
+until = "false"
+max_iterations = 2
+
+[[steps.steps]]
+name = "attempt"
+type = "shell"
+run = "echo {{ loop.index }}"
+
+[[steps]]
+name = "strict"
+type = "loop"
+until = "false"
+max_iterations = 2
+fail_on_max = true
+continue_on_error = true
+
+[[steps.steps]]
+name = "attempt"
+type = "shell"
+run = "echo {{ loop.index }}"
+"#,
+        )
+        .unwrap();
+        workflow.validate().unwrap();
+
+        let dir = TempDir::new().unwrap();
+        let runner = WorkflowRunner::new(Arc::new(create_test_config()));
+        let result = runner
+            .run(workflow, HashMap::new(), dir.path(), None)
+            .await
+            .unwrap();
+
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.step_output("refine").map(str::trim), Some("2 -> 3"));
+        assert_eq!(
+            result.steps["refine"].steps["attempt"]
+                .output
+                .as_deref()
+                .map(str::trim),
+            Some("3")
+        );
+        assert_eq!(result.step_output("capped").map(str::trim), Some("2"));
+        assert!(!result.steps["capped"].failed);
+        assert!(result.steps["strict"].failed);
+        assert_eq!(
+            result.steps["strict"].error.as_deref(),
+            Some("'until' still false after 2 iteration(s)")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parallel_for_each() {
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "review"
+
+[[steps]]
+name = "each"
+type = "shell"
+run = "sleep 0.1 && echo {{ item }}"
+for_each = "'a,b,c'"
+for_each_concurrency = 3
+
+[[steps]]
+name = "second"
+type = "shell"
+run = "echo {{ steps.each.items[1].output }}"
+depends_on = ["each"]
+"#,
+        )
+        .unwrap();
+
+        let dir = TempDir::new().unwrap();
+        let runner = WorkflowRunner::new(Arc::new(create_test_config()));
+        let result = runner
+            .run(workflow, HashMap::new(), dir.path(), None)
+            .await
+            .unwrap();
+
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.step_output("each"), Some("a\nb\nc"));
+        assert_eq!(result.steps["each"].items.len(), 3);
+        assert_eq!(result.step_output("second"), Some("b"));
+        let saved = result.output_dir.unwrap();
+        assert!(Path::new(&saved).join("each.2.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_for_each_thresholds() {
+        let workflow = |threshold: &str| -> WorkflowConfig {
+            toml::from_str(&format!(
+                r#"
+name = "thresholds"
+
+[[steps]]
+name = "each"
+type = "shell"
+run = "test {{{{ item }}}} != 2 && echo {{{{ item }}}}"
+for_each = "'1,2,3,4'"
+{}
+"#,
+                threshold
+            ))
+            .unwrap()
+        };
+        let dir = TempDir::new().unwrap();
+        let runner = WorkflowRunner::new(Arc::new(create_test_config()));
+
+        let result = runner
+            .run(
+                workflow("for_each_max_failures = 0"),
+                HashMap::new(),
+                dir.path(),
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(!result.success);
+        let each = &result.steps["each"];
+        assert_eq!(
+            each.error.as_deref(),
+            Some("1 of 4 iterations failed (max 0)")
+        );
+        assert!(each.items[1].failed);
+        assert!(
+            each.items[2]
+                .error
+                .as_deref()
+                .unwrap()
+                .starts_with("skipped:")
+        );
+
+        let result = runner
+            .run(
+                workflow("for_each_min_success = 3"),
+                HashMap::new(),
+                dir.path(),
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.step_output("each"), Some("1\n3\n4"));
+
+        let result = runner
+            .run(
+                workflow("for_each_min_success = 4"),
+                HashMap::new(),
+                dir.path(),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            result.error.as_deref(),
+            Some("3 of 4 iterations succeeded (min 4)")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_matrix_results_in_templates() {
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "matrix"
+
+[[steps]]
+name = "each"
+type = "shell"
+run = "echo {{ matrix.size }}-{{ matrix.color }}"
+matrix = { color = ["red", "blue"], size = ["s", "l"] }
+
+[[steps]]
+name = "pick"
+type = "shell"
+run = "echo {{ steps.each.matrix['red/l'].output }}"
+depends_on = ["each"]
+"#,
+        )
+        .unwrap();
+
+        let dir = TempDir::new().unwrap();
+        let runner = WorkflowRunner::new(Arc::new(create_test_config()));
+        let result = runner
+            .run(workflow, HashMap::new(), dir.path(), None)
+            .await
+            .unwrap();
+
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.steps["each"].matrix.len(), 4);
+        assert_eq!(result.step_output("pick"), Some("l-red"));
+    }
+
+    #[tokio::test]
+    async fn test_failure_handlers_and_finally() {
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "handlers"
+on_failure = ["notify"]
+
+[[steps]]
+name = "build"
+type = "shell"
+run = "echo built"
+
+[[steps]]
+name = "deploy"
+type = "shell"
+run = "echo disk full >&2; exit 3"
+depends_on = ["build"]
+on_failure = ["rollback"]
+
+[[steps]]
+name = "rollback"
+type = "shell"
+run = "echo {{ failure.step }}: {{ failure.error }} > rollback.txt"
+
+[[steps]]
+name = "notify"
+type = "shell"
+run = "echo {{ failure.step }} > notify.txt"
+
+[[finally]]
+name = "cleanup"
+type = "shell"
+run = "echo {{ 'failed' if failure else 'ok' }}"
+"#,
+        )
+        .unwrap();
+
+        let dir = TempDir::new().unwrap();
+        let runner = WorkflowRunner::new(Arc::new(create_test_config()));
+        let result = runner
+            .run(workflow.clone(), HashMap::new(), dir.path(), None)
+            .await;
+        assert!(matches!(
+            result,
+            Err(WorkflowError::StepFailed { ref step, .. }) if step == "deploy"
+        ));
+        let read = |name: &str| std::fs::read_to_string(dir.path().join(name)).unwrap();
+        assert_eq!(
+            read("rollback.txt").trim(),
+            "deploy: shell command failed: disk full"
+        );
+        assert_eq!(read("notify.txt").trim(), "deploy");
+
+        // Handlers don't run when nothing fails, finally always does
+        let mut passing = workflow;
+        passing.steps[1].run = Some("echo deployed".into());
+        let result = runner
+            .run(passing, HashMap::new(), dir.path(), None)
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert!(!result.steps.contains_key("rollback"));
+        assert!(!result.steps.contains_key("notify"));
+        assert_eq!(result.step_output("cleanup"), Some("ok"));
+        assert_eq!(result.output.as_deref(), Some("deployed"));
+    }
+
+    #[tokio::test]
+    async fn test_shared_failure_handler_runs_once() {
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "handlers"
+on_failure = ["rollback", "notify"]
+
+[[steps]]
+name = "deploy"
+type = "shell"
+run = "exit 1"
+on_failure = ["rollback"]
+
+[[steps]]
+name = "rollback"
+type = "shell"
+run = "echo rollback >> handlers.log"
+
+[[steps]]
+name = "notify"
+type = "shell"
+run = "echo notify >> handlers.log"
+"#,
+        )
+        .unwrap();
+
+        let dir = TempDir::new().unwrap();
+        let runner = WorkflowRunner::new(Arc::new(create_test_config()));
+        let result = runner.run(workflow, HashMap::new(), dir.path(), None).await;
+        assert!(result.is_err());
+
+        let log = std::fs::read_to_string(dir.path().join("handlers.log")).unwrap();
+        assert_eq!(log.lines().collect::<Vec<_>>(), ["rollback", "notify"]);
+    }
+
+    #[tokio::test]
+    async fn test_typed_args() {
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "typed"
+
+[args.count]
+type = "int"
+default = 2
+
+[args.verbose]
+type = "bool"
+
+[[steps]]
+name = "show"
+type = "shell"
+run = "echo {{ args.count + 1 }} {{ 'verbose' if args.verbose else 'terse' }}"
+"#,
+        )
+        .unwrap();
+
+        let dir = TempDir::new().unwrap();
+        let runner = WorkflowRunner::new(Arc::new(create_test_config()));
+        let args = HashMap::from([("verbose".to_string(), "no".to_string())]);
+        let result = runner
+            .run(workflow.clone(), args, dir.path(), None)
+            .await
+            .unwrap();
+        assert_eq!(result.step_output("show"), Some("3 terse"));
+
+        let args = HashMap::from([("count".to_string(), "many".to_string())]);
+        let err = runner
+            .run(workflow, args, dir.path(), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, WorkflowError::InvalidArgs(ref errors) if errors.len() == 1));
+    }
 }
@@ -4,6 +4,7 @@
 
 use crate::config::{EcosystemConfig, StepResult, TeamConfig, WorkflowConfig};
 use crate::template::TemplateContext;
+use minijinja::Value;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::{Duration, Instant};
@@ -17,6 +18,9 @@ pub struct WorkflowState {
     /// CLI arguments
     pub args: HashMap<String, String>,
 
+    /// Typed values of declared arguments
+    pub typed_args: HashMap<String, serde_json::Value>,
+
     /// Detected or specified team
     pub team: Option<String>,
 
@@ -38,6 +42,9 @@ pub struct WorkflowState {
     /// Results from completed steps
     pub step_results: HashMap<String, StepResult>,
 
+    /// Output of the most recently completed step that produced one
+    pub output: Option<String>,
+
     /// Overall workflow start time
     pub started_at: Instant,
 
@@ -46,6 +53,9 @@ pub struct WorkflowState {
 
     /// Error message if failed
     pub error: Option<String>,
+
+    /// Step whose failure failed the workflow
+    pub failed_step: Option<String>,
 }
 
 impl WorkflowState {
@@ -58,6 +68,7 @@ impl WorkflowState {
         Self {
             workflow,
             args,
+            typed_args: HashMap::new(),
             team: None,
             team_config: None,
             ecosystem: None,
@@ -65,9 +76,11 @@ impl WorkflowState {
             current_project: None,
             working_dir,
             step_results: HashMap::new(),
+            output: None,
             started_at: Instant::now(),
             failed: false,
             error: None,
+            failed_step: None,
         }
     }
 
@@ -102,6 +115,10 @@ impl WorkflowState {
         if result.failed && !self.workflow.continue_on_error && !step_continue_on_error {
             self.failed = true;
             self.error = result.error.clone();
+            self.failed_step = Some(step_name.to_string());
+        }
+        if result.output.is_some() {
+            self.output = result.output.clone();
         }
         self.step_results.insert(step_name.to_string(), result);
     }
@@ -124,6 +141,11 @@ impl WorkflowState {
     /// Build template context from current state
     pub fn to_template_context(&self) -> TemplateContext {
         let mut ctx = TemplateContext::with_args(self.args.clone());
+        ctx.typed_args = self
+            .typed_args
+            .iter()
+            .map(|(name, value)| (name.clone(), Value::from_serialize(value)))
+            .collect();
 
         // Add step results
         for (name, result) in &self.step_results {
@@ -168,6 +190,9 @@ pub struct WorkflowResult {
     /// Step results map
     pub steps: HashMap<String, StepResult>,
 
+    /// Final output: that of the last step to produce one
+    pub output: Option<String>,
+
     /// Overall success
     pub success: bool,
 
@@ -189,6 +214,7 @@ impl WorkflowResult {
     pub fn from_state(state: &WorkflowState) -> Self {
         Self {
             steps: state.step_results.clone(),
+            output: state.output.clone(),
             success: !state.failed,
             error: state.error.clone(),
             duration: state.elapsed(),
@@ -225,6 +251,8 @@ mod tests {
             args: HashMap::new(),
             timeout: None,
             continue_on_error: false,
+            on_failure: Vec::new(),
+            finally: Vec::new(),
             steps: vec![
                 StepConfig {
                     name: "step1".into(),
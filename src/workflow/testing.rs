@@ -0,0 +1,696 @@
+//! Workflow tests: `*.test.toml` files next to workflows
+//!
+//! A test runs its workflow in-process in a temporary working tree, with
+//! canned responses for query steps and optional fake shell output, then
+//! checks step outputs, skipped and failed steps, and file contents.
+//!
+//! ```toml
+//! # .llm-mux/workflows/fix.test.toml tests fix.toml
+//! [[test]]
+//! name = "applies the suggested edit"
+//! args = { issue = "42" }
+//! files = { "src/lib.rs" = "fn add() {}\n" }
+//!
+//! [test.mock.fetch]              # shell step: fake stdout
+//! output = "Issue 42: add is empty"
+//!
+//! [test.mock.analyze]            # query step: backend response
+//! output = '[{"path": "src/lib.rs", "old": "fn add() {}", "new": "fn add() -> i32 { 1 }"}]'
+//!
+//! [test.expect]
+//! skipped = ["notify"]
+//! steps.analyze = { contains = "src/lib.rs" }
+//! files."src/lib.rs" = { contains = "-> i32" }
+//! ```
+
+use super::runner::WorkflowRunner;
+use crate::backend_executor::{BackendError, BackendExecutor, BackendRequest, BackendResponse};
+use crate::config::{LlmuxConfig, WorkflowConfig, load_workflow_file};
+use crate::role::BackendFactory;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Suffix of workflow test files
+pub const TEST_FILE_SUFFIX: &str = ".test.toml";
+
+/// Canned result for a step, used instead of calling backends or running commands
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct StepMock {
+    /// Backend response (query steps) or stdout (shell steps)
+    pub output: Option<String>,
+
+    /// Per-backend responses for query steps; other backends answer with `output`
+    #[serde(default)]
+    pub outputs: HashMap<String, String>,
+
+    /// Backend error (query steps) or stderr (shell steps)
+    pub error: Option<String>,
+
+    /// Exit code for shell steps
+    #[serde(default)]
+    pub exit_code: i32,
+}
+
+impl StepMock {
+    /// Backends answering with this mock, in place of the configured ones
+    pub fn backends(&self) -> BackendFactory {
+        let mock = Arc::new(self.clone());
+        Arc::new(move |name: &str| -> Box<dyn BackendExecutor> {
+            Box::new(MockBackend {
+                name: name.to_string(),
+                mock: mock.clone(),
+            })
+        })
+    }
+}
+
+/// Backend that answers with a step's mock
+///
+/// Everything above the backend (parallel, vote and synthesis handling) runs
+/// as it would against real backends.
+struct MockBackend {
+    name: String,
+    mock: Arc<StepMock>,
+}
+
+#[async_trait]
+impl BackendExecutor for MockBackend {
+    async fn execute(&self, _request: &BackendRequest) -> Result<BackendResponse, BackendError> {
+        if let Some(ref error) = self.mock.error {
+            return Err(BackendError::execution_failed(
+                None,
+                String::new(),
+                error.clone(),
+            ));
+        }
+        match self
+            .mock
+            .outputs
+            .get(&self.name)
+            .or(self.mock.output.as_ref())
+        {
+            Some(output) => Ok(BackendResponse::new(
+                output.clone(),
+                self.name.clone(),
+                Duration::ZERO,
+            )),
+            None => Err(BackendError::Config {
+                message: format!("no mock response for backend '{}'", self.name),
+            }),
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A `*.test.toml` file
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkflowTestFile {
+    /// Workflow under test (defaults to the file name without `.test.toml`)
+    pub workflow: Option<String>,
+
+    #[serde(default, rename = "test")]
+    pub tests: Vec<WorkflowTest>,
+}
+
+/// One test case
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkflowTest {
+    pub name: String,
+
+    /// Workflow arguments
+    #[serde(default)]
+    pub args: HashMap<String, String>,
+
+    /// Files of the temporary working tree, by relative path
+    #[serde(default)]
+    pub files: HashMap<String, String>,
+
+    /// Mocks by step name; every query step that runs needs one
+    #[serde(default)]
+    pub mock: HashMap<String, StepMock>,
+
+    #[serde(default)]
+    pub expect: Expectations,
+}
+
+/// What a test expects of the run
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expectations {
+    /// Whether the workflow succeeds (defaults to true unless `error` is set)
+    pub success: Option<bool>,
+
+    /// Substring of the error that stopped the workflow
+    pub error: Option<String>,
+
+    /// Steps whose condition skipped them
+    #[serde(default)]
+    pub skipped: Vec<String>,
+
+    /// Steps that failed
+    #[serde(default)]
+    pub failed: Vec<String>,
+
+    /// Step outputs
+    #[serde(default)]
+    pub steps: HashMap<String, Expect>,
+
+    /// File contents after the run
+    #[serde(default)]
+    pub files: HashMap<String, Expect>,
+}
+
+/// Expectation about a step output or file content
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expect {
+    pub equals: Option<String>,
+    pub contains: Option<String>,
+    pub not_contains: Option<String>,
+    /// Regular expression
+    pub matches: Option<String>,
+    /// Whether there is any output (or the file exists)
+    pub exists: Option<bool>,
+}
+
+impl Expect {
+    /// Failures for `what`, whose value is `actual`
+    fn check(&self, what: &str, actual: Option<&str>, failures: &mut Vec<String>) {
+        if let Some(exists) = self.exists
+            && exists != actual.is_some()
+        {
+            failures.push(if exists {
+                format!("{} is missing", what)
+            } else {
+                format!("{} should not exist", what)
+            });
+            return;
+        }
+
+        let needs_value = self.equals.is_some()
+            || self.contains.is_some()
+            || self.not_contains.is_some()
+            || self.matches.is_some();
+        let Some(actual) = actual else {
+            if needs_value {
+                failures.push(format!("{} is missing", what));
+            }
+            return;
+        };
+
+        if let Some(ref expected) = self.equals
+            && actual.trim() != expected.trim()
+        {
+            failures.push(format!(
+                "{}: expected {:?}, got {:?}",
+                what,
+                expected.trim(),
+                actual.trim()
+            ));
+        }
+        if let Some(ref needle) = self.contains
+            && !actual.contains(needle.as_str())
+        {
+            failures.push(format!("{}: expected to contain {:?}", what, needle));
+        }
+        if let Some(ref needle) = self.not_contains
+            && actual.contains(needle.as_str())
+        {
+            failures.push(format!("{}: expected not to contain {:?}", what, needle));
+        }
+        if let Some(ref pattern) = self.matches {
+            match regex::Regex::new(pattern) {
+                Ok(re) if re.is_match(actual) => {}
+                Ok(_) => failures.push(format!("{}: expected to match /{}/", what, pattern)),
+                Err(e) => failures.push(format!("{}: invalid regex /{}/: {}", what, pattern, e)),
+            }
+        }
+    }
+}
+
+/// Result of one test
+#[derive(Debug, Clone, Serialize)]
+pub struct TestOutcome {
+    pub file: String,
+    pub name: String,
+    pub failures: Vec<String>,
+    pub duration_ms: u64,
+}
+
+impl TestOutcome {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Test files in a directory, sorted by name
+pub fn discover_tests(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.ends_with(TEST_FILE_SUFFIX))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Run the tests of a file whose name contains `filter` (all when `None`)
+pub async fn run_test_file(
+    path: &Path,
+    config: Arc<LlmuxConfig>,
+    filter: Option<&str>,
+) -> Result<Vec<TestOutcome>> {
+    let contents =
+        std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let file: WorkflowTestFile =
+        toml::from_str(&contents).with_context(|| format!("parsing {}", path.display()))?;
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let workflow_name = file
+        .workflow
+        .clone()
+        .unwrap_or_else(|| file_name.trim_end_matches(TEST_FILE_SUFFIX).to_string());
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let workflow_path = dir.join(format!("{}.toml", workflow_name));
+    let workflow = load_workflow_file(&workflow_path)?;
+
+    let mut outcomes = Vec::new();
+    for test in &file.tests {
+        if filter.is_some_and(|filter| !test.name.contains(filter)) {
+            continue;
+        }
+        let mut outcome = run_test(&workflow, test, config.clone(), Some(dir)).await;
+        outcome.file = file_name.clone();
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
+
+/// Run one test in a fresh working tree, finding sub-workflows in `workflows_dir`
+pub async fn run_test(
+    workflow: &WorkflowConfig,
+    test: &WorkflowTest,
+    config: Arc<LlmuxConfig>,
+    workflows_dir: Option<&Path>,
+) -> TestOutcome {
+    let start = Instant::now();
+    let mut failures = Vec::new();
+
+    match Fixture::create(&workflow.name, &test.files) {
+        Ok(fixture) => {
+            let mut runner = WorkflowRunner::new(config)
+                .with_cache(false)
+                .with_mocks(Arc::new(test.mock.clone()));
+            if let Some(dir) = workflows_dir {
+                runner = runner.with_workflows_dir(dir.to_path_buf());
+            }
+            let result = runner
+                .run(workflow.clone(), test.args.clone(), &fixture.dir, None)
+                .await;
+            let (success, error, steps) = match result {
+                Ok(result) => (result.success, result.error, result.steps),
+                Err(e) => (false, Some(e.to_string()), HashMap::new()),
+            };
+            check_run(
+                &test.expect,
+                success,
+                error.as_deref(),
+                &steps,
+                &mut failures,
+            );
+
+            for (file, expect) in &test.expect.files {
+                let content = std::fs::read_to_string(fixture.dir.join(file)).ok();
+                expect.check(
+                    &format!("file '{}'", file),
+                    content.as_deref(),
+                    &mut failures,
+                );
+            }
+        }
+        Err(e) => failures.push(format!("could not create working tree: {}", e)),
+    }
+
+    TestOutcome {
+        file: String::new(),
+        name: test.name.clone(),
+        failures,
+        duration_ms: start.elapsed().as_millis() as u64,
+    }
+}
+
+/// Check the workflow's outcome and its steps against the expectations
+fn check_run(
+    expect: &Expectations,
+    success: bool,
+    error: Option<&str>,
+    steps: &HashMap<String, crate::config::StepResult>,
+    failures: &mut Vec<String>,
+) {
+    let expected_success = expect.success.unwrap_or(expect.error.is_none());
+    if success != expected_success {
+        failures.push(match error {
+            Some(error) if !success => format!("workflow failed: {}", error),
+            _ if success => "workflow succeeded, expected it to fail".to_string(),
+            _ => "workflow failed".to_string(),
+        });
+    }
+    if let Some(ref expected) = expect.error
+        && !error.is_some_and(|error| error.contains(expected.as_str()))
+    {
+        failures.push(format!(
+            "expected error containing {:?}, got {:?}",
+            expected,
+            error.unwrap_or("no error")
+        ));
+    }
+
+    let skipped = |result: &crate::config::StepResult| {
+        result
+            .error
+            .as_deref()
+            .is_some_and(|error| error.starts_with("skipped:"))
+    };
+    for name in &expect.skipped {
+        match steps.get(name) {
+            Some(result) if skipped(result) => {}
+            Some(_) => failures.push(format!("step '{}' ran, expected it to be skipped", name)),
+            None => failures.push(format!("step '{}' did not run", name)),
+        }
+    }
+    for name in &expect.failed {
+        match steps.get(name) {
+            Some(result) if result.failed => {}
+            Some(_) => failures.push(format!("step '{}' succeeded, expected it to fail", name)),
+            None => failures.push(format!("step '{}' did not run", name)),
+        }
+    }
+
+    let mut names: Vec<&String> = expect.steps.keys().collect();
+    names.sort();
+    for name in names {
+        let Some(result) = steps.get(name) else {
+            failures.push(format!("step '{}' did not run", name));
+            continue;
+        };
+        expect.steps[name].check(
+            &format!("step '{}' output", name),
+            result.output.as_deref(),
+            failures,
+        );
+    }
+}
+
+/// Temporary working tree, removed when dropped
+struct Fixture {
+    dir: PathBuf,
+}
+
+impl Fixture {
+    fn create(workflow: &str, files: &HashMap<String, String>) -> std::io::Result<Self> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir()
+            .join("llm-mux")
+            .join("tests")
+            .join(format!(
+                "{}-{}-{}",
+                workflow,
+                std::process::id(),
+                NEXT.fetch_add(1, Ordering::Relaxed)
+            ));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        std::fs::create_dir_all(&dir)?;
+        let fixture = Self { dir };
+
+        for (path, content) in files {
+            let relative = Path::new(path);
+            if relative.is_absolute()
+                || relative
+                    .components()
+                    .any(|c| matches!(c, std::path::Component::ParentDir))
+            {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("fixture path '{}' must stay inside the working tree", path),
+                ));
+            }
+            let target = fixture.dir.join(relative);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(target, content)?;
+        }
+        Ok(fixture)
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RoleConfig;
+    use tempfile::TempDir;
+
+    const WORKFLOW: &str = r#"
+name = "fix"
+
+[[steps]]
+name = "fetch"
+type = "shell"
+run = "echo real"
+
+[[steps]]
+name = "analyze"
+type = "query"
+role = "analyzer"
+prompt = "Fix: {{ steps.fetch.output }}"
+depends_on = ["fetch"]
+
+[[steps]]
+name = "apply"
+type = "apply"
+source = "analyze"
+depends_on = ["analyze"]
+
+[[steps]]
+name = "notify"
+type = "shell"
+run = "echo done"
+if = "args.notify == 'yes'"
+depends_on = ["apply"]
+"#;
+
+    const TESTS: &str = r#"
+[[test]]
+name = "applies the edit"
+args = { notify = "no" }
+files = { "src/lib.rs" = "fn add() {}\n" }
+
+[test.mock.fetch]
+output = "add is empty"
+
+[test.mock.analyze]
+output = '[{"path": "src/lib.rs", "old": "fn add() {}", "new": "fn add() -> i32 { 1 }"}]'
+
+[test.expect]
+skipped = ["notify"]
+steps.fetch = { equals = "add is empty" }
+files."src/lib.rs" = { contains = "-> i32" }
+
+[[test]]
+name = "wrong expectations"
+args = { notify = "yes" }
+
+[test.mock.fetch]
+exit_code = 1
+error = "gh: not found"
+
+[test.expect]
+steps.notify = { contains = "done" }
+"#;
+
+    fn config() -> Arc<LlmuxConfig> {
+        let mut config = LlmuxConfig::default();
+        config.roles.insert(
+            "analyzer".into(),
+            RoleConfig {
+                backends: vec!["claude".into()],
+                ..Default::default()
+            },
+        );
+        config
+            .backends
+            .insert("claude".into(), crate::config::BackendConfig::default());
+        Arc::new(config)
+    }
+
+    #[tokio::test]
+    async fn test_run_test_file() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("fix.toml"), WORKFLOW).unwrap();
+        std::fs::write(dir.path().join("fix.test.toml"), TESTS).unwrap();
+
+        assert_eq!(
+            discover_tests(dir.path()),
+            vec![dir.path().join("fix.test.toml")]
+        );
+
+        let outcomes = run_test_file(&dir.path().join("fix.test.toml"), config(), None)
+            .await
+            .unwrap();
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes[0].passed(), "{:?}", outcomes[0].failures);
+        assert_eq!(outcomes[0].file, "fix.test.toml");
+
+        let failures = &outcomes[1].failures;
+        assert!(failures[0].contains("gh: not found"), "{:?}", failures);
+        assert!(failures.iter().any(|f| f.contains("'notify' did not run")));
+
+        let filtered = run_test_file(&dir.path().join("fix.test.toml"), config(), Some("wrong"))
+            .await
+            .unwrap();
+        assert_eq!(filtered.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unmocked_query_step_fails() {
+        let workflow: WorkflowConfig = toml::from_str(WORKFLOW).unwrap();
+        let test = WorkflowTest {
+            name: "no mocks".into(),
+            expect: Expectations {
+                error: Some("no mock response for query step 'analyze'".into()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let outcome = run_test(&workflow, &test, config(), None).await;
+        assert!(outcome.passed(), "{:?}", outcome.failures);
+    }
+
+    #[tokio::test]
+    async fn test_mocks_run_synthesis_and_votes() {
+        let mut config = (*config()).clone();
+        for backend in ["codex", "judge"] {
+            config
+                .backends
+                .insert(backend.into(), crate::config::BackendConfig::default());
+        }
+        config.roles.insert(
+            "panel".into(),
+            RoleConfig {
+                backends: vec!["claude".into(), "codex".into()],
+                execution: crate::config::RoleExecution::Parallel,
+                judge: Some("merger".into()),
+                ..Default::default()
+            },
+        );
+        config.roles.insert(
+            "merger".into(),
+            RoleConfig {
+                backends: vec!["judge".into()],
+                ..Default::default()
+            },
+        );
+        config.roles.insert(
+            "jury".into(),
+            RoleConfig {
+                backends: vec!["claude".into(), "codex".into(), "judge".into()],
+                execution: crate::config::RoleExecution::Vote,
+                ..Default::default()
+            },
+        );
+
+        let workflow: WorkflowConfig = toml::from_str(
+            r#"
+name = "review"
+
+[[steps]]
+name = "review"
+type = "query"
+role = "panel"
+prompt = "Review this"
+
+[[steps]]
+name = "verdict"
+type = "query"
+role = "jury"
+prompt = "Approve or reject?"
+"#,
+        )
+        .unwrap();
+        let test: WorkflowTest = toml::from_str(
+            r#"
+name = "merges reviews and tallies votes"
+
+[mock.review]
+outputs = { claude = "looks fine", codex = "missing test" }
+output = "merged: add a test"
+
+[mock.verdict]
+outputs = { claude = "approve", codex = "approve", judge = "reject" }
+
+[expect]
+steps.review = { equals = "merged: add a test" }
+steps.verdict = { equals = "approve" }
+"#,
+        )
+        .unwrap();
+
+        let outcome = run_test(&workflow, &test, Arc::new(config), None).await;
+        assert!(outcome.passed(), "{:?}", outcome.failures);
+    }
+
+    #[test]
+    fn test_expect() {
+        let expect = Expect {
+            contains: Some("ok".into()),
+            matches: Some("^o".into()),
+            ..Default::default()
+        };
+        let mut failures = Vec::new();
+        expect.check("x", Some("ok"), &mut failures);
+        assert!(failures.is_empty());
+        expect.check("x", Some("nope"), &mut failures);
+        assert_eq!(failures.len(), 2);
+
+        let absent = Expect {
+            exists: Some(false),
+            ..Default::default()
+        };
+        let mut failures = Vec::new();
+        absent.check("file 'a'", None, &mut failures);
+        assert!(failures.is_empty());
+        absent.check("file 'a'", Some(""), &mut failures);
+        assert_eq!(failures, vec!["file 'a' should not exist"]);
+    }
+}